@@ -1,10 +1,8 @@
-#![allow(unreachable_code)]
+#![allow(unreachable_code, dead_code)]
 use embedded_graphics::{pixelcolor::Rgb565, prelude::*, primitives::PrimitiveStyle};
-use rppal::{
-    gpio::{self, Gpio, Level, Trigger},
-    hal::Delay,
-};
-use st7789::{Orientation, TearingEffect, ST7789};
+use linux_embedded_hal::Delay;
+use rppal::gpio::{self, Gpio, Level, Trigger};
+use st7789::{eh02, Orientation, SpiInterface, TearingEffect, ST7789};
 use std::{error::Error, thread, time::Duration};
 mod utils;
 use std::sync::{Arc, Mutex};
@@ -23,7 +21,6 @@ fn main() -> Result<(), Box<dyn Error>> {
                 let mut flag = (*pressed).lock().unwrap();
                 *flag = !(*flag);
             }
-            ()
         })?;
     }
     let key2_pressed = Arc::new(Mutex::new(false));
@@ -37,7 +34,6 @@ fn main() -> Result<(), Box<dyn Error>> {
                 let mut flag = (*pressed).lock().unwrap();
                 *flag = !(*flag);
             }
-            ()
         })?;
     }
 
@@ -49,16 +45,15 @@ fn main() -> Result<(), Box<dyn Error>> {
         Pins::DC as u8,  // DC
     ])?;
     let mut display = ST7789::new(
-        spi,
+        SpiInterface::new(spi, dc),
         Some(rst),
         Some(bl),
-        Some(dc),
         size_x as u16,
         size_y as u16,
     );
 
     // initialize
-    let mut delay = Delay::new();
+    let mut delay = eh02::Delay(Delay);
     display.init(&mut delay).unwrap();
     // set default orientation
     display.set_orientation(Orientation::Landscape).unwrap();
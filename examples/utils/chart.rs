@@ -1,7 +1,7 @@
 use embedded_graphics::{
     geometry::Point,
     mono_font::{ascii::FONT_6X10, MonoTextStyle},
-    pixelcolor::{BinaryColor, Rgb565},
+    pixelcolor::Rgb565,
     prelude::*,
     primitives::{Line, Polyline, PrimitiveStyle, Rectangle},
     text::Text,
@@ -9,8 +9,6 @@ use embedded_graphics::{
 use std::{
     fs::File,
     io::{self, BufRead, BufReader},
-    thread,
-    time::Duration,
 };
 const LCD_SIZE_X: i32 = 240i32;
 const LCD_SIZE_Y: i32 = 240i32;
@@ -93,15 +91,14 @@ impl LineChart {
     pub fn new(style: PrimitiveStyle<Rgb565>) -> Self {
         let axis = Axis::new();
         // println!("{:?}", axis);
-        let chart = Self {
+        Self {
             prev_point: None,
             count: 0,
             style,
             axis,
             // 5hz -> 200ms per data
             freq: 200,
-        };
-        chart
+        }
     }
     pub fn draw_data<D>(&mut self, y: f32, target: &mut D) -> Result<(), D::Error>
     where
@@ -220,7 +217,7 @@ impl LineChart {
         .draw(target)?;
         Ok(())
     }
-    pub fn clear_axis<D>(&mut self, y: i32, target: &mut D) -> Result<(), D::Error>
+    pub fn clear_axis<D>(&mut self, _y: i32, _target: &mut D) -> Result<(), D::Error>
     where
         D: DrawTarget<Color = Rgb565>,
     {
@@ -1,4 +1,4 @@
-#[allow(dead_code)]
+#[allow(dead_code, clippy::upper_case_acronyms)]
 #[repr(u8)]
 pub enum Pins {
     KEY1 = 21u8,  // Key1
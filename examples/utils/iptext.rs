@@ -3,7 +3,7 @@ use embedded_graphics::{
     mono_font::{ascii::FONT_10X20, MonoTextStyle},
     pixelcolor::Rgb565,
     prelude::*,
-    primitives::{PrimitiveStyle, Rectangle},
+    primitives::PrimitiveStyle,
     text::Text,
 };
 use local_ip_address::local_ip;
@@ -1,10 +1,12 @@
 use embedded_graphics::primitives::Rectangle;
+use embedded_hal::digital::{ErrorType, OutputPin};
 use linux_embedded_hal::{
     spidev::{SpiModeFlags, SpidevOptions},
     Spidev,
 };
 use rppal::gpio::{self, Gpio};
-use std::{boxed::Box, error::Error, io};
+use st7789::eh02;
+use std::{boxed::Box, convert::Infallible, error::Error, io};
 pub mod chart;
 mod enums;
 pub mod iptext;
@@ -13,7 +15,23 @@ pub struct BoundingBoxes {
     pub ip: Option<Rectangle>,
     pub chart: Option<Rectangle>,
 }
-pub fn create_spi() -> io::Result<Spidev> {
+/// GPIO output pin, rppal only implements the embedded-hal 0.2 alpha traits
+pub struct Pin(gpio::OutputPin);
+impl ErrorType for Pin {
+    type Error = Infallible;
+}
+impl OutputPin for Pin {
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.0.set_low();
+        Ok(())
+    }
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.0.set_high();
+        Ok(())
+    }
+}
+/// SPI bus, the kernel driver handles CS
+pub fn create_spi() -> io::Result<eh02::Spi<Spidev>> {
     let mut spi = Spidev::open("/dev/spidev0.0")?;
     let options = SpidevOptions::new()
         .bits_per_word(8)
@@ -22,13 +40,13 @@ pub fn create_spi() -> io::Result<Spidev> {
         .mode(SpiModeFlags::SPI_MODE_0)
         .build();
     spi.configure(&options)?;
-    Ok(spi)
+    Ok(eh02::Spi::new(spi))
 }
-pub fn create_outputpin(pins: [u8; 3]) -> Result<[gpio::OutputPin; 3], Box<dyn Error + 'static>> {
+pub fn create_outputpin(pins: [u8; 3]) -> Result<[Pin; 3], Box<dyn Error + 'static>> {
     let [rst, bl, dc] = pins;
     Ok([
-        Gpio::new()?.get(rst)?.into_output(),
-        Gpio::new()?.get(bl)?.into_output(),
-        Gpio::new()?.get(dc)?.into_output(),
+        Pin(Gpio::new()?.get(rst)?.into_output()),
+        Pin(Gpio::new()?.get(bl)?.into_output()),
+        Pin(Gpio::new()?.get(dc)?.into_output()),
     ])
 }
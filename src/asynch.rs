@@ -0,0 +1,702 @@
+//! Async driver on embedded-hal-async.
+//!
+//! [`ST7789`] here mirrors the blocking [`crate::ST7789`] with `async` methods, talking to
+//! the display through an async [`DisplayInterface`] and waiting on an
+//! [`embedded_hal_async::delay::DelayNs`]. [`SpiInterface`] and [`ThreeWireInterface`]
+//! implement it over an [`embedded_hal_async::spi::SpiDevice`], so executors like embassy
+//! can run other tasks while large pixel transfers go out by DMA:
+//!
+//! ```
+//! # use st7789::testing::{MockBus, PinRole};
+//! # embassy_futures::block_on(async {
+//! # let bus = MockBus::new();
+//! # let (spi, dc, rst, mut delay) = (bus.spi(), bus.pin(PinRole::Dc), bus.pin(PinRole::Reset), bus.delay());
+//! use st7789::{asynch::ST7789, SpiInterface};
+//!
+//! let mut display = ST7789::new(SpiInterface::new(spi, dc), Some(rst), None, 240, 240);
+//! display.init(&mut delay).await.unwrap();
+//! display
+//!     .set_pixels(0, 0, 9, 9, core::iter::repeat(0xF800).take(100))
+//!     .await
+//!     .unwrap();
+//! # });
+//! ```
+//!
+//! Both drivers share the command encoding, the pixel format conversion and the state
+//! tracking, so a display behaves the same whichever one drives it. Register readback,
+//! gamma, brightness and frame rate control and embedded-graphics drawing are only
+//! available on the blocking driver.
+
+use core::iter::once;
+
+use embedded_hal::digital::OutputPin;
+use embedded_hal_async::{delay::DelayNs, spi::SpiDevice};
+
+use crate::bus::BusChunks;
+use crate::color::{self, PixelBytes};
+use crate::interface::DataBytes;
+use crate::power::{SLEEP_IN_DELAY_US, SLEEP_OUT_DELAY_US};
+use crate::state::DisplayState;
+use crate::{
+    BacklightState, DataFormat, DisplayMode, Error, InitSequence, InitStep, Instruction,
+    MemoryAccessControl, Orientation, PanelConfig, PixelFormat, PowerState, ScrollRegion, SpiError,
+    SpiInterface, TearingEffect, ThreeWireInterface,
+};
+
+///
+/// Async counterpart of [`crate::DisplayInterface`], the bus the async driver writes
+/// commands and data through.
+///
+/// [`SpiInterface`] and [`ThreeWireInterface`] implement it over an embedded-hal-async
+/// [`SpiDevice`], grouping the bus transactions the same way as for the blocking driver.
+/// The parallel interface only toggles GPIOs and has nothing to wait on, it is blocking
+/// only.
+///
+#[allow(async_fn_in_trait)]
+pub trait DisplayInterface {
+    ///
+    /// Writes a command byte
+    ///
+    async fn write_command(&mut self, command: u8) -> Result<(), SpiError>;
+
+    ///
+    /// Writes data following the last command
+    ///
+    async fn write_data(&mut self, data: DataFormat<'_>) -> Result<(), SpiError>;
+
+    ///
+    /// Writes a command byte followed by its parameters, see
+    /// [`crate::DisplayInterface::send_command`]
+    ///
+    async fn send_command(&mut self, command: u8, params: &[u8]) -> Result<(), SpiError> {
+        self.write_command(command).await?;
+        if params.is_empty() {
+            return Ok(());
+        }
+        self.write_data(DataFormat::U8(params)).await
+    }
+}
+
+// Writes every chunk in a transaction of its own
+async fn write_chunks<SPI: SpiDevice>(
+    spi: &mut SPI,
+    mut chunks: BusChunks<impl Iterator<Item = u8>>,
+) -> Result<(), SpiError> {
+    while let Some(chunk) = chunks.next_chunk() {
+        spi.write(chunk)
+            .await
+            .map_err(|_| SpiError::BusWriteError)?;
+    }
+    Ok(())
+}
+
+impl<SPI, DC> DisplayInterface for SpiInterface<SPI, DC>
+where
+    SPI: SpiDevice,
+    DC: OutputPin,
+{
+    async fn write_command(&mut self, command: u8) -> Result<(), SpiError> {
+        // 1 = data, 0 = command
+        self.dc.set_low().map_err(|_| SpiError::DCError)?;
+        self.spi
+            .write(&[command])
+            .await
+            .map_err(|_| SpiError::BusWriteError)
+    }
+
+    async fn write_data(&mut self, data: DataFormat<'_>) -> Result<(), SpiError> {
+        // 1 = data, 0 = command
+        self.dc.set_high().map_err(|_| SpiError::DCError)?;
+        match data {
+            // the slice goes out as it is
+            DataFormat::U8(slice) => self
+                .spi
+                .write(slice)
+                .await
+                .map_err(|_| SpiError::BusWriteError),
+            data => write_chunks(&mut self.spi, BusChunks::new(DataBytes::new(data)?)).await,
+        }
+    }
+}
+
+impl<SPI> DisplayInterface for ThreeWireInterface<SPI>
+where
+    SPI: SpiDevice,
+{
+    async fn write_command(&mut self, command: u8) -> Result<(), SpiError> {
+        let chunks = BusChunks::nine_bit_command(command, core::iter::empty());
+        write_chunks(&mut self.spi, chunks).await
+    }
+
+    async fn write_data(&mut self, data: DataFormat<'_>) -> Result<(), SpiError> {
+        let chunks = BusChunks::nine_bit(true, DataBytes::new(data)?);
+        write_chunks(&mut self.spi, chunks).await
+    }
+
+    async fn send_command(&mut self, command: u8, params: &[u8]) -> Result<(), SpiError> {
+        let chunks = BusChunks::nine_bit_command(command, params.iter().copied());
+        write_chunks(&mut self.spi, chunks).await
+    }
+}
+
+///
+/// Async ST7789 driver, see the [module docs](self).
+///
+pub struct ST7789<DI, OUT>
+where
+    DI: DisplayInterface,
+    OUT: OutputPin,
+{
+    // Display interface
+    di: DI,
+    // Reset pin.
+    rst: Option<OUT>,
+    // Backlight pin,
+    bl: Option<OUT>,
+    // Panel geometry and the state the commands sent left the display in
+    state: DisplayState,
+}
+
+impl<DI, OUT, PinE> ST7789<DI, OUT>
+where
+    DI: DisplayInterface,
+    OUT: OutputPin<Error = PinE>,
+{
+    ///
+    /// Creates a new async ST7789 driver instance
+    ///
+    /// # Arguments
+    ///
+    /// * `di` - display interface the display is connected through
+    /// * `rst` - display hard reset pin
+    /// * `bl` - backlight pin
+    /// * `size_x` - x axis resolution of the display in pixels
+    /// * `size_y` - y axis resolution of the display in pixels
+    ///
+    pub fn new(di: DI, rst: Option<OUT>, bl: Option<OUT>, size_x: u16, size_y: u16) -> Self {
+        Self::with_panel(di, rst, bl, PanelConfig::new(size_x, size_y))
+    }
+
+    ///
+    /// Creates a new async ST7789 driver instance for a panel with known geometry
+    ///
+    /// # Arguments
+    ///
+    /// * `di` - display interface the display is connected through
+    /// * `rst` - display hard reset pin
+    /// * `bl` - backlight pin
+    /// * `panel` - panel geometry, either a [`crate::DisplayModel`] or a custom [`PanelConfig`]
+    ///
+    pub fn with_panel(
+        di: DI,
+        rst: Option<OUT>,
+        bl: Option<OUT>,
+        panel: impl Into<PanelConfig>,
+    ) -> Self {
+        Self {
+            di,
+            rst,
+            bl,
+            state: DisplayState::new(panel.into()),
+        }
+    }
+
+    ///
+    /// Runs commands to initialize the display
+    ///
+    /// # Arguments
+    ///
+    /// * `delay_source` - mutable reference to a delay provider
+    ///
+    pub async fn init(&mut self, delay_source: &mut impl DelayNs) -> Result<(), Error<PinE>> {
+        self.init_with(&InitSequence::DEFAULT, delay_source).await
+    }
+
+    ///
+    /// Runs commands to initialize the display using a custom initialization sequence
+    ///
+    /// # Arguments
+    ///
+    /// * `sequence` - commands, parameters and delays to send after the hard reset
+    /// * `delay_source` - mutable reference to a delay provider
+    ///
+    pub async fn init_with(
+        &mut self,
+        sequence: &InitSequence<'_>,
+        delay_source: &mut impl DelayNs,
+    ) -> Result<(), Error<PinE>> {
+        self.hard_reset(delay_source).await?;
+        if let Some(bl) = self.bl.as_mut() {
+            bl.set_low().map_err(Error::Pin)?;
+            delay_source.delay_us(10_000).await;
+            bl.set_high().map_err(Error::Pin)?;
+        }
+
+        for step in sequence.steps() {
+            match *step {
+                InitStep::Command(instruction, params) => {
+                    // keep track of the state the sequence leaves the display in
+                    self.command(instruction, params).await?;
+                }
+                InitStep::Raw(command, params) => {
+                    // whatever it does, the address window may not be what we think it is
+                    self.state.forget_window();
+                    self.di
+                        .send_command(command, params)
+                        .await
+                        .map_err(|_| Error::DisplayError)?;
+                }
+                InitStep::Delay(us) => delay_source.delay_us(us).await,
+            }
+        }
+        Ok(())
+    }
+
+    ///
+    /// Performs a hard reset using the RST pin sequence.
+    /// The tracked state returns to the register defaults, with the display asleep.
+    ///
+    /// # Arguments
+    ///
+    /// * `delay_source` - mutable reference to a delay provider
+    ///
+    pub async fn hard_reset(&mut self, delay_source: &mut impl DelayNs) -> Result<(), Error<PinE>> {
+        if let Some(rst) = self.rst.as_mut() {
+            rst.set_high().map_err(Error::Pin)?;
+            delay_source.delay_us(10).await; // ensure the pin change will get registered
+            rst.set_low().map_err(Error::Pin)?;
+            delay_source.delay_us(10).await; // ensure the pin change will get registered
+            rst.set_high().map_err(Error::Pin)?;
+            delay_source.delay_us(10).await; // ensure the pin change will get registered
+            self.state.reset();
+        }
+
+        Ok(())
+    }
+
+    ///
+    /// Switches the backlight pin on or off
+    ///
+    /// # Arguments
+    ///
+    /// * `state` - backlight state
+    /// * `delay_source` - mutable reference to a delay provider
+    ///
+    pub async fn set_backlight(
+        &mut self,
+        state: BacklightState,
+        delay_source: &mut impl DelayNs,
+    ) -> Result<(), Error<PinE>> {
+        if let Some(bl) = self.bl.as_mut() {
+            match state {
+                BacklightState::On => bl.set_high().map_err(Error::Pin)?,
+                BacklightState::Off => bl.set_low().map_err(Error::Pin)?,
+            }
+            delay_source.delay_us(10).await; // ensure the pin change will get registered
+        }
+        Ok(())
+    }
+
+    ///
+    /// Returns the panel geometry
+    ///
+    pub fn panel(&self) -> PanelConfig {
+        self.state.panel
+    }
+
+    ///
+    /// Returns the visible size in pixels for the current orientation
+    ///
+    pub fn size(&self) -> (u16, u16) {
+        self.state.panel.size(self.state.madctl)
+    }
+
+    ///
+    /// Returns currently set orientation
+    ///
+    pub fn orientation(&self) -> Orientation {
+        self.state.madctl.orientation()
+    }
+
+    ///
+    /// Returns currently set memory access control value
+    ///
+    pub fn memory_access_control(&self) -> MemoryAccessControl {
+        self.state.madctl
+    }
+
+    ///
+    /// Sets display orientation
+    ///
+    /// # Arguments
+    ///
+    /// * `madctl` - an [`Orientation`] or a [`MemoryAccessControl`] value for mirroring and BGR panels
+    ///
+    pub async fn set_orientation(
+        &mut self,
+        madctl: impl Into<MemoryAccessControl>,
+    ) -> Result<(), Error<PinE>> {
+        let madctl = madctl.into();
+        self.send_command(Instruction::MADCTL, &[madctl.bits()])
+            .await?;
+        self.state.apply_madctl(madctl);
+        Ok(())
+    }
+
+    ///
+    /// Returns the current display mode
+    ///
+    pub fn display_mode(&self) -> DisplayMode {
+        self.state.mode
+    }
+
+    ///
+    /// Returns the interface pixel format pixel data is sent in
+    ///
+    pub fn pixel_format(&self) -> PixelFormat {
+        self.state.pixel_format
+    }
+
+    ///
+    /// Sets the interface pixel format, colors are converted to it when drawing
+    ///
+    /// # Arguments
+    ///
+    /// * `format` - pixel format to use for the RGB and the control interface
+    ///
+    pub async fn set_pixel_format(&mut self, format: PixelFormat) -> Result<(), Error<PinE>> {
+        self.send_command(Instruction::COLMOD, &[format.colmod()])
+            .await?;
+        self.state.pixel_format = format;
+        Ok(())
+    }
+
+    ///
+    /// Sets the lines shown in partial mode, see [`crate::ST7789::set_partial_area`]
+    ///
+    /// # Arguments
+    ///
+    /// * `start_row` - first line of the partial area
+    /// * `end_row` - last line of the partial area, inclusive
+    ///
+    pub async fn set_partial_area(
+        &mut self,
+        start_row: u16,
+        end_row: u16,
+    ) -> Result<(), Error<PinE>> {
+        let params = self
+            .state
+            .partial_area(start_row, end_row)
+            .ok_or(Error::InvalidArgument)?;
+
+        self.send_command(Instruction::PTLAR, &params).await
+    }
+
+    ///
+    /// Turns on partial mode, only the lines set by [`Self::set_partial_area`] are shown
+    ///
+    pub async fn enter_partial_mode(&mut self) -> Result<(), Error<PinE>> {
+        self.command(Instruction::PTLON, &[]).await
+    }
+
+    ///
+    /// Turns off partial mode, the whole panel is shown again
+    ///
+    pub async fn enter_normal_mode(&mut self) -> Result<(), Error<PinE>> {
+        self.command(Instruction::NORON, &[]).await
+    }
+
+    ///
+    /// Returns the power state the driver put the display in
+    ///
+    pub fn power_state(&self) -> PowerState {
+        self.state.power
+    }
+
+    ///
+    /// Puts the display to sleep, see [`crate::ST7789::sleep`]
+    ///
+    /// # Arguments
+    ///
+    /// * `delay_source` - mutable reference to a delay provider
+    ///
+    pub async fn sleep(&mut self, delay_source: &mut impl DelayNs) -> Result<(), Error<PinE>> {
+        if self.state.power.sleeping {
+            return Err(Error::InvalidState);
+        }
+        self.command(Instruction::SLPIN, &[]).await?;
+        delay_source.delay_us(SLEEP_IN_DELAY_US).await;
+        Ok(())
+    }
+
+    ///
+    /// Wakes the display up from sleep, waiting 120 ms for it to become ready
+    ///
+    /// # Arguments
+    ///
+    /// * `delay_source` - mutable reference to a delay provider
+    ///
+    pub async fn wake(&mut self, delay_source: &mut impl DelayNs) -> Result<(), Error<PinE>> {
+        if !self.state.power.sleeping {
+            return Err(Error::InvalidState);
+        }
+        self.command(Instruction::SLPOUT, &[]).await?;
+        delay_source.delay_us(SLEEP_OUT_DELAY_US).await;
+        Ok(())
+    }
+
+    ///
+    /// Turns the display output on, showing the frame memory
+    ///
+    pub async fn display_on(&mut self) -> Result<(), Error<PinE>> {
+        self.command(Instruction::DISPON, &[]).await
+    }
+
+    ///
+    /// Turns the display output off, showing a blank screen
+    ///
+    pub async fn display_off(&mut self) -> Result<(), Error<PinE>> {
+        self.command(Instruction::DISPOFF, &[]).await
+    }
+
+    ///
+    /// Turns idle mode on or off, see [`crate::ST7789::idle_mode`]
+    ///
+    /// # Arguments
+    ///
+    /// * `idle` - true to turn idle mode on
+    ///
+    pub async fn idle_mode(&mut self, idle: bool) -> Result<(), Error<PinE>> {
+        let instruction = if idle {
+            Instruction::IDMON
+        } else {
+            Instruction::IDMOFF
+        };
+        self.command(instruction, &[]).await
+    }
+
+    ///
+    /// Configures the tearing effect output.
+    ///
+    pub async fn set_tearing_effect(
+        &mut self,
+        tearing_effect: TearingEffect,
+    ) -> Result<(), Error<PinE>> {
+        let (instruction, params) = tearing_effect.command();
+        self.command(instruction, params).await
+    }
+
+    ///
+    /// Blits raw pixel data to the display, see [`crate::ST7789::blit_pixels`].
+    /// With a DC pin the data goes out in a single SPI write, letting the bus hand it to
+    /// DMA in one piece. An empty area sends nothing.
+    ///
+    /// # Arguments
+    ///
+    /// * `sx` - x coordinate start
+    /// * `sy` - y coordinate start
+    /// * `dx` - width
+    /// * `dy` - height
+    /// * `data` - u8 slice containing raw pixel data in the active [`PixelFormat`]
+    ///
+    pub async fn blit_pixels(
+        &mut self,
+        sx: u16,
+        sy: u16,
+        dx: u16,
+        dy: u16,
+        data: &[u8],
+    ) -> Result<(), Error<PinE>> {
+        let Some((ex, ey)) = self.state.blit_area(sx, sy, dx, dy, data.len())? else {
+            return Ok(());
+        };
+        self.set_address_window(sx, sy, ex, ey).await?;
+        self.write_command(Instruction::RAMWR).await?;
+        self.di
+            .write_data(DataFormat::U8(data))
+            .await
+            .map_err(|_| Error::DisplayError)
+    }
+
+    ///
+    /// Sets a pixel color at the given coords.
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - x coordinate
+    /// * `y` - y coordinate
+    /// * `color` - the Rgb565 color value
+    ///
+    pub async fn set_pixel(&mut self, x: u16, y: u16, color: u16) -> Result<(), Error<PinE>> {
+        self.set_pixels(x, y, x, y, once(color)).await
+    }
+
+    ///
+    /// Sets pixel colors in given rectangle bounds.
+    /// The RGB565 colors are converted to the active [`PixelFormat`].
+    ///
+    /// # Arguments
+    ///
+    /// * `sx` - x coordinate start
+    /// * `sy` - y coordinate start
+    /// * `ex` - x coordinate end
+    /// * `ey` - y coordinate end
+    /// * `colors` - anything that can provide `IntoIterator<Item = u16>` to iterate over pixel data
+    ///
+    pub async fn set_pixels<T>(
+        &mut self,
+        sx: u16,
+        sy: u16,
+        ex: u16,
+        ey: u16,
+        colors: T,
+    ) -> Result<(), Error<PinE>>
+    where
+        T: IntoIterator<Item = u16>,
+    {
+        self.set_address_window(sx, sy, ex, ey).await?;
+        self.write_command(Instruction::RAMWR).await?;
+        self.write_pixels(colors.into_iter()).await
+    }
+
+    ///
+    /// Continues the last frame memory write with more pixel colors, using RAMWRC.
+    /// The colors go into the address window of the preceding write, e.g. [`Self::set_pixels`],
+    /// following the last pixel written, so a window can be filled across several calls.
+    /// The RGB565 colors are converted to the active [`PixelFormat`].
+    ///
+    /// # Arguments
+    ///
+    /// * `colors` - anything that can provide `IntoIterator<Item = u16>` to iterate over pixel data
+    ///
+    pub async fn continue_pixels<T>(&mut self, colors: T) -> Result<(), Error<PinE>>
+    where
+        T: IntoIterator<Item = u16>,
+    {
+        self.write_command(Instruction::RAMWRC).await?;
+        self.write_pixels(colors.into_iter()).await
+    }
+
+    ///
+    /// Sets scroll offset "shifting" the displayed picture
+    /// # Arguments
+    ///
+    /// * `offset` - scroll offset in pixels
+    ///
+    pub async fn set_scroll_offset(&mut self, offset: u16) -> Result<(), Error<PinE>> {
+        self.command(Instruction::VSCAD, &offset.to_be_bytes())
+            .await
+    }
+
+    ///
+    /// Defines the vertical scrolling area between fixed top and bottom areas, see
+    /// [`crate::ST7789::define_scroll_area`]
+    ///
+    /// # Arguments
+    ///
+    /// * `top_fixed` - number of frame memory rows in the fixed top area
+    /// * `scroll_height` - number of frame memory rows in the scrolling area
+    /// * `bottom_fixed` - number of frame memory rows in the fixed bottom area, the three have to add up to 320
+    ///
+    pub async fn define_scroll_area(
+        &mut self,
+        top_fixed: u16,
+        scroll_height: u16,
+        bottom_fixed: u16,
+    ) -> Result<ScrollRegion, Error<PinE>> {
+        let region = ScrollRegion::new(top_fixed, scroll_height, bottom_fixed)
+            .ok_or(Error::InvalidArgument)?;
+
+        self.command(Instruction::VSCRDER, &region.vscrder())
+            .await?;
+        Ok(region)
+    }
+
+    ///
+    /// Scrolls the display to the current offset of a scroll region
+    ///
+    /// # Arguments
+    ///
+    /// * `region` - region returned by [`Self::define_scroll_area`]
+    ///
+    pub async fn scroll(&mut self, region: &ScrollRegion) -> Result<(), Error<PinE>> {
+        self.set_scroll_offset(region.start_address()).await
+    }
+
+    ///
+    /// Release resources allocated to this driver back.
+    /// This returns the display interface, the RST and the backlight pin deconstructing
+    /// the driver.
+    ///
+    pub fn release(self) -> (DI, Option<OUT>, Option<OUT>) {
+        (self.di, self.rst, self.bl)
+    }
+
+    // Sends a command with its parameters and tracks the state it leaves the display in
+    async fn command(
+        &mut self,
+        instruction: Instruction,
+        params: &[u8],
+    ) -> Result<(), Error<PinE>> {
+        self.send_command(instruction, params).await?;
+        self.state.track_command(instruction, params);
+        Ok(())
+    }
+
+    // Sends RGB565 colors in the active pixel format
+    async fn write_pixels(
+        &mut self,
+        mut colors: impl Iterator<Item = u16>,
+    ) -> Result<(), Error<PinE>> {
+        let result = match self.state.pixel_format {
+            PixelFormat::Rgb565 => self.di.write_data(DataFormat::U16BEIter(&mut colors)).await,
+            format => {
+                let mut bytes = PixelBytes::new(format, colors.map(color::rgb565_to_rgb888));
+                self.di.write_data(DataFormat::U8Iter(&mut bytes)).await
+            }
+        };
+        result.map_err(|_| Error::DisplayError)
+    }
+
+    // Sets the address window for the display.
+    async fn set_address_window(
+        &mut self,
+        sx: u16,
+        sy: u16,
+        ex: u16,
+        ey: u16,
+    ) -> Result<(), Error<PinE>> {
+        for (instruction, params) in self
+            .state
+            .window_commands(sx, sy, ex, ey)
+            .into_iter()
+            .flatten()
+        {
+            if let Err(error) = self.command(instruction, &params).await {
+                // the display may hold either window now
+                self.state.forget_window();
+                return Err(error);
+            }
+        }
+        Ok(())
+    }
+
+    async fn write_command(&mut self, command: Instruction) -> Result<(), Error<PinE>> {
+        self.di
+            .write_command(command as u8)
+            .await
+            .map_err(|_| Error::DisplayError)
+    }
+
+    // Sends a command with its parameters
+    async fn send_command(
+        &mut self,
+        command: Instruction,
+        params: &[u8],
+    ) -> Result<(), Error<PinE>> {
+        self.di
+            .send_command(command as u8, params)
+            .await
+            .map_err(|_| Error::DisplayError)
+    }
+}
@@ -0,0 +1,259 @@
+use core::time::Duration;
+
+use embedded_hal::{digital::OutputPin, pwm::SetDutyCycle};
+
+///
+/// Output driving the backlight LEDs.
+///
+/// Implemented by [`PwmBacklight`] for dimmable backlights and [`GpioBacklight`] for
+/// backlights which can only be switched on and off.
+///
+pub trait BacklightOutput {
+    /// Error returned by the underlying pin
+    type Error;
+
+    ///
+    /// Sets the share of time the backlight is on, from 0 (off) to `u16::MAX` (fully on)
+    ///
+    fn set_duty(&mut self, duty: u16) -> Result<(), Self::Error>;
+}
+
+///
+/// Backlight dimmed by a PWM channel.
+///
+/// The channel has to be enabled already, embedded-hal 0.2 channels can be wrapped in
+/// `eh02::Pwm` which does that.
+///
+pub struct PwmBacklight<P>(P);
+
+impl<P> PwmBacklight<P>
+where
+    P: SetDutyCycle,
+{
+    ///
+    /// Creates the output
+    ///
+    pub fn new(pin: P) -> Self {
+        Self(pin)
+    }
+
+    ///
+    /// Returns the PWM channel
+    ///
+    pub fn release(self) -> P {
+        self.0
+    }
+}
+
+impl<P> BacklightOutput for PwmBacklight<P>
+where
+    P: SetDutyCycle,
+{
+    type Error = P::Error;
+
+    fn set_duty(&mut self, duty: u16) -> Result<(), Self::Error> {
+        self.0.set_duty_cycle_fraction(duty, u16::MAX)
+    }
+}
+
+///
+/// Backlight switched by a GPIO pin, on for any level above zero.
+///
+pub struct GpioBacklight<P>(P);
+
+impl<P> GpioBacklight<P>
+where
+    P: OutputPin,
+{
+    ///
+    /// Creates the output
+    ///
+    pub fn new(pin: P) -> Self {
+        Self(pin)
+    }
+
+    ///
+    /// Returns the pin
+    ///
+    pub fn release(self) -> P {
+        self.0
+    }
+}
+
+impl<P> BacklightOutput for GpioBacklight<P>
+where
+    P: OutputPin,
+{
+    type Error = P::Error;
+
+    fn set_duty(&mut self, duty: u16) -> Result<(), Self::Error> {
+        if duty > 0 {
+            self.0.set_high()
+        } else {
+            self.0.set_low()
+        }
+    }
+}
+
+// Fade in progress
+#[derive(Copy, Clone, Debug)]
+struct Fade {
+    from: u8,
+    to: u8,
+    duration: Duration,
+    elapsed: Duration,
+}
+
+///
+/// Backlight brightness control with gamma correction and fades.
+///
+/// Brightness levels go from 0 (off) to 255 (fully on). With gamma correction the
+/// levels are perceptually even: the duty cycle grows with the square of the level,
+/// so the steps at the dark end are as visible as the ones at the bright end.
+///
+/// Fades run without blocking, [`Backlight::tick`] has to be called regularly with the
+/// time passed since the last call:
+///
+/// ```
+/// use core::time::Duration;
+/// use st7789::testing::MockBus;
+/// use st7789::{Backlight, PwmBacklight};
+///
+/// let bus = MockBus::new();
+/// let mut backlight = Backlight::new(PwmBacklight::new(bus.pwm(1000))).with_gamma(true);
+/// backlight.set_brightness(255).unwrap();
+///
+/// // dim for the night over half a second
+/// backlight.fade_to(32, Duration::from_millis(500));
+/// while backlight.tick(Duration::from_millis(20)).unwrap() {}
+/// assert_eq!(backlight.brightness(), 32);
+/// ```
+///
+/// The display driver's own backlight pin can be left as `None` when the backlight is
+/// controlled this way.
+///
+pub struct Backlight<D> {
+    output: D,
+    level: u8,
+    gamma: bool,
+    fade: Option<Fade>,
+}
+
+impl<D> Backlight<D>
+where
+    D: BacklightOutput,
+{
+    ///
+    /// Creates a backlight control, the output is not changed until a level is set
+    ///
+    /// # Arguments
+    ///
+    /// * `output` - a [`PwmBacklight`] or [`GpioBacklight`]
+    ///
+    pub fn new(output: D) -> Self {
+        Self {
+            output,
+            level: 0,
+            gamma: false,
+            fade: None,
+        }
+    }
+
+    ///
+    /// Turns gamma-corrected perceptual mapping of the levels on or off, off by default
+    ///
+    pub fn with_gamma(mut self, gamma: bool) -> Self {
+        self.gamma = gamma;
+        self
+    }
+
+    ///
+    /// Returns the current brightness level
+    ///
+    pub fn brightness(&self) -> u8 {
+        self.level
+    }
+
+    ///
+    /// Returns true while a fade is in progress
+    ///
+    pub fn is_fading(&self) -> bool {
+        self.fade.is_some()
+    }
+
+    ///
+    /// Sets the brightness level right away, stopping a fade in progress
+    ///
+    /// # Arguments
+    ///
+    /// * `level` - brightness from 0 (off) to 255 (fully on)
+    ///
+    pub fn set_brightness(&mut self, level: u8) -> Result<(), D::Error> {
+        self.fade = None;
+        self.apply(level)
+    }
+
+    ///
+    /// Starts fading to a brightness level, the steps happen in [`Backlight::tick`]
+    ///
+    /// # Arguments
+    ///
+    /// * `level` - brightness to end up at
+    /// * `duration` - how long the fade takes
+    ///
+    pub fn fade_to(&mut self, level: u8, duration: Duration) {
+        self.fade = Some(Fade {
+            from: self.level,
+            to: level,
+            duration,
+            elapsed: Duration::ZERO,
+        });
+    }
+
+    ///
+    /// Advances a fade in progress, returns true until it is finished
+    ///
+    /// # Arguments
+    ///
+    /// * `elapsed` - time passed since the fade started or since the last call
+    ///
+    pub fn tick(&mut self, elapsed: Duration) -> Result<bool, D::Error> {
+        let Some(mut fade) = self.fade else {
+            return Ok(false);
+        };
+
+        fade.elapsed = fade.elapsed.saturating_add(elapsed);
+        let level = if fade.elapsed >= fade.duration {
+            self.fade = None;
+            fade.to
+        } else {
+            self.fade = Some(fade);
+            // elapsed < duration, so the duration is at least 1 ns
+            let span = i128::from(fade.to) - i128::from(fade.from);
+            let step = span * fade.elapsed.as_nanos() as i128 / fade.duration.as_nanos() as i128;
+            (i128::from(fade.from) + step) as u8
+        };
+        if level != self.level {
+            self.apply(level)?;
+        }
+        Ok(self.fade.is_some())
+    }
+
+    ///
+    /// Returns the backlight output
+    ///
+    pub fn release(self) -> D {
+        self.output
+    }
+
+    fn apply(&mut self, level: u8) -> Result<(), D::Error> {
+        let duty = if self.gamma {
+            (u32::from(level) * u32::from(level) * u32::from(u16::MAX) / (255 * 255)) as u16
+        } else {
+            u16::from(level) * 257
+        };
+        self.output.set_duty(duty)?;
+        self.level = level;
+        Ok(())
+    }
+}
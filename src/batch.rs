@@ -1,26 +1,25 @@
 //! Original code from: https://github.com/lupyuen/piet-embedded/blob/master/piet-embedded-graphics/src/batch.rs
 //! Batch the pixels to be rendered into Pixel Rows and Pixel Blocks (contiguous Pixel Rows).
 //! This enables the pixels to be rendered efficiently as Pixel Blocks, which may be transmitted in a single Non-Blocking SPI request.
-use crate::{Error, ST7789};
+use crate::{DisplayInterface, Error, ST7789};
 use embedded_graphics_core::{
     pixelcolor::{raw::RawU16, Rgb565},
     prelude::*,
 };
-use embedded_hal::blocking::spi;
-use embedded_hal::digital::v2::OutputPin;
+use embedded_hal::digital::OutputPin;
 
-pub trait DrawBatch<SPI, OUT, T, PinE>
+pub trait DrawBatch<DI, OUT, T, PinE>
 where
-    SPI: spi::Write<u8>,
+    DI: DisplayInterface,
     OUT: OutputPin<Error = PinE>,
     T: IntoIterator<Item = Pixel<Rgb565>>,
 {
     fn draw_batch(&mut self, item_pixels: T) -> Result<(), Error<PinE>>;
 }
 
-impl<SPI, OUT, T, PinE> DrawBatch<SPI, OUT, T, PinE> for ST7789<SPI, OUT>
+impl<DI, OUT, T, PinE> DrawBatch<DI, OUT, T, PinE> for ST7789<DI, OUT>
 where
-    SPI: spi::Write<u8>,
+    DI: DisplayInterface,
     OUT: OutputPin<Error = PinE>,
     T: IntoIterator<Item = Pixel<Rgb565>>,
 {
@@ -0,0 +1,133 @@
+use embedded_hal::digital::OutputPin;
+
+use crate::{CabcMode, DisplayInterface, Error, Instruction, ST7789};
+
+// WRCTRLD bits
+const BCTRL: u8 = 0b0010_0000;
+const DD: u8 = 0b0000_1000;
+const BL: u8 = 0b0000_0100;
+
+///
+/// CTRL display (WRCTRLD) register value, configuring the controller's brightness
+/// control block which drives the LEDPWM pin.
+///
+/// ```
+/// use st7789::BrightnessControl;
+///
+/// let control = BrightnessControl::new()
+///     .brightness_control(true)
+///     .backlight(true)
+///     .dimming(true);
+/// assert_eq!(control.bits(), 0b0010_1100);
+/// ```
+///
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct BrightnessControl(u8);
+
+impl BrightnessControl {
+    ///
+    /// Creates a new value with all bits cleared, the brightness control block is off
+    ///
+    pub const fn new() -> Self {
+        Self(0)
+    }
+
+    ///
+    /// Creates a new value from the raw register byte
+    ///
+    pub const fn from_bits(bits: u8) -> Self {
+        Self(bits & (BCTRL | DD | BL))
+    }
+
+    ///
+    /// Returns the raw register byte
+    ///
+    pub const fn bits(self) -> u8 {
+        self.0
+    }
+
+    ///
+    /// Sets BCTRL, turning the brightness control block on
+    ///
+    pub const fn brightness_control(self, on: bool) -> Self {
+        self.with(BCTRL, on)
+    }
+
+    ///
+    /// Sets DD, fading between brightness levels instead of switching right away
+    ///
+    pub const fn dimming(self, on: bool) -> Self {
+        self.with(DD, on)
+    }
+
+    ///
+    /// Sets BL, turning the backlight control on. The LEDPWM pin stays low while off.
+    ///
+    pub const fn backlight(self, on: bool) -> Self {
+        self.with(BL, on)
+    }
+
+    const fn with(self, bit: u8, set: bool) -> Self {
+        if set {
+            Self(self.0 | bit)
+        } else {
+            Self(self.0 & !bit)
+        }
+    }
+}
+
+///
+/// Brightness control of panels whose backlight is driven by the controller's LEDPWM pin.
+///
+impl<DI, OUT, PinE> ST7789<DI, OUT>
+where
+    DI: DisplayInterface,
+    OUT: OutputPin<Error = PinE>,
+{
+    ///
+    /// Sets the display brightness (WRDISBV)
+    ///
+    /// # Arguments
+    ///
+    /// * `level` - brightness from 0 (lowest) to 255 (highest)
+    ///
+    pub fn set_display_brightness(&mut self, level: u8) -> Result<(), Error<PinE>> {
+        self.send_command(Instruction::WRDISBV, &[level])
+    }
+
+    ///
+    /// Configures the brightness control block, dimming and backlight (WRCTRLD)
+    ///
+    /// # Arguments
+    ///
+    /// * `control` - brightness control bits
+    ///
+    pub fn set_brightness_control(
+        &mut self,
+        control: BrightnessControl,
+    ) -> Result<(), Error<PinE>> {
+        self.send_command(Instruction::WRCTRLD, &[control.bits()])
+    }
+
+    ///
+    /// Sets the content adaptive brightness control mode (WRCACE)
+    ///
+    /// # Arguments
+    ///
+    /// * `mode` - how strongly the backlight follows the picture content
+    ///
+    pub fn set_cabc_mode(&mut self, mode: CabcMode) -> Result<(), Error<PinE>> {
+        self.send_command(Instruction::WRCACE, &[mode as u8])
+    }
+
+    ///
+    /// Sets the lowest brightness content adaptive brightness control may dim to (WRCABCMB)
+    ///
+    /// # Arguments
+    ///
+    /// * `level` - minimum brightness from 0 to 255
+    ///
+    pub fn set_cabc_min_brightness(&mut self, level: u8) -> Result<(), Error<PinE>> {
+        self.send_command(Instruction::WRCABCMB, &[level])
+    }
+}
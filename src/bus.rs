@@ -0,0 +1,111 @@
+// 128 frames of 9 bits fill the buffer exactly, so every full chunk ends on a frame boundary
+pub(crate) const CHUNK_LEN: usize = 144;
+
+///
+/// Splits bytes into chunks of at most [`CHUNK_LEN`] bytes, one bus write each.
+///
+/// On the 3-wire serial interface every byte is prefixed with its D/C bit (0 = command,
+/// 1 = data) and the 9-bit frames are sent MSB first as a continuous bit stream over a
+/// regular 8-bit SPI bus. A chunk always holds whole frames, the last byte of the last
+/// chunk is padded with zero bits, which the controller discards once CS is deasserted.
+///
+/// The blocking and the async driver both encode through this type and only differ in
+/// how they write the chunks.
+///
+pub(crate) struct BusChunks<I> {
+    bytes: I,
+    // D/C bit of every frame, `None` for plain 8-bit bytes
+    nine_bit: Option<bool>,
+    // Command frame going in front of the bytes
+    command: Option<u8>,
+    buffer: [u8; CHUNK_LEN],
+}
+
+impl<I> BusChunks<I>
+where
+    I: Iterator<Item = u8>,
+{
+    ///
+    /// Chunks bytes for an interface with a DC pin
+    ///
+    pub(crate) fn new(bytes: I) -> Self {
+        Self {
+            bytes,
+            nine_bit: None,
+            command: None,
+            buffer: [0; CHUNK_LEN],
+        }
+    }
+
+    ///
+    /// Packs bytes with the same D/C bit into 9-bit frames
+    ///
+    pub(crate) fn nine_bit(dc: bool, bytes: I) -> Self {
+        Self {
+            nine_bit: Some(dc),
+            ..Self::new(bytes)
+        }
+    }
+
+    ///
+    /// Packs a command and its parameters into 9-bit frames, the command with a low
+    /// D/C bit
+    ///
+    pub(crate) fn nine_bit_command(command: u8, params: I) -> Self {
+        Self {
+            command: Some(command),
+            ..Self::nine_bit(true, params)
+        }
+    }
+
+    ///
+    /// Returns the next chunk, `None` once all bytes are encoded
+    ///
+    pub(crate) fn next_chunk(&mut self) -> Option<&[u8]> {
+        let len = match self.nine_bit {
+            None => self.fill(),
+            Some(dc) => self.fill_nine_bit(dc),
+        };
+        (len > 0).then(|| &self.buffer[..len])
+    }
+
+    fn fill(&mut self) -> usize {
+        let mut len = 0;
+        while len < CHUNK_LEN {
+            let Some(byte) = self.bytes.next() else {
+                break;
+            };
+            self.buffer[len] = byte;
+            len += 1;
+        }
+        len
+    }
+
+    fn fill_nine_bit(&mut self, dc: bool) -> usize {
+        let mut len = 0;
+        // Bits not yet written to the buffer, right aligned
+        let mut acc = 0u32;
+        let mut acc_bits = 0;
+        while len < CHUNK_LEN {
+            let frame = match self.command.take() {
+                Some(command) => u32::from(command),
+                None => match self.bytes.next() {
+                    Some(byte) => (u32::from(dc) << 8) | u32::from(byte),
+                    None => break,
+                },
+            };
+            acc = (acc << 9) | frame;
+            acc_bits += 9;
+            while acc_bits >= 8 {
+                acc_bits -= 8;
+                self.buffer[len] = (acc >> acc_bits) as u8;
+                len += 1;
+            }
+        }
+        if acc_bits > 0 {
+            self.buffer[len] = (acc << (8 - acc_bits)) as u8;
+            len += 1;
+        }
+        len
+    }
+}
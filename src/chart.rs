@@ -0,0 +1,436 @@
+use core::fmt::{self, Write as _};
+use core::ops::RangeInclusive;
+
+use embedded_graphics::{
+    mono_font::{MonoFont, MonoTextStyle},
+    pixelcolor::Rgb565,
+    prelude::*,
+    primitives::{PrimitiveStyle, Rectangle},
+    text::{Alignment, Baseline, Text, TextStyleBuilder},
+};
+use embedded_hal::digital::OutputPin;
+
+use crate::panel::{GRAM_HEIGHT, GRAM_WIDTH};
+use crate::{DisplayInterface, Error, MemoryAccessControl, Orientation, ScrollRegion, ST7789};
+
+// Characters reserved for the y-axis labels
+const LABEL_CHARS: u16 = 6;
+// Gap between the labels and the axis line
+const LABEL_GAP: u16 = 2;
+// Share of the range added beyond a sample that did not fit
+const MARGIN: f32 = 0.1;
+
+///
+/// Scrolling strip chart using hardware scrolling.
+///
+/// The chart switches the display to [`Orientation::Landscape`], where each frame memory
+/// row is one column on screen, and scrolls the frame memory rows behind the plot. Time
+/// runs from left to right: once the plot is full, every new sample scrolls the plot to
+/// the left by one column and is drawn into the column that scrolled off, so a sample
+/// costs one column of pixels and a scroll command.
+///
+/// Each of the `N` series is drawn as a line in its own colour. Samples outside the
+/// current y range widen it and redraw the plot when autoscaling is on. The y-axis
+/// labels sit in the fixed area on the left and are only redrawn when the range changes.
+///
+/// ```
+/// use embedded_graphics::{mono_font::ascii::FONT_6X10, pixelcolor::Rgb565, prelude::*};
+/// use st7789::{testing::{MockBus, PinRole}, SpiInterface, StripChart, ST7789};
+///
+/// let bus = MockBus::new();
+/// let di = SpiInterface::new(bus.spi(), bus.pin(PinRole::Dc));
+/// let mut display = ST7789::new(di, Some(bus.pin(PinRole::Reset)), None, 240, 240);
+/// let mut chart =
+///     StripChart::new(&mut display, &FONT_6X10, [Rgb565::RED, Rgb565::GREEN], 0.0..=100.0)
+///         .unwrap();
+/// chart.push(&mut display, [20.0, 75.5]).unwrap();
+/// ```
+///
+/// A non-finite sample leaves a gap in its series.
+///
+pub struct StripChart<'a, const N: usize> {
+    font: &'a MonoFont<'a>,
+    region: ScrollRegion,
+    colors: [Rgb565; N],
+    axis: Rgb565,
+    background: Rgb565,
+    min: f32,
+    max: f32,
+    autoscale: bool,
+    // Plot area on screen
+    plot_x: u16,
+    plot_width: u16,
+    plot_height: u16,
+    // Samples by plot column, as a ring starting at `next` once full
+    history: [[f32; N]; GRAM_HEIGHT as usize],
+    len: u16,
+    next: u16,
+}
+
+impl<'a, const N: usize> StripChart<'a, N> {
+    ///
+    /// Creates a new chart covering the display and clears it
+    ///
+    /// # Arguments
+    ///
+    /// * `display` - the display to draw to
+    /// * `font` - monospace font for the y-axis labels
+    /// * `colors` - line colour of each series
+    /// * `range` - initial y range
+    ///
+    pub fn new<DI, OUT, PinE>(
+        display: &mut ST7789<DI, OUT>,
+        font: &'a MonoFont<'a>,
+        colors: [Rgb565; N],
+        range: RangeInclusive<f32>,
+    ) -> Result<Self, Error<PinE>>
+    where
+        DI: DisplayInterface,
+        OUT: OutputPin<Error = PinE>,
+    {
+        Self::with_colors(display, font, colors, range, Rgb565::WHITE, Rgb565::BLACK)
+    }
+
+    ///
+    /// Creates a new chart with the given axis and background colours and clears it
+    ///
+    /// # Arguments
+    ///
+    /// * `display` - the display to draw to
+    /// * `font` - monospace font for the y-axis labels
+    /// * `colors` - line colour of each series
+    /// * `range` - initial y range
+    /// * `axis` - colour of the axis line and labels
+    /// * `background` - background colour
+    ///
+    pub fn with_colors<DI, OUT, PinE>(
+        display: &mut ST7789<DI, OUT>,
+        font: &'a MonoFont<'a>,
+        colors: [Rgb565; N],
+        range: RangeInclusive<f32>,
+        axis: Rgb565,
+        background: Rgb565,
+    ) -> Result<Self, Error<PinE>>
+    where
+        DI: DisplayInterface,
+        OUT: OutputPin<Error = PinE>,
+    {
+        let (min, max) = range.into_inner();
+        if !(min.is_finite() && max.is_finite() && min < max) {
+            return Err(Error::InvalidArgument);
+        }
+
+        let panel = display.panel();
+        let plot_x =
+            LABEL_CHARS * (font.character_size.width + font.character_spacing) as u16 + LABEL_GAP;
+        // landscape shows the panel rows as columns
+        let (width, height) = (panel.height, panel.width);
+        if plot_x >= width {
+            return Err(Error::InvalidArgument);
+        }
+
+        let madctl = display.memory_access_control();
+        display.set_orientation(
+            MemoryAccessControl::from(Orientation::Landscape).bgr(madctl.is_bgr()),
+        )?;
+        // the plot scrolls, the labels on the left and anything past the panel stay fixed
+        let top_fixed = panel.row_offset + plot_x;
+        let plot_width = width - plot_x;
+        let region = display.define_scroll_area(
+            top_fixed,
+            plot_width,
+            GRAM_HEIGHT - top_fixed - plot_width,
+        )?;
+
+        let mut chart = Self {
+            font,
+            region,
+            colors,
+            axis,
+            background,
+            min,
+            max,
+            autoscale: true,
+            plot_x,
+            plot_width,
+            plot_height: height,
+            history: [[f32::NAN; N]; GRAM_HEIGHT as usize],
+            len: 0,
+            next: 0,
+        };
+        chart.clear(display)?;
+        Ok(chart)
+    }
+
+    ///
+    /// Returns the current y range
+    ///
+    pub fn range(&self) -> RangeInclusive<f32> {
+        self.min..=self.max
+    }
+
+    ///
+    /// Returns the number of samples the plot shows at once
+    ///
+    pub fn capacity(&self) -> u16 {
+        self.plot_width
+    }
+
+    ///
+    /// Turns widening the y range for samples outside of it on or off, on by default
+    ///
+    pub fn set_autoscale(&mut self, autoscale: bool) {
+        self.autoscale = autoscale;
+    }
+
+    ///
+    /// Sets the y range and redraws the labels and the plot
+    ///
+    /// # Arguments
+    ///
+    /// * `display` - the display to draw to
+    /// * `range` - new y range
+    ///
+    pub fn set_range<DI, OUT, PinE>(
+        &mut self,
+        display: &mut ST7789<DI, OUT>,
+        range: RangeInclusive<f32>,
+    ) -> Result<(), Error<PinE>>
+    where
+        DI: DisplayInterface,
+        OUT: OutputPin<Error = PinE>,
+    {
+        let (min, max) = range.into_inner();
+        if !(min.is_finite() && max.is_finite() && min < max) {
+            return Err(Error::InvalidArgument);
+        }
+
+        self.min = min;
+        self.max = max;
+        self.draw_labels(display)?;
+        self.redraw(display)
+    }
+
+    ///
+    /// Clears the display and the sample history, resets scrolling and draws the axis
+    ///
+    pub fn clear<DI, OUT, PinE>(&mut self, display: &mut ST7789<DI, OUT>) -> Result<(), Error<PinE>>
+    where
+        DI: DisplayInterface,
+        OUT: OutputPin<Error = PinE>,
+    {
+        display.clear(self.background)?;
+        self.region.set_offset(0);
+        display.scroll(&self.region)?;
+        self.len = 0;
+        self.next = 0;
+        self.draw_labels(display)
+    }
+
+    ///
+    /// Adds a sample to every series, scrolling the plot once it is full
+    ///
+    /// # Arguments
+    ///
+    /// * `display` - the display to draw to
+    /// * `values` - the new value of each series
+    ///
+    pub fn push<DI, OUT, PinE>(
+        &mut self,
+        display: &mut ST7789<DI, OUT>,
+        values: [f32; N],
+    ) -> Result<(), Error<PinE>>
+    where
+        DI: DisplayInterface,
+        OUT: OutputPin<Error = PinE>,
+    {
+        let column = self.next;
+        self.history[usize::from(column)] = values;
+        self.next = (column + 1) % self.plot_width;
+        if self.len < self.plot_width {
+            self.len += 1;
+        } else {
+            // the oldest sample left is shown in the first plot column
+            self.region.set_offset(self.next);
+            display.scroll(&self.region)?;
+        }
+
+        if self.autoscale && self.fit(&values) {
+            self.draw_labels(display)?;
+            return self.redraw(display);
+        }
+        self.draw_column(display, column)
+    }
+
+    // Widens the range to include the values, returns true if it changed
+    fn fit(&mut self, values: &[f32; N]) -> bool {
+        let finite = values.iter().copied().filter(|v| v.is_finite());
+        let low = finite.clone().fold(self.min, f32::min);
+        let high = finite.fold(self.max, f32::max);
+        if low >= self.min && high <= self.max {
+            return false;
+        }
+
+        let margin = (high - low) * MARGIN;
+        if low < self.min {
+            self.min = low - margin;
+        }
+        if high > self.max {
+            self.max = high + margin;
+        }
+        true
+    }
+
+    // Draws every sample in the history
+    fn redraw<DI, OUT, PinE>(&mut self, display: &mut ST7789<DI, OUT>) -> Result<(), Error<PinE>>
+    where
+        DI: DisplayInterface,
+        OUT: OutputPin<Error = PinE>,
+    {
+        for column in 0..self.len {
+            self.draw_column(display, column)?;
+        }
+        Ok(())
+    }
+
+    // Draws the plot column holding the given sample, joined to the sample before it
+    fn draw_column<DI, OUT, PinE>(
+        &self,
+        display: &mut ST7789<DI, OUT>,
+        column: u16,
+    ) -> Result<(), Error<PinE>>
+    where
+        DI: DisplayInterface,
+        OUT: OutputPin<Error = PinE>,
+    {
+        let oldest = if self.len < self.plot_width {
+            0
+        } else {
+            self.next
+        };
+        let current = &self.history[usize::from(column)];
+        let previous = if column == oldest {
+            current
+        } else {
+            let before = (column + self.plot_width - 1) % self.plot_width;
+            &self.history[usize::from(before)]
+        };
+
+        let mut pixels = [self.background.into_storage(); GRAM_WIDTH as usize];
+        let pixels = &mut pixels[..usize::from(self.plot_height)];
+        for ((&value, &last), color) in current.iter().zip(previous).zip(self.colors) {
+            let Some(y) = self.to_y(value) else {
+                continue;
+            };
+            let last = self.to_y(last).unwrap_or(y);
+            pixels[y.min(last)..=y.max(last)].fill(color.into_storage());
+        }
+
+        // the frame memory row of a plot column does not change while scrolling
+        let x = self.plot_x + column;
+        display.set_pixels(x, 0, x, self.plot_height - 1, pixels.iter().copied())
+    }
+
+    // Returns the plot row of a value, None if it is not finite
+    fn to_y(&self, value: f32) -> Option<usize> {
+        if !value.is_finite() {
+            return None;
+        }
+        let bottom = f32::from(self.plot_height - 1);
+        let scaled = (value - self.min) / (self.max - self.min) * bottom;
+        // rounds to the nearest row without std
+        Some((bottom - scaled.clamp(0.0, bottom) + 0.5) as usize)
+    }
+
+    // Draws the axis line and the labels for the top, middle and bottom of the range
+    fn draw_labels<DI, OUT, PinE>(&self, display: &mut ST7789<DI, OUT>) -> Result<(), Error<PinE>>
+    where
+        DI: DisplayInterface,
+        OUT: OutputPin<Error = PinE>,
+    {
+        let height = u32::from(self.plot_height);
+        Rectangle::new(Point::zero(), Size::new(u32::from(self.plot_x), height))
+            .into_styled(PrimitiveStyle::with_fill(self.background))
+            .draw(display)?;
+        Rectangle::new(
+            Point::new(i32::from(self.plot_x) - 1, 0),
+            Size::new(1, height),
+        )
+        .into_styled(PrimitiveStyle::with_fill(self.axis))
+        .draw(display)?;
+
+        let character_style = MonoTextStyle::new(self.font, self.axis);
+        let right = i32::from(self.plot_x - LABEL_GAP) - 1;
+        let labels = [
+            (self.max, 0, Baseline::Top),
+            (
+                (self.min + self.max) / 2.0,
+                height as i32 / 2,
+                Baseline::Middle,
+            ),
+            (self.min, height as i32, Baseline::Bottom),
+        ];
+        for (value, y, baseline) in labels {
+            let text_style = TextStyleBuilder::new()
+                .alignment(Alignment::Right)
+                .baseline(baseline)
+                .build();
+            let mut label = Label::default();
+            self.format(&mut label, value);
+            Text::with_text_style(
+                label.as_str(),
+                Point::new(right, y),
+                character_style,
+                text_style,
+            )
+            .draw(display)?;
+        }
+        Ok(())
+    }
+
+    // Formats a label with as many decimals as the range needs
+    fn format(&self, label: &mut Label, value: f32) {
+        let span = self.max - self.min;
+        let decimals = if span >= 10.0 {
+            0
+        } else if span >= 1.0 {
+            1
+        } else {
+            2
+        };
+        if write!(label, "{:.*}", decimals, value).is_err() {
+            // too long for the label area, drop the decimals
+            label.len = 0;
+            write!(label, "{:.0}", value).ok();
+        }
+    }
+}
+
+// Fixed size buffer for a label
+#[derive(Default)]
+struct Label {
+    buf: [u8; LABEL_CHARS as usize],
+    len: usize,
+}
+
+impl Label {
+    fn as_str(&self) -> &str {
+        core::str::from_utf8(&self.buf[..self.len]).unwrap_or_default()
+    }
+}
+
+impl fmt::Write for Label {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let end = self.len + s.len();
+        if end > self.buf.len() {
+            // keep what fits
+            let fits = self.buf.len() - self.len;
+            self.buf[self.len..].copy_from_slice(&s.as_bytes()[..fits]);
+            self.len = self.buf.len();
+            return Err(fmt::Error);
+        }
+        self.buf[self.len..end].copy_from_slice(s.as_bytes());
+        self.len = end;
+        Ok(())
+    }
+}
@@ -0,0 +1,118 @@
+use crate::PixelFormat;
+
+///
+/// Expands a RGB565 color to 8 bits per channel, repeating the upper bits in the low ones
+///
+pub(crate) fn rgb565_to_rgb888(color: u16) -> [u8; 3] {
+    let r = (color >> 11) as u8;
+    let g = ((color >> 5) & 0x3F) as u8;
+    let b = (color & 0x1F) as u8;
+    [
+        (r << 3) | (r >> 2),
+        (g << 2) | (g >> 4),
+        (b << 3) | (b >> 2),
+    ]
+}
+
+///
+/// Reduces a color with 8 bits per channel to RGB565
+///
+pub(crate) fn rgb888_to_rgb565([r, g, b]: [u8; 3]) -> u16 {
+    (u16::from(r >> 3) << 11) | (u16::from(g >> 2) << 5) | u16::from(b >> 3)
+}
+
+impl PixelFormat {
+    ///
+    /// Returns the number of bytes `pixels` pixels take on the bus in this format.
+    /// Two RGB444 pixels share three bytes, a single one at the end takes two.
+    ///
+    pub fn data_len(self, pixels: usize) -> usize {
+        match self {
+            Self::Rgb444 => (pixels * 3).div_ceil(2),
+            Self::Rgb565 => pixels * 2,
+            Self::Rgb666 => pixels * 3,
+        }
+    }
+
+    // COLMOD parameter setting the RGB and the control interface to this format
+    pub(crate) fn colmod(self) -> u8 {
+        let bits = self as u8;
+        (bits << 4) | bits
+    }
+}
+
+///
+/// Iterator over the bus bytes of colors with 8 bits per channel in an interface pixel format
+///
+pub(crate) struct PixelBytes<I> {
+    colors: I,
+    format: PixelFormat,
+    buf: [u8; 3],
+    pos: usize,
+    len: usize,
+}
+
+impl<I> PixelBytes<I>
+where
+    I: Iterator<Item = [u8; 3]>,
+{
+    pub(crate) fn new(format: PixelFormat, colors: I) -> Self {
+        Self {
+            colors,
+            format,
+            buf: [0; 3],
+            pos: 0,
+            len: 0,
+        }
+    }
+
+    // Encodes the next pixel, or the next two RGB444 pixels, into the buffer
+    fn refill(&mut self) -> bool {
+        let Some([r, g, b]) = self.colors.next() else {
+            return false;
+        };
+        self.pos = 0;
+        match self.format {
+            PixelFormat::Rgb444 => {
+                self.buf[0] = (r & 0xF0) | (g >> 4);
+                match self.colors.next() {
+                    Some([r2, g2, b2]) => {
+                        self.buf[1] = (b & 0xF0) | (r2 >> 4);
+                        self.buf[2] = (g2 & 0xF0) | (b2 >> 4);
+                        self.len = 3;
+                    }
+                    None => {
+                        // a lone last pixel leaves the low nibble unused
+                        self.buf[1] = b & 0xF0;
+                        self.len = 2;
+                    }
+                }
+            }
+            PixelFormat::Rgb565 => {
+                self.buf[..2].copy_from_slice(&rgb888_to_rgb565([r, g, b]).to_be_bytes());
+                self.len = 2;
+            }
+            PixelFormat::Rgb666 => {
+                // 6 bits per channel in the upper bits of each byte
+                self.buf = [r & 0xFC, g & 0xFC, b & 0xFC];
+                self.len = 3;
+            }
+        }
+        true
+    }
+}
+
+impl<I> Iterator for PixelBytes<I>
+where
+    I: Iterator<Item = [u8; 3]>,
+{
+    type Item = u8;
+
+    fn next(&mut self) -> Option<u8> {
+        if self.pos == self.len && !self.refill() {
+            return None;
+        }
+        self.pos += 1;
+        Some(self.buf[self.pos - 1])
+    }
+}
@@ -0,0 +1,405 @@
+use core::fmt;
+
+use embedded_graphics::{
+    mono_font::{MonoFont, MonoTextStyleBuilder},
+    pixelcolor::Rgb565,
+    prelude::*,
+    primitives::{PrimitiveStyle, Rectangle},
+    text::{Baseline, Text},
+};
+use embedded_hal::digital::OutputPin;
+
+use crate::{DisplayInterface, Error, MemoryAccessControl, Orientation, ScrollRegion, ST7789};
+
+// Columns a tab advances to a multiple of
+const TAB_WIDTH: u16 = 4;
+// Most parameters kept from one escape sequence
+const MAX_PARAMS: usize = 4;
+
+// VGA palette for SGR colors 30-37 and 90-97
+const PALETTE: [Rgb565; 16] = [
+    Rgb565::new(0, 0, 0),
+    Rgb565::new(21, 0, 0),
+    Rgb565::new(0, 42, 0),
+    Rgb565::new(21, 21, 0),
+    Rgb565::new(0, 0, 21),
+    Rgb565::new(21, 0, 21),
+    Rgb565::new(0, 42, 21),
+    Rgb565::new(21, 42, 21),
+    Rgb565::new(10, 21, 10),
+    Rgb565::new(31, 21, 10),
+    Rgb565::new(10, 63, 10),
+    Rgb565::new(31, 63, 10),
+    Rgb565::new(10, 21, 31),
+    Rgb565::new(31, 21, 31),
+    Rgb565::new(10, 63, 31),
+    Rgb565::new(31, 63, 31),
+];
+
+// Escape sequence parser state
+#[derive(Copy, Clone)]
+enum Escape {
+    None,
+    Started,
+    Csi {
+        params: [u16; MAX_PARAMS],
+        count: usize,
+    },
+}
+
+///
+/// Text console using hardware scrolling.
+///
+/// Lines of monospace text are drawn into the frame memory as a ring: when the cursor
+/// moves past the bottom line, the scroll start address advances by one text line and
+/// the new line is drawn into the rows that just scrolled off the top. Nothing else is
+/// redrawn, so printing costs one line of pixels at most.
+///
+/// Long lines wrap, `\n`, `\r` and `\t` move the cursor and the SGR colour escapes
+/// `ESC[...m` (0, 1, 22, 30-37, 39, 40-47, 49, 90-97 and 100-107) change the colours.
+///
+/// ```
+/// use core::fmt::Write;
+/// use embedded_graphics::mono_font::ascii::FONT_6X10;
+/// use st7789::{testing::{MockBus, PinRole}, Console, SpiInterface, ST7789};
+///
+/// let bus = MockBus::new();
+/// let di = SpiInterface::new(bus.spi(), bus.pin(PinRole::Dc));
+/// let mut display = ST7789::new(di, Some(bus.pin(PinRole::Reset)), None, 240, 240);
+/// let mut console = Console::new(&mut display, &FONT_6X10).unwrap();
+/// writeln!(console.writer(&mut display), "\x1b[32mok\x1b[0m booted in {} ms", 42).unwrap();
+/// ```
+///
+/// The console switches the display to portrait orientation and uses the whole panel.
+///
+pub struct Console<'a> {
+    font: &'a MonoFont<'a>,
+    region: ScrollRegion,
+    // First frame memory row of the panel
+    row_offset: u16,
+    columns: u16,
+    rows: u16,
+    // Cursor position in characters
+    column: u16,
+    row: u16,
+    foreground: Rgb565,
+    background: Rgb565,
+    default_foreground: Rgb565,
+    default_background: Rgb565,
+    bold: bool,
+    escape: Escape,
+}
+
+impl<'a> Console<'a> {
+    ///
+    /// Creates a new console covering the display and clears it
+    ///
+    /// # Arguments
+    ///
+    /// * `display` - the display to print to
+    /// * `font` - monospace font, e.g. from `embedded_graphics::mono_font::ascii`
+    ///
+    pub fn new<DI, OUT, PinE>(
+        display: &mut ST7789<DI, OUT>,
+        font: &'a MonoFont<'a>,
+    ) -> Result<Self, Error<PinE>>
+    where
+        DI: DisplayInterface,
+        OUT: OutputPin<Error = PinE>,
+    {
+        Self::with_colors(display, font, Rgb565::WHITE, Rgb565::BLACK)
+    }
+
+    ///
+    /// Creates a new console with the given default colours and clears it
+    ///
+    /// # Arguments
+    ///
+    /// * `display` - the display to print to
+    /// * `font` - monospace font, e.g. from `embedded_graphics::mono_font::ascii`
+    /// * `foreground` - default text colour
+    /// * `background` - default background colour
+    ///
+    pub fn with_colors<DI, OUT, PinE>(
+        display: &mut ST7789<DI, OUT>,
+        font: &'a MonoFont<'a>,
+        foreground: Rgb565,
+        background: Rgb565,
+    ) -> Result<Self, Error<PinE>>
+    where
+        DI: DisplayInterface,
+        OUT: OutputPin<Error = PinE>,
+    {
+        let panel = display.panel();
+        let line_height = font.character_size.height as u16;
+        let columns = panel.width / Self::column_width(font);
+        let rows = panel.height / line_height;
+        if columns == 0 || rows == 0 {
+            return Err(Error::InvalidArgument);
+        }
+
+        let madctl = display.memory_access_control();
+        display.set_orientation(
+            MemoryAccessControl::from(Orientation::Portrait).bgr(madctl.is_bgr()),
+        )?;
+        // the scrolling area holds whole text lines, the rest of the panel stays fixed
+        let scroll_height = rows * line_height;
+        let region = display.define_scroll_area(
+            panel.row_offset,
+            scroll_height,
+            crate::panel::GRAM_HEIGHT - panel.row_offset - scroll_height,
+        )?;
+
+        let mut console = Self {
+            font,
+            region,
+            row_offset: panel.row_offset,
+            columns,
+            rows,
+            column: 0,
+            row: 0,
+            foreground,
+            background,
+            default_foreground: foreground,
+            default_background: background,
+            bold: false,
+            escape: Escape::None,
+        };
+        console.clear(display)?;
+        Ok(console)
+    }
+
+    ///
+    /// Returns the console size in characters (columns, rows)
+    ///
+    pub fn size(&self) -> (u16, u16) {
+        (self.columns, self.rows)
+    }
+
+    ///
+    /// Returns the cursor position in characters (column, row)
+    ///
+    pub fn cursor(&self) -> (u16, u16) {
+        (self.column, self.row)
+    }
+
+    ///
+    /// Clears the display, resets scrolling and moves the cursor to the top left
+    ///
+    pub fn clear<DI, OUT, PinE>(&mut self, display: &mut ST7789<DI, OUT>) -> Result<(), Error<PinE>>
+    where
+        DI: DisplayInterface,
+        OUT: OutputPin<Error = PinE>,
+    {
+        display.clear(self.background)?;
+        self.region.set_offset(0);
+        display.scroll(&self.region)?;
+        self.column = 0;
+        self.row = 0;
+        Ok(())
+    }
+
+    ///
+    /// Prints text, interpreting control characters and colour escapes
+    ///
+    pub fn write_str<DI, OUT, PinE>(
+        &mut self,
+        display: &mut ST7789<DI, OUT>,
+        text: &str,
+    ) -> Result<(), Error<PinE>>
+    where
+        DI: DisplayInterface,
+        OUT: OutputPin<Error = PinE>,
+    {
+        for c in text.chars() {
+            self.write_char(display, c)?;
+        }
+        Ok(())
+    }
+
+    ///
+    /// Returns a [`fmt::Write`] adapter for use with `write!` and `writeln!`
+    ///
+    pub fn writer<'c, 'd, DI, OUT>(
+        &'c mut self,
+        display: &'d mut ST7789<DI, OUT>,
+    ) -> ConsoleWriter<'c, 'd, 'a, DI, OUT>
+    where
+        DI: DisplayInterface,
+        OUT: OutputPin,
+    {
+        ConsoleWriter {
+            console: self,
+            display,
+        }
+    }
+
+    fn write_char<DI, OUT, PinE>(
+        &mut self,
+        display: &mut ST7789<DI, OUT>,
+        c: char,
+    ) -> Result<(), Error<PinE>>
+    where
+        DI: DisplayInterface,
+        OUT: OutputPin<Error = PinE>,
+    {
+        match (self.escape, c) {
+            (Escape::None, '\x1b') => self.escape = Escape::Started,
+            (Escape::None, '\n') => self.new_line(display)?,
+            (Escape::None, '\r') => self.column = 0,
+            (Escape::None, '\t') => {
+                self.column = (self.column / TAB_WIDTH + 1) * TAB_WIDTH;
+                self.column = self.column.min(self.columns);
+            }
+            (Escape::None, c) if c.is_control() => {}
+            (Escape::None, c) => self.draw_char(display, c)?,
+            (Escape::Started, '[') => {
+                self.escape = Escape::Csi {
+                    params: [0; MAX_PARAMS],
+                    count: 0,
+                }
+            }
+            (Escape::Started, _) => self.escape = Escape::None,
+            (Escape::Csi { mut params, count }, '0'..='9') => {
+                if let Some(param) = params.get_mut(count) {
+                    let digit = c as u16 - u16::from(b'0');
+                    *param = param.saturating_mul(10).saturating_add(digit);
+                }
+                self.escape = Escape::Csi { params, count };
+            }
+            (Escape::Csi { params, count }, ';') => {
+                self.escape = Escape::Csi {
+                    params,
+                    count: count + 1,
+                }
+            }
+            (Escape::Csi { params, count }, 'm') => {
+                let count = (count + 1).min(MAX_PARAMS);
+                params[..count].iter().for_each(|&p| self.select_graphic(p));
+                self.escape = Escape::None;
+            }
+            // other sequences are ignored up to their final byte
+            (Escape::Csi { .. }, '\x40'..='\x7e') => self.escape = Escape::None,
+            (Escape::Csi { .. }, _) => {}
+        }
+        Ok(())
+    }
+
+    // Applies an SGR parameter
+    fn select_graphic(&mut self, param: u16) {
+        match param {
+            0 => {
+                self.foreground = self.default_foreground;
+                self.background = self.default_background;
+                self.bold = false;
+            }
+            // bold shows the normal colours as their bright variants
+            1 => {
+                self.bold = true;
+                if let Some(i) = PALETTE[..8].iter().position(|&c| c == self.foreground) {
+                    self.foreground = PALETTE[i + 8];
+                }
+            }
+            22 => self.bold = false,
+            30..=37 => {
+                let bright = if self.bold { 8 } else { 0 };
+                self.foreground = PALETTE[usize::from(param - 30) + bright];
+            }
+            39 => self.foreground = self.default_foreground,
+            40..=47 => self.background = PALETTE[usize::from(param - 40)],
+            49 => self.background = self.default_background,
+            90..=97 => self.foreground = PALETTE[usize::from(param - 90 + 8)],
+            100..=107 => self.background = PALETTE[usize::from(param - 100 + 8)],
+            _ => {}
+        }
+    }
+
+    fn draw_char<DI, OUT, PinE>(
+        &mut self,
+        display: &mut ST7789<DI, OUT>,
+        c: char,
+    ) -> Result<(), Error<PinE>>
+    where
+        DI: DisplayInterface,
+        OUT: OutputPin<Error = PinE>,
+    {
+        if self.column >= self.columns {
+            self.new_line(display)?;
+        }
+
+        let style = MonoTextStyleBuilder::new()
+            .font(self.font)
+            .text_color(self.foreground)
+            .background_color(self.background)
+            .build();
+        let position = Point::new(
+            i32::from(self.column * Self::column_width(self.font)),
+            i32::from(self.line_y(self.row)),
+        );
+        Text::with_baseline(c.encode_utf8(&mut [0; 4]), position, style, Baseline::Top)
+            .draw(display)?;
+        self.column += 1;
+        Ok(())
+    }
+
+    // Moves the cursor to the start of the next line, scrolling at the bottom
+    fn new_line<DI, OUT, PinE>(&mut self, display: &mut ST7789<DI, OUT>) -> Result<(), Error<PinE>>
+    where
+        DI: DisplayInterface,
+        OUT: OutputPin<Error = PinE>,
+    {
+        self.column = 0;
+        if self.row + 1 < self.rows {
+            self.row += 1;
+        } else {
+            self.region
+                .scroll_by(self.font.character_size.height as i32);
+            display.scroll(&self.region)?;
+        }
+
+        // the new line still holds what scrolled off the top, colours don't carry over
+        let line = Rectangle::new(
+            Point::new(0, i32::from(self.line_y(self.row))),
+            Size::new(
+                u32::from(self.columns * Self::column_width(self.font)),
+                self.font.character_size.height,
+            ),
+        );
+        line.into_styled(PrimitiveStyle::with_fill(self.default_background))
+            .draw(display)
+    }
+
+    // Returns the display row the given text line is drawn at
+    fn line_y(&self, row: u16) -> u16 {
+        let line = self.row_offset + row * self.font.character_size.height as u16;
+        self.region.gram_row(line) - self.row_offset
+    }
+
+    fn column_width(font: &MonoFont<'_>) -> u16 {
+        (font.character_size.width + font.character_spacing) as u16
+    }
+}
+
+///
+/// [`fmt::Write`] adapter printing to a [`Console`], see [`Console::writer`].
+///
+pub struct ConsoleWriter<'c, 'd, 'a, DI, OUT>
+where
+    DI: DisplayInterface,
+    OUT: OutputPin,
+{
+    console: &'c mut Console<'a>,
+    display: &'d mut ST7789<DI, OUT>,
+}
+
+impl<DI, OUT> fmt::Write for ConsoleWriter<'_, '_, '_, DI, OUT>
+where
+    DI: DisplayInterface,
+    OUT: OutputPin,
+{
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.console
+            .write_str(self.display, s)
+            .map_err(|_| fmt::Error)
+    }
+}
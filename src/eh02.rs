@@ -0,0 +1,321 @@
+//! Adapters for embedded-hal 0.2 peripherals.
+//!
+//! The driver is built on the embedded-hal 1.0 traits. HALs which only implement 0.2 can
+//! still be used by wrapping their SPI bus, pins, delay and PWM channel:
+//!
+//! ```
+//! # use core::convert::Infallible;
+//! # use embedded_hal_02::blocking::{delay::DelayUs, spi};
+//! # use embedded_hal_02::digital::v2::OutputPin;
+//! # struct HalSpi;
+//! # impl spi::Write<u8> for HalSpi {
+//! #     type Error = Infallible;
+//! #     fn write(&mut self, _: &[u8]) -> Result<(), Infallible> { Ok(()) }
+//! # }
+//! # impl spi::Transfer<u8> for HalSpi {
+//! #     type Error = Infallible;
+//! #     fn transfer<'w>(&mut self, words: &'w mut [u8]) -> Result<&'w [u8], Infallible> { Ok(words) }
+//! # }
+//! # struct HalPin;
+//! # impl OutputPin for HalPin {
+//! #     type Error = Infallible;
+//! #     fn set_low(&mut self) -> Result<(), Infallible> { Ok(()) }
+//! #     fn set_high(&mut self) -> Result<(), Infallible> { Ok(()) }
+//! # }
+//! # struct HalDelay;
+//! # impl DelayUs<u32> for HalDelay {
+//! #     fn delay_us(&mut self, _: u32) {}
+//! # }
+//! # let (spi, cs, rst, dc, delay) = (HalSpi, HalPin, HalPin, HalPin, HalDelay);
+//! use st7789::{eh02, SpiInterface, ST7789};
+//!
+//! let di = SpiInterface::new(eh02::Spi::with_cs(spi, cs), eh02::Pin(dc));
+//! let mut display = ST7789::new(di, Some(eh02::Pin(rst)), None, 240, 240);
+//! display.init(&mut eh02::Delay(delay)).unwrap();
+//! ```
+
+use core::convert::Infallible;
+use core::fmt::Debug;
+
+use embedded_hal::spi::{Operation, SpiDevice};
+use embedded_hal::{delay::DelayNs, digital, pwm::SetDutyCycle, spi as spi1};
+use embedded_hal_02::blocking::{delay::DelayUs, spi};
+use embedded_hal_02::digital::v2::OutputPin;
+use embedded_hal_02::PwmPin;
+
+///
+/// Error of an adapted peripheral, wrapping the 0.2 error.
+///
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum AdapterError<E> {
+    /// The wrapped peripheral failed
+    Hal(E),
+    /// Driving the chip select pin failed
+    ChipSelect,
+    /// The transaction asked for a delay, but the adapter has no delay provider
+    Unsupported,
+}
+
+impl<E: Debug> spi1::Error for AdapterError<E> {
+    fn kind(&self) -> spi1::ErrorKind {
+        spi1::ErrorKind::Other
+    }
+}
+
+impl<E: Debug> digital::Error for AdapterError<E> {
+    fn kind(&self) -> digital::ErrorKind {
+        digital::ErrorKind::Other
+    }
+}
+
+///
+/// Output pin adapter, see the [module docs](self).
+///
+pub struct Pin<P>(pub P);
+
+impl<P> digital::ErrorType for Pin<P>
+where
+    P: OutputPin,
+    P::Error: Debug,
+{
+    type Error = AdapterError<P::Error>;
+}
+
+impl<P> digital::OutputPin for Pin<P>
+where
+    P: OutputPin,
+    P::Error: Debug,
+{
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.0.set_low().map_err(AdapterError::Hal)
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.0.set_high().map_err(AdapterError::Hal)
+    }
+}
+
+///
+/// Stand-in for a chip select pin of buses where the SPI peripheral or the OS drives CS.
+///
+pub struct NoCs;
+
+impl OutputPin for NoCs {
+    type Error = Infallible;
+
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+///
+/// Delay type of an [`Spi`] adapter without a delay provider, it has no values.
+///
+/// The driver doesn't put delays into its transactions. A transaction with an
+/// [`Operation::DelayNs`] fails with [`AdapterError::Unsupported`], add a delay with
+/// [`Spi::with_delay`] for those.
+///
+pub enum NoDelay {}
+
+impl DelayNs for NoDelay {
+    fn delay_ns(&mut self, _: u32) {
+        match *self {}
+    }
+}
+
+///
+/// SPI device adapter, turning a 0.2 bus and an optional chip select pin into an
+/// embedded-hal 1.0 [`SpiDevice`].
+///
+/// CS is asserted for the duration of every transaction. The bus has to implement both
+/// [`spi::Write`] and [`spi::Transfer`] with the same error type.
+///
+pub struct Spi<SPI, CS = NoCs, D = NoDelay> {
+    spi: SPI,
+    cs: CS,
+    delay: Option<D>,
+}
+
+impl<SPI> Spi<SPI, NoCs> {
+    ///
+    /// Wraps a bus whose chip select is handled elsewhere
+    ///
+    pub fn new(spi: SPI) -> Self {
+        Self::with_cs(spi, NoCs)
+    }
+}
+
+impl<SPI, CS> Spi<SPI, CS> {
+    ///
+    /// Wraps a bus with its chip select pin
+    ///
+    pub fn with_cs(spi: SPI, cs: CS) -> Self {
+        Self {
+            spi,
+            cs,
+            delay: None,
+        }
+    }
+
+    ///
+    /// Adds a delay provider for transactions pausing between operations
+    ///
+    /// # Arguments
+    ///
+    /// * `delay` - delay provider, e.g. a 0.2 delay wrapped in [`Delay`]
+    ///
+    pub fn with_delay<D: DelayNs>(self, delay: D) -> Spi<SPI, CS, D> {
+        Spi {
+            spi: self.spi,
+            cs: self.cs,
+            delay: Some(delay),
+        }
+    }
+}
+
+impl<SPI, CS, D> Spi<SPI, CS, D> {
+    ///
+    /// Returns the bus and the chip select pin
+    ///
+    pub fn release(self) -> (SPI, CS) {
+        (self.spi, self.cs)
+    }
+}
+
+impl<SPI, CS, D, E> spi1::ErrorType for Spi<SPI, CS, D>
+where
+    SPI: spi::Write<u8, Error = E> + spi::Transfer<u8, Error = E>,
+    CS: OutputPin,
+    E: Debug,
+{
+    type Error = AdapterError<E>;
+}
+
+impl<SPI, CS, D, E> SpiDevice for Spi<SPI, CS, D>
+where
+    SPI: spi::Write<u8, Error = E> + spi::Transfer<u8, Error = E>,
+    CS: OutputPin,
+    D: DelayNs,
+    E: Debug,
+{
+    fn transaction(&mut self, operations: &mut [Operation<'_, u8>]) -> Result<(), Self::Error> {
+        self.cs.set_low().map_err(|_| AdapterError::ChipSelect)?;
+        let result = operations
+            .iter_mut()
+            .try_for_each(|operation| match operation {
+                Operation::Write(words) => self.spi.write(words).map_err(AdapterError::Hal),
+                Operation::Read(words) => {
+                    words.fill(0);
+                    self.spi
+                        .transfer(words)
+                        .map(drop)
+                        .map_err(AdapterError::Hal)
+                }
+                Operation::TransferInPlace(words) => self
+                    .spi
+                    .transfer(words)
+                    .map(drop)
+                    .map_err(AdapterError::Hal),
+                Operation::Transfer(read, write) => {
+                    // clock the longer of both buffers one byte at a time
+                    for i in 0..read.len().max(write.len()) {
+                        let mut word = [write.get(i).copied().unwrap_or(0)];
+                        let received = self.spi.transfer(&mut word).map_err(AdapterError::Hal)?;
+                        if let Some(byte) = read.get_mut(i) {
+                            *byte = received[0];
+                        }
+                    }
+                    Ok(())
+                }
+                Operation::DelayNs(ns) => match &mut self.delay {
+                    Some(delay) => {
+                        delay.delay_ns(*ns);
+                        Ok(())
+                    }
+                    None => Err(AdapterError::Unsupported),
+                },
+            });
+        let deselected = self.cs.set_high();
+
+        result?;
+        deselected.map_err(|_| AdapterError::ChipSelect)
+    }
+}
+
+///
+/// Delay adapter, see the [module docs](self).
+///
+/// Nanosecond delays are rounded up to whole microseconds.
+///
+pub struct Delay<D>(pub D);
+
+impl<D> DelayNs for Delay<D>
+where
+    D: DelayUs<u32>,
+{
+    fn delay_ns(&mut self, ns: u32) {
+        self.0.delay_us(ns.div_ceil(1_000));
+    }
+
+    fn delay_us(&mut self, us: u32) {
+        self.0.delay_us(us);
+    }
+}
+
+///
+/// PWM channel adapter for [`crate::PwmBacklight`], enabling the channel on creation.
+///
+/// Channels with a maximum duty above `u16::MAX` are scaled down to 16 bits.
+///
+pub struct Pwm<P>(P);
+
+impl<P> Pwm<P>
+where
+    P: PwmPin,
+{
+    ///
+    /// Wraps the channel and enables it
+    ///
+    pub fn new(mut pin: P) -> Self {
+        pin.enable();
+        Self(pin)
+    }
+
+    ///
+    /// Returns the PWM channel
+    ///
+    pub fn release(self) -> P {
+        self.0
+    }
+}
+
+impl<P> embedded_hal::pwm::ErrorType for Pwm<P>
+where
+    P: PwmPin,
+{
+    type Error = Infallible;
+}
+
+impl<P> SetDutyCycle for Pwm<P>
+where
+    P: PwmPin,
+    P::Duty: Into<u32> + TryFrom<u32>,
+{
+    fn max_duty_cycle(&self) -> u16 {
+        let max: u32 = self.0.get_max_duty().into();
+        max.min(u32::from(u16::MAX)) as u16
+    }
+
+    fn set_duty_cycle(&mut self, duty: u16) -> Result<(), Self::Error> {
+        let max: u32 = self.0.get_max_duty().into();
+        let scaled = u64::from(max) * u64::from(duty) / u64::from(self.max_duty_cycle().max(1));
+        if let Ok(duty) = P::Duty::try_from(scaled as u32) {
+            self.0.set_duty(duty);
+        }
+        Ok(())
+    }
+}
@@ -1,10 +1,34 @@
-/// ST7789 instructions.
-#[repr(u8)]
-pub enum Instruction {
+// Declares the instructions once, for the enum and for the mapping from opcodes back to it
+macro_rules! instructions {
+    ($($name:ident = $opcode:literal,)*) => {
+        /// ST7789 instructions.
+        #[repr(u8)]
+        #[derive(Copy, Clone, Debug, PartialEq, Eq)]
+        pub enum Instruction {
+            $($name = $opcode,)*
+        }
+
+        impl TryFrom<u8> for Instruction {
+            /// The unknown opcode
+            type Error = u8;
+
+            fn try_from(value: u8) -> Result<Self, Self::Error> {
+                match value {
+                    $($opcode => Ok(Self::$name),)*
+                    other => Err(other),
+                }
+            }
+        }
+    };
+}
+
+instructions! {
     NOP = 0x00,
     SWRESET = 0x01,
     RDDID = 0x04,
     RDDST = 0x09,
+    RDDPM = 0x0A,
+    RDDMADCTL = 0x0B,
     SLPIN = 0x10,
     SLPOUT = 0x11,
     PTLON = 0x12,
@@ -23,7 +47,15 @@ pub enum Instruction {
     TEON = 0x35,
     MADCTL = 0x36,
     VSCAD = 0x37,
+    IDMOFF = 0x38,
+    IDMON = 0x39,
     COLMOD = 0x3A,
+    RAMWRC = 0x3C,
+    RAMRDC = 0x3E,
+    WRDISBV = 0x51,
+    WRCTRLD = 0x53,
+    WRCACE = 0x55,
+    WRCABCMB = 0x5E,
     PORCTRL = 0xB2,
     VCMOFSET = 0xC5,
     GCTRL = 0xB7,
@@ -64,24 +96,59 @@ pub enum DataFormat<'a> {
 /// Display orientation.
 ///
 #[repr(u8)]
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
 pub enum Orientation {
-    Portrait = 0b0000_0000,         // no inverting
+    #[default]
+    Portrait = 0b0000_0000, // no inverting
     Landscape = 0b0110_0000,        // invert column and page/column order
     PortraitSwapped = 0b1100_0000,  // invert page and column order
     LandscapeSwapped = 0b1010_0000, // invert page and page/column order
 }
 
-impl Default for Orientation {
-    fn default() -> Self {
-        Self::Portrait
+///
+/// Interface pixel format, as set by COLMOD.
+///
+#[repr(u8)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PixelFormat {
+    /// 12 bits per pixel
+    Rgb444 = 0b011,
+    /// 16 bits per pixel
+    Rgb565 = 0b101,
+    /// 18 bits per pixel
+    Rgb666 = 0b110,
+}
+
+impl TryFrom<u8> for PixelFormat {
+    /// The unknown format bits
+    type Error = u8;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0b011 => Ok(Self::Rgb444),
+            0b101 => Ok(Self::Rgb565),
+            0b110 => Ok(Self::Rgb666),
+            other => Err(other),
+        }
     }
 }
 
+///
+/// Display mode, either the whole panel or only the partial area is refreshed.
+///
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum DisplayMode {
+    /// Whole panel shows the frame memory
+    #[default]
+    Normal,
+    /// Only the lines set by `set_partial_area` are shown
+    Partial,
+}
+
 ///
 /// Tearing effect output setting.
 ///
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum TearingEffect {
     /// Disable output.
     Off,
@@ -91,6 +158,77 @@ pub enum TearingEffect {
     HorizontalAndVertical,
 }
 
+impl TearingEffect {
+    // Command and parameters configuring the output
+    pub(crate) fn command(self) -> (Instruction, &'static [u8]) {
+        match self {
+            Self::Off => (Instruction::TEOFF, &[]),
+            Self::Vertical => (Instruction::TEON, &[0]),
+            Self::HorizontalAndVertical => (Instruction::TEON, &[1]),
+        }
+    }
+}
+
+///
+/// Content adaptive brightness control (CABC) mode, lowering the backlight for dark
+/// content to save power.
+///
+#[repr(u8)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum CabcMode {
+    /// Brightness is not adapted to the content
+    #[default]
+    Off = 0b00,
+    /// Light adaptation for user interfaces, keeping text readable
+    UserInterface = 0b01,
+    /// Medium adaptation for still pictures
+    StillPicture = 0b10,
+    /// Strongest adaptation for moving images
+    MovingImage = 0b11,
+}
+
+///
+/// Frame rate as set by RTNA in FRCTRL2 (and RTNB/RTNC in FRCTRL1), named after the refresh
+/// rate in Hz with the default porch of 12 lines each.
+///
+#[repr(u8)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum FrameRate {
+    Hz119 = 0x00,
+    Hz111 = 0x01,
+    Hz105 = 0x02,
+    Hz99 = 0x03,
+    Hz94 = 0x04,
+    Hz90 = 0x05,
+    Hz86 = 0x06,
+    Hz82 = 0x07,
+    Hz78 = 0x08,
+    Hz75 = 0x09,
+    Hz72 = 0x0A,
+    Hz69 = 0x0B,
+    Hz67 = 0x0C,
+    Hz64 = 0x0D,
+    Hz62 = 0x0E,
+    #[default]
+    Hz60 = 0x0F,
+    Hz58 = 0x10,
+    Hz57 = 0x11,
+    Hz55 = 0x12,
+    Hz53 = 0x13,
+    Hz52 = 0x14,
+    Hz50 = 0x15,
+    Hz49 = 0x16,
+    Hz48 = 0x17,
+    Hz46 = 0x18,
+    Hz45 = 0x19,
+    Hz44 = 0x1A,
+    Hz43 = 0x1B,
+    Hz42 = 0x1C,
+    Hz41 = 0x1D,
+    Hz40 = 0x1E,
+    Hz39 = 0x1F,
+}
+
 #[derive(Copy, Clone, Debug)]
 pub enum BacklightState {
     On,
@@ -105,6 +243,10 @@ pub enum BacklightState {
 pub enum Error<PinE> {
     DisplayError,
     Pin(PinE),
+    /// An argument is out of range for the panel or current orientation
+    InvalidArgument,
+    /// The display is not in a state allowing the operation, e.g. waking it while awake
+    InvalidState,
 }
 
 /// A ubiquitous error type for all kinds of problems which could happen when communicating with a
@@ -0,0 +1,292 @@
+use embedded_hal::digital::OutputPin;
+
+use crate::{DisplayInterface, DisplayMode, Error, FrameRate, Instruction, ST7789};
+
+// Refresh rate of every RTNA value with the default porch, from the datasheet
+const FRAME_RATE_HZ: [u8; 32] = [
+    119, 111, 105, 99, 94, 90, 86, 82, 78, 75, 72, 69, 67, 64, 62, 60, 58, 57, 55, 53, 52, 50, 49,
+    48, 46, 45, 44, 43, 42, 41, 40, 39,
+];
+// Frame rate of every RTNA value
+const FRAME_RATES: [FrameRate; 32] = [
+    FrameRate::Hz119,
+    FrameRate::Hz111,
+    FrameRate::Hz105,
+    FrameRate::Hz99,
+    FrameRate::Hz94,
+    FrameRate::Hz90,
+    FrameRate::Hz86,
+    FrameRate::Hz82,
+    FrameRate::Hz78,
+    FrameRate::Hz75,
+    FrameRate::Hz72,
+    FrameRate::Hz69,
+    FrameRate::Hz67,
+    FrameRate::Hz64,
+    FrameRate::Hz62,
+    FrameRate::Hz60,
+    FrameRate::Hz58,
+    FrameRate::Hz57,
+    FrameRate::Hz55,
+    FrameRate::Hz53,
+    FrameRate::Hz52,
+    FrameRate::Hz50,
+    FrameRate::Hz49,
+    FrameRate::Hz48,
+    FrameRate::Hz46,
+    FrameRate::Hz45,
+    FrameRate::Hz44,
+    FrameRate::Hz43,
+    FrameRate::Hz42,
+    FrameRate::Hz41,
+    FrameRate::Hz40,
+    FrameRate::Hz39,
+];
+// Lines scanned per frame with the default porch
+const DEFAULT_FRAME_LINES: u32 = 320 + 12 + 12;
+// FRCTRL1 separate frame rate enable bit
+const FRSEN: u8 = 0b0001_0000;
+// PORCTRL separate porch enable bit
+const PSEN: u8 = 0b0000_0001;
+
+impl FrameRate {
+    ///
+    /// Creates the frame rate from the RTNA bits, ignoring the inversion selection bits
+    ///
+    pub const fn from_bits(bits: u8) -> Self {
+        FRAME_RATES[(bits & 0x1F) as usize]
+    }
+
+    ///
+    /// Returns the refresh rate in Hz with the default porch
+    ///
+    pub const fn hz(self) -> u8 {
+        FRAME_RATE_HZ[self as usize]
+    }
+
+    ///
+    /// Returns the frame rate closest to the given refresh rate in Hz
+    ///
+    /// # Arguments
+    ///
+    /// * `hz` - refresh rate, clamped to 39 to 119 Hz
+    ///
+    pub fn from_hz(hz: u8) -> Self {
+        let mut best = 0;
+        for (bits, rate) in FRAME_RATE_HZ.iter().enumerate() {
+            if rate.abs_diff(hz) < FRAME_RATE_HZ[best].abs_diff(hz) {
+                best = bits;
+            }
+        }
+        Self::from_bits(best as u8)
+    }
+}
+
+///
+/// Number of blank lines before (back porch) and after (front porch) the panel lines in
+/// every frame. Longer porches lower the refresh rate.
+///
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Porch {
+    /// Lines before the first panel line
+    pub back: u8,
+    /// Lines after the last panel line
+    pub front: u8,
+}
+
+impl Porch {
+    ///
+    /// Creates a new porch
+    ///
+    /// # Arguments
+    ///
+    /// * `back` - lines before the first panel line
+    /// * `front` - lines after the last panel line
+    ///
+    pub const fn new(back: u8, front: u8) -> Self {
+        Self { back, front }
+    }
+}
+
+impl Default for Porch {
+    fn default() -> Self {
+        Self::new(12, 12)
+    }
+}
+
+///
+/// Porch setting (PORCTRL) for normal, idle and partial mode.
+///
+/// The normal mode porch can be 1 to 127 lines. Idle and partial mode use it too unless
+/// `separate` is set, in which case they use their own porch of 4 to 60 lines in steps
+/// of 4.
+///
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct PorchConfig {
+    /// Porch in normal mode
+    pub normal: Porch,
+    /// Porch in idle mode when `separate` is set
+    pub idle: Porch,
+    /// Porch in partial mode when `separate` is set
+    pub partial: Porch,
+    /// Use the idle and partial mode porches
+    pub separate: bool,
+}
+
+impl PorchConfig {
+    ///
+    /// Creates a setting using the same porch in every mode
+    ///
+    pub fn new(normal: Porch) -> Self {
+        Self {
+            normal,
+            ..Self::default()
+        }
+    }
+
+    ///
+    /// Creates a setting from the 5 register bytes
+    ///
+    pub fn from_bytes(bytes: [u8; 5]) -> Self {
+        let nibbles = |byte: u8| Porch::new((byte >> 4) * 4, (byte & 0x0F) * 4);
+
+        Self {
+            normal: Porch::new(bytes[0] & 0x7F, bytes[1] & 0x7F),
+            idle: nibbles(bytes[3]),
+            partial: nibbles(bytes[4]),
+            separate: bytes[2] & PSEN != 0,
+        }
+    }
+
+    ///
+    /// Returns the 5 register bytes, `None` if a porch is out of range
+    ///
+    pub fn to_bytes(&self) -> Option<[u8; 5]> {
+        let normal = |lines: u8| (1..=127).contains(&lines);
+        let nibble = |lines: u8| ((4..=60).contains(&lines) && lines % 4 == 0).then_some(lines / 4);
+        let nibbles = |porch: Porch| Some((nibble(porch.back)? << 4) | nibble(porch.front)?);
+
+        if !normal(self.normal.back) || !normal(self.normal.front) {
+            return None;
+        }
+        Some([
+            self.normal.back,
+            self.normal.front,
+            if self.separate { PSEN } else { 0 },
+            nibbles(self.idle)?,
+            nibbles(self.partial)?,
+        ])
+    }
+
+    // Porch used in the given mode
+    fn porch(&self, idle: bool, mode: DisplayMode) -> Porch {
+        match (self.separate, idle, mode) {
+            (true, true, _) => self.idle,
+            (true, false, DisplayMode::Partial) => self.partial,
+            _ => self.normal,
+        }
+    }
+}
+
+// Frame rate and porch settings as tracked by the driver
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct FrameTiming {
+    pub(crate) normal: FrameRate,
+    // idle and partial mode frame rates if FRSEN is set
+    pub(crate) separate: Option<(FrameRate, FrameRate)>,
+    pub(crate) porch: PorchConfig,
+}
+
+impl FrameTiming {
+    // Refresh rate in Hz with idle mode on or off in the given display mode
+    pub(crate) fn refresh_rate(&self, idle: bool, mode: DisplayMode) -> u16 {
+        let rate = match (self.separate, idle, mode) {
+            (Some((idle_rate, _)), true, _) => idle_rate,
+            (Some((_, partial_rate)), false, DisplayMode::Partial) => partial_rate,
+            _ => self.normal,
+        };
+        let porch = self.porch.porch(idle, mode);
+        let lines = 320 + u32::from(porch.back) + u32::from(porch.front);
+
+        ((u32::from(rate.hz()) * DEFAULT_FRAME_LINES + lines / 2) / lines) as u16
+    }
+}
+
+impl<DI, OUT, PinE> ST7789<DI, OUT>
+where
+    DI: DisplayInterface,
+    OUT: OutputPin<Error = PinE>,
+{
+    ///
+    /// Sets the normal mode frame rate (FRCTRL2). Lower rates save power, matching the
+    /// rate to the render loop reduces tearing.
+    ///
+    /// # Arguments
+    ///
+    /// * `rate` - frame rate, 60 Hz after reset
+    ///
+    pub fn set_frame_rate(&mut self, rate: FrameRate) -> Result<(), Error<PinE>> {
+        let params = [rate as u8];
+        self.send_command(Instruction::FRCTRL2, &params)?;
+        self.state.track_command(Instruction::FRCTRL2, &params);
+        Ok(())
+    }
+
+    ///
+    /// Sets separate frame rates for idle and partial mode (FRCTRL1), which otherwise use
+    /// the normal mode frame rate
+    ///
+    /// # Arguments
+    ///
+    /// * `idle` - frame rate in idle mode
+    /// * `partial` - frame rate in partial mode
+    ///
+    pub fn set_idle_partial_frame_rates(
+        &mut self,
+        idle: FrameRate,
+        partial: FrameRate,
+    ) -> Result<(), Error<PinE>> {
+        let params = [FRSEN, idle as u8, partial as u8];
+        self.send_command(Instruction::FRCTRL1, &params)?;
+        self.state.track_command(Instruction::FRCTRL1, &params);
+        Ok(())
+    }
+
+    ///
+    /// Sets the porch (PORCTRL).
+    /// Fails with [`Error::InvalidArgument`] before sending anything if a porch is out
+    /// of range.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - porch for normal, idle and partial mode
+    ///
+    pub fn set_porch(&mut self, config: PorchConfig) -> Result<(), Error<PinE>> {
+        let params = config.to_bytes().ok_or(Error::InvalidArgument)?;
+        self.send_command(Instruction::PORCTRL, &params)?;
+        self.state.track_command(Instruction::PORCTRL, &params);
+        Ok(())
+    }
+
+    ///
+    /// Returns the normal mode frame rate
+    ///
+    pub fn frame_rate(&self) -> FrameRate {
+        self.state.frame.normal
+    }
+
+    ///
+    /// Returns the porch setting
+    ///
+    pub fn porch(&self) -> PorchConfig {
+        self.state.frame.porch
+    }
+
+    ///
+    /// Returns the refresh rate in Hz the display runs at in its current mode, taking
+    /// idle and partial mode frame rates and the porch into account
+    ///
+    pub fn refresh_rate(&self) -> u16 {
+        self.state.refresh_rate()
+    }
+}
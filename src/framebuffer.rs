@@ -0,0 +1,301 @@
+//! Offscreen framebuffer with dirty-rectangle tracking.
+//!
+//! Drawing into a [`Framebuffer`] never touches the bus. Every draw call records the
+//! bounding box of the pixels it changed and [`Framebuffer::flush`] sends only those
+//! regions to the display, which avoids flicker when redrawing charts or text labels.
+use embedded_graphics_core::pixelcolor::raw::RawU16;
+use embedded_graphics_core::pixelcolor::Rgb565;
+use embedded_graphics_core::prelude::{
+    DrawTarget, IntoStorage, OriginDimensions, Point, PointsIter, Size,
+};
+use embedded_graphics_core::primitives::Rectangle;
+use embedded_graphics_core::Pixel;
+use embedded_hal::digital::OutputPin;
+
+use crate::{DisplayInterface, Error, ST7789};
+
+/// Max number of separate dirty rectangles tracked before they get merged
+const MAX_DIRTY: usize = 8;
+
+/// Inclusive pixel bounds of a changed region
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct DirtyRect {
+    /// Start column number
+    pub x_left: u16,
+    /// End column number
+    pub x_right: u16,
+    /// Start row number
+    pub y_top: u16,
+    /// End row number
+    pub y_bottom: u16,
+}
+
+impl DirtyRect {
+    fn union(&self, other: &DirtyRect) -> DirtyRect {
+        DirtyRect {
+            x_left: self.x_left.min(other.x_left),
+            x_right: self.x_right.max(other.x_right),
+            y_top: self.y_top.min(other.y_top),
+            y_bottom: self.y_bottom.max(other.y_bottom),
+        }
+    }
+
+    /// True if the rectangles overlap or share an edge
+    fn touches(&self, other: &DirtyRect) -> bool {
+        self.x_left <= other.x_right.saturating_add(1)
+            && other.x_left <= self.x_right.saturating_add(1)
+            && self.y_top <= other.y_bottom.saturating_add(1)
+            && other.y_top <= self.y_bottom.saturating_add(1)
+    }
+
+    fn area(&self) -> u32 {
+        (self.x_right - self.x_left + 1) as u32 * (self.y_bottom - self.y_top + 1) as u32
+    }
+}
+
+///
+/// Offscreen RGB565 framebuffer of `W`x`H` pixels.
+///
+/// The buffer is large (`W * H * 2` bytes), so it is usually placed in a `static`
+/// rather than on the stack.
+///
+pub struct Framebuffer<const W: usize, const H: usize> {
+    // Pixel data, row by row
+    pixels: [[u16; W]; H],
+    // Regions changed since the last flush
+    dirty: [Option<DirtyRect>; MAX_DIRTY],
+}
+
+impl<const W: usize, const H: usize> Framebuffer<W, H> {
+    // Pixel coordinates are kept as u16, so neither side may exceed 65536 pixels
+    const SIZE: () = assert!(
+        W <= 1 << 16 && H <= 1 << 16,
+        "the framebuffer is at most 65536 pixels wide and high"
+    );
+
+    ///
+    /// Creates a new black framebuffer with nothing marked dirty
+    ///
+    pub const fn new() -> Self {
+        let () = Self::SIZE;
+        Self {
+            pixels: [[0; W]; H],
+            dirty: [None; MAX_DIRTY],
+        }
+    }
+
+    ///
+    /// Returns the color at the given coords or `None` if they lie outside the buffer
+    ///
+    pub fn pixel(&self, x: usize, y: usize) -> Option<Rgb565> {
+        self.pixels
+            .get(y)
+            .and_then(|row| row.get(x))
+            .map(|&raw| RawU16::new(raw).into())
+    }
+
+    ///
+    /// Returns the raw pixel rows
+    ///
+    pub fn buffer(&self) -> &[[u16; W]; H] {
+        &self.pixels
+    }
+
+    ///
+    /// Returns true if anything was drawn since the last flush
+    ///
+    pub fn is_dirty(&self) -> bool {
+        self.dirty.iter().any(Option::is_some)
+    }
+
+    ///
+    /// Returns the regions that will be sent by the next flush
+    ///
+    pub fn dirty_rects(&self) -> impl Iterator<Item = DirtyRect> + '_ {
+        self.dirty.iter().flatten().copied()
+    }
+
+    ///
+    /// Marks the whole buffer as changed, e.g. after the display lost its contents
+    ///
+    pub fn mark_all_dirty(&mut self) {
+        self.dirty = [None; MAX_DIRTY];
+        if W > 0 && H > 0 {
+            self.mark_dirty(DirtyRect {
+                x_left: 0,
+                x_right: (W - 1) as u16,
+                y_top: 0,
+                y_bottom: (H - 1) as u16,
+            });
+        }
+    }
+
+    ///
+    /// Sends all changed regions to the display and clears the dirty list.
+    /// The framebuffer is mapped to the display starting at (0, 0).
+    ///
+    /// # Arguments
+    ///
+    /// * `display` - the display to send the changed regions to
+    ///
+    pub fn flush<DI, OUT, PinE>(&mut self, display: &mut ST7789<DI, OUT>) -> Result<(), Error<PinE>>
+    where
+        DI: DisplayInterface,
+        OUT: OutputPin<Error = PinE>,
+    {
+        for slot in self.dirty.iter_mut() {
+            if let Some(rect) = slot.take() {
+                let pixels = &self.pixels;
+                let colors = (rect.y_top..=rect.y_bottom).flat_map(|y| {
+                    pixels[y as usize][rect.x_left as usize..=rect.x_right as usize]
+                        .iter()
+                        .copied()
+                });
+                if let Err(e) =
+                    display.set_pixels(rect.x_left, rect.y_top, rect.x_right, rect.y_bottom, colors)
+                {
+                    // keep the region so a later flush can retry it
+                    *slot = Some(rect);
+                    return Err(e);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    // Records a changed region, merging it with the ones it touches
+    fn mark_dirty(&mut self, rect: DirtyRect) {
+        let mut rect = rect;
+        // absorb every region the new one touches, repeating as the union grows
+        loop {
+            let mut merged = false;
+            for slot in self.dirty.iter_mut() {
+                if let Some(other) = slot {
+                    if rect.touches(other) {
+                        rect = rect.union(other);
+                        *slot = None;
+                        merged = true;
+                    }
+                }
+            }
+            if !merged {
+                break;
+            }
+        }
+
+        if let Some(slot) = self.dirty.iter_mut().find(|slot| slot.is_none()) {
+            *slot = Some(rect);
+            return;
+        }
+
+        // no free slot, grow the region that gets the smallest increase in area
+        let growth = |other: &DirtyRect| other.union(&rect).area() - other.area();
+        if let Some(slot) = self
+            .dirty
+            .iter_mut()
+            .min_by_key(|slot| slot.as_ref().map_or(u32::MAX, growth))
+        {
+            *slot = slot.map(|other| other.union(&rect));
+        }
+    }
+
+    // Clips a rectangle to the buffer, returning it as inclusive bounds
+    fn clip(&self, area: &Rectangle) -> Option<DirtyRect> {
+        let area = area.intersection(&Rectangle::new(Point::zero(), self.size()));
+        area.bottom_right().map(|bottom_right| DirtyRect {
+            x_left: area.top_left.x as u16,
+            x_right: bottom_right.x as u16,
+            y_top: area.top_left.y as u16,
+            y_bottom: bottom_right.y as u16,
+        })
+    }
+}
+
+impl<const W: usize, const H: usize> Default for Framebuffer<W, H> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const W: usize, const H: usize> DrawTarget for Framebuffer<W, H> {
+    type Error = core::convert::Infallible;
+    type Color = Rgb565;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        let mut changed: Option<DirtyRect> = None;
+
+        for Pixel(coord, color) in pixels {
+            if coord.x < 0 || coord.y < 0 || coord.x as usize >= W || coord.y as usize >= H {
+                continue;
+            }
+            let (x, y) = (coord.x as u16, coord.y as u16);
+            self.pixels[y as usize][x as usize] = color.into_storage();
+
+            let point = DirtyRect {
+                x_left: x,
+                x_right: x,
+                y_top: y,
+                y_bottom: y,
+            };
+            changed = Some(changed.map_or(point, |rect| rect.union(&point)));
+        }
+
+        if let Some(rect) = changed {
+            self.mark_dirty(rect);
+        }
+
+        Ok(())
+    }
+
+    fn fill_contiguous<I>(&mut self, area: &Rectangle, colors: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Self::Color>,
+    {
+        if let Some(rect) = self.clip(area) {
+            for (point, color) in area.points().zip(colors) {
+                if point.x >= rect.x_left as i32
+                    && point.x <= rect.x_right as i32
+                    && point.y >= rect.y_top as i32
+                    && point.y <= rect.y_bottom as i32
+                {
+                    self.pixels[point.y as usize][point.x as usize] = color.into_storage();
+                }
+            }
+            self.mark_dirty(rect);
+        }
+
+        Ok(())
+    }
+
+    fn fill_solid(&mut self, area: &Rectangle, color: Self::Color) -> Result<(), Self::Error> {
+        if let Some(rect) = self.clip(area) {
+            let raw = color.into_storage();
+            for row in &mut self.pixels[rect.y_top as usize..=rect.y_bottom as usize] {
+                row[rect.x_left as usize..=rect.x_right as usize].fill(raw);
+            }
+            self.mark_dirty(rect);
+        }
+
+        Ok(())
+    }
+
+    fn clear(&mut self, color: Self::Color) -> Result<(), Self::Error> {
+        let raw = color.into_storage();
+        for row in self.pixels.iter_mut() {
+            row.fill(raw);
+        }
+        self.mark_all_dirty();
+
+        Ok(())
+    }
+}
+
+impl<const W: usize, const H: usize> OriginDimensions for Framebuffer<W, H> {
+    fn size(&self) -> Size {
+        Size::new(W as u32, H as u32)
+    }
+}
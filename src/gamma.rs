@@ -0,0 +1,199 @@
+use embedded_hal::digital::OutputPin;
+
+use crate::{DisplayInterface, Error, Instruction, ST7789};
+
+///
+/// Voltage gamma curve as set by PVGAMCTRL (positive polarity) and NVGAMCTRL (negative
+/// polarity).
+///
+/// Each field is a datasheet coefficient: `v0` to `v63` adjust the gray scale voltage
+/// levels, `j0` and `j1` the interpolation between them. [`GammaCurve::to_bytes`] checks
+/// every coefficient fits its bit range.
+///
+/// ```
+/// use st7789::GammaCurve;
+///
+/// // lift the dark end of the default curve a little
+/// let positive = GammaCurve {
+///     v1: 6,
+///     ..GammaCurve::DEFAULT_POSITIVE
+/// };
+/// assert!(positive.to_bytes().is_some());
+/// assert!(GammaCurve { v1: 64, ..positive }.to_bytes().is_none());
+/// ```
+///
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct GammaCurve {
+    /// Gray scale voltage level 63, 4 bits
+    pub v63: u8,
+    /// Gray scale voltage level 0, 4 bits
+    pub v0: u8,
+    /// Gray scale voltage level 1, 6 bits
+    pub v1: u8,
+    /// Gray scale voltage level 2, 6 bits
+    pub v2: u8,
+    /// Gray scale voltage level 4, 5 bits
+    pub v4: u8,
+    /// Gray scale voltage level 6, 5 bits
+    pub v6: u8,
+    /// Interpolation at the dark end, 2 bits
+    pub j0: u8,
+    /// Gray scale voltage level 13, 4 bits
+    pub v13: u8,
+    /// Gray scale voltage level 20, 7 bits
+    pub v20: u8,
+    /// Gray scale voltage level 36, 3 bits
+    pub v36: u8,
+    /// Gray scale voltage level 27, 3 bits
+    pub v27: u8,
+    /// Gray scale voltage level 43, 7 bits
+    pub v43: u8,
+    /// Interpolation at the bright end, 2 bits
+    pub j1: u8,
+    /// Gray scale voltage level 50, 4 bits
+    pub v50: u8,
+    /// Gray scale voltage level 57, 5 bits
+    pub v57: u8,
+    /// Gray scale voltage level 59, 5 bits
+    pub v59: u8,
+    /// Gray scale voltage level 61, 6 bits
+    pub v61: u8,
+    /// Gray scale voltage level 62, 6 bits
+    pub v62: u8,
+}
+
+impl GammaCurve {
+    /// Positive curve of [`crate::InitSequence::DEFAULT`] and [`crate::InitSequence::LEGACY`]
+    pub const DEFAULT_POSITIVE: Self = Self::from_bytes([
+        0xd0, 0x04, 0x0d, 0x11, 0x13, 0x2b, 0x3f, 0x54, 0x4c, 0x18, 0x0d, 0x0b, 0x1f, 0x23,
+    ]);
+    /// Negative curve of [`crate::InitSequence::DEFAULT`] and [`crate::InitSequence::LEGACY`]
+    pub const DEFAULT_NEGATIVE: Self = Self::from_bytes([
+        0xd0, 0x04, 0x0c, 0x11, 0x13, 0x2c, 0x3f, 0x44, 0x51, 0x2f, 0x1f, 0x1f, 0x20, 0x23,
+    ]);
+    /// Positive curve the controller resets to, kept by [`crate::InitSequence::MINIMAL`]
+    pub const RESET_POSITIVE: Self = Self::from_bytes([
+        0xd0, 0x00, 0x02, 0x07, 0x0a, 0x28, 0x32, 0x44, 0x42, 0x06, 0x0e, 0x12, 0x14, 0x17,
+    ]);
+    /// Negative curve the controller resets to, kept by [`crate::InitSequence::MINIMAL`]
+    pub const RESET_NEGATIVE: Self = Self::from_bytes([
+        0xd0, 0x00, 0x02, 0x07, 0x0a, 0x28, 0x31, 0x54, 0x47, 0x0e, 0x1c, 0x17, 0x1b, 0x1e,
+    ]);
+    /// Positive curve of many module vendors' reference code, close to a gamma of 2.2
+    pub const GAMMA_2_2_POSITIVE: Self = Self::from_bytes([
+        0xd0, 0x00, 0x05, 0x0e, 0x15, 0x0d, 0x37, 0x43, 0x47, 0x09, 0x15, 0x12, 0x16, 0x19,
+    ]);
+    /// Negative curve of many module vendors' reference code, close to a gamma of 2.2
+    pub const GAMMA_2_2_NEGATIVE: Self = Self::from_bytes([
+        0xd0, 0x00, 0x05, 0x0d, 0x0c, 0x06, 0x2d, 0x44, 0x40, 0x0e, 0x1c, 0x18, 0x16, 0x19,
+    ]);
+
+    ///
+    /// Creates a curve from the 14 register bytes, ignoring unused bits
+    ///
+    pub const fn from_bytes(bytes: [u8; 14]) -> Self {
+        Self {
+            v63: bytes[0] >> 4,
+            v0: bytes[0] & 0x0F,
+            v1: bytes[1] & 0x3F,
+            v2: bytes[2] & 0x3F,
+            v4: bytes[3] & 0x1F,
+            v6: bytes[4] & 0x1F,
+            j0: (bytes[5] >> 4) & 0x03,
+            v13: bytes[5] & 0x0F,
+            v20: bytes[6] & 0x7F,
+            v36: (bytes[7] >> 4) & 0x07,
+            v27: bytes[7] & 0x07,
+            v43: bytes[8] & 0x7F,
+            j1: (bytes[9] >> 4) & 0x03,
+            v50: bytes[9] & 0x0F,
+            v57: bytes[10] & 0x1F,
+            v59: bytes[11] & 0x1F,
+            v61: bytes[12] & 0x3F,
+            v62: bytes[13] & 0x3F,
+        }
+    }
+
+    ///
+    /// Returns the 14 register bytes, `None` if a coefficient exceeds its bit range
+    ///
+    pub const fn to_bytes(&self) -> Option<[u8; 14]> {
+        if !self.is_valid() {
+            return None;
+        }
+
+        Some([
+            (self.v63 << 4) | self.v0,
+            self.v1,
+            self.v2,
+            self.v4,
+            self.v6,
+            (self.j0 << 4) | self.v13,
+            self.v20,
+            (self.v36 << 4) | self.v27,
+            self.v43,
+            (self.j1 << 4) | self.v50,
+            self.v57,
+            self.v59,
+            self.v61,
+            self.v62,
+        ])
+    }
+
+    ///
+    /// Returns true if every coefficient fits its bit range
+    ///
+    pub const fn is_valid(&self) -> bool {
+        const fn fits(value: u8, bits: u32) -> bool {
+            (value as u32) < (1 << bits)
+        }
+
+        fits(self.v63, 4)
+            && fits(self.v0, 4)
+            && fits(self.v1, 6)
+            && fits(self.v2, 6)
+            && fits(self.v4, 5)
+            && fits(self.v6, 5)
+            && fits(self.j0, 2)
+            && fits(self.v13, 4)
+            && fits(self.v20, 7)
+            && fits(self.v36, 3)
+            && fits(self.v27, 3)
+            && fits(self.v43, 7)
+            && fits(self.j1, 2)
+            && fits(self.v50, 4)
+            && fits(self.v57, 5)
+            && fits(self.v59, 5)
+            && fits(self.v61, 6)
+            && fits(self.v62, 6)
+    }
+}
+
+impl<DI, OUT, PinE> ST7789<DI, OUT>
+where
+    DI: DisplayInterface,
+    OUT: OutputPin<Error = PinE>,
+{
+    ///
+    /// Sets the positive and negative voltage gamma curves.
+    /// Fails with [`Error::InvalidArgument`] before sending anything if a coefficient is
+    /// out of range.
+    ///
+    /// # Arguments
+    ///
+    /// * `positive` - curve for positive polarity (PVGAMCTRL)
+    /// * `negative` - curve for negative polarity (NVGAMCTRL)
+    ///
+    pub fn set_gamma(
+        &mut self,
+        positive: &GammaCurve,
+        negative: &GammaCurve,
+    ) -> Result<(), Error<PinE>> {
+        let (Some(positive), Some(negative)) = (positive.to_bytes(), negative.to_bytes()) else {
+            return Err(Error::InvalidArgument);
+        };
+
+        self.send_command(Instruction::PVGAMCTRL, &positive)?;
+        self.send_command(Instruction::NVGAMCTRL, &negative)
+    }
+}
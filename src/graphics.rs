@@ -1,36 +1,42 @@
+use core::marker::PhantomData;
+
 use embedded_graphics_core::pixelcolor::Rgb565;
-use embedded_graphics_core::prelude::{DrawTarget, IntoStorage, Point, Size};
+use embedded_graphics_core::prelude::{DrawTarget, IntoStorage, Point, RgbColor, Size};
 use embedded_graphics_core::{
     pixelcolor::raw::{RawData, RawU16},
     primitives::Rectangle,
 };
 use embedded_graphics_core::{prelude::OriginDimensions, Pixel};
 
-use embedded_hal::digital::v2::OutputPin;
+use embedded_hal::digital::OutputPin;
 
-use crate::{Error, Orientation, ST7789};
+use crate::{DisplayInterface, Error, Instruction, ST7789};
 // use display_interface::WriteOnlyDataCommand;
-use embedded_hal::blocking::spi;
 
-impl<SPI, OUT, PinE> ST7789<SPI, OUT>
+// Solid fills are encoded into a buffer of a 240 pixel RGB565 row, which holds whole
+// pixel pairs in every pixel format
+const FILL_BUFFER_LEN: usize = 480;
+
+impl<DI, OUT, PinE> ST7789<DI, OUT>
 where
-    SPI: spi::Write<u8>,
+    DI: DisplayInterface,
     OUT: OutputPin<Error = PinE>,
 {
     /// Returns the bounding box for the entire framebuffer.
     fn framebuffer_bounding_box(&self) -> Rectangle {
-        let size = match self.orientation {
-            Orientation::Portrait | Orientation::PortraitSwapped => Size::new(240, 320),
-            Orientation::Landscape | Orientation::LandscapeSwapped => Size::new(320, 240),
+        let size = if self.state.madctl.is_exchanged() {
+            Size::new(320, 240)
+        } else {
+            Size::new(240, 320)
         };
 
         Rectangle::new(Point::zero(), size)
     }
 }
 
-impl<SPI, OUT, PinE> DrawTarget for ST7789<SPI, OUT>
+impl<DI, OUT, PinE> DrawTarget for ST7789<DI, OUT>
 where
-    SPI: spi::Write<u8>,
+    DI: DisplayInterface,
     OUT: OutputPin<Error = PinE>,
 {
     type Error = Error<PinE>;
@@ -89,70 +95,274 @@ where
         }
     }
 
+    ///
+    /// Fills the area through a buffer on the stack holding a row of pixels, see
+    /// [`ST7789::scratch_target`] for a larger one
+    ///
     fn fill_solid(&mut self, area: &Rectangle, color: Self::Color) -> Result<(), Self::Error> {
-        let area = area.intersection(&self.framebuffer_bounding_box());
+        self.scratch_target(&mut [0; FILL_BUFFER_LEN])
+            .fill_solid(area, color)
+    }
 
-        if let Some(bottom_right) = area.bottom_right() {
-            let mut count = 0u32;
-            let max = area.size.width * area.size.height;
+    ///
+    /// Clears the whole frame memory through a buffer on the stack holding a row of
+    /// pixels, see [`ST7789::scratch_target`] for a larger one
+    ///
+    fn clear(&mut self, color: Rgb565) -> Result<(), Self::Error>
+    where
+        Self: Sized,
+    {
+        self.scratch_target(&mut [0; FILL_BUFFER_LEN]).clear(color)
+    }
+}
 
-            let mut colors = core::iter::repeat(color.into_storage()).take_while(|_| {
-                count += 1;
-                count <= max
-            });
+impl<DI, OUT, PinE> OriginDimensions for ST7789<DI, OUT>
+where
+    DI: DisplayInterface,
+    OUT: OutputPin<Error = PinE>,
+{
+    fn size(&self) -> Size {
+        let (size_x, size_y) = self.state.panel.size(self.state.madctl);
+        Size::new(size_x.into(), size_y.into()) // visible area, not RAM-pixel size
+    }
+}
 
-            let sx = area.top_left.x as u16;
-            let sy = area.top_left.y as u16;
-            let ex = bottom_right.x as u16;
-            let ey = bottom_right.y as u16;
-            self.set_pixels(sx, sy, ex, ey, &mut colors)
+///
+/// Draw target for colors other than [`Rgb565`], see [`ST7789::color_target`].
+///
+/// Colors are sent with the precision of the active [`crate::PixelFormat`], so drawing
+/// [`embedded_graphics_core::pixelcolor::Rgb666`] or `Rgb888` in RGB666 mode keeps
+/// 6 bits for every channel.
+///
+pub struct ColorTarget<'a, DI, OUT, C>
+where
+    DI: DisplayInterface,
+    OUT: OutputPin,
+{
+    display: &'a mut ST7789<DI, OUT>,
+    color: PhantomData<C>,
+}
+
+impl<DI, OUT, PinE> ST7789<DI, OUT>
+where
+    DI: DisplayInterface,
+    OUT: OutputPin<Error = PinE>,
+{
+    ///
+    /// Returns a draw target accepting RGB colors of type `C`, e.g. `Rgb666` or `Rgb888`
+    ///
+    pub fn color_target<C>(&mut self) -> ColorTarget<'_, DI, OUT, C>
+    where
+        C: RgbColor,
+    {
+        ColorTarget {
+            display: self,
+            color: PhantomData,
+        }
+    }
+}
+
+impl<DI, OUT, PinE, C> DrawTarget for ColorTarget<'_, DI, OUT, C>
+where
+    DI: DisplayInterface,
+    OUT: OutputPin<Error = PinE>,
+    C: RgbColor,
+{
+    type Error = Error<PinE>;
+    type Color = C;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        let bounds = self.display.framebuffer_bounding_box();
+        for Pixel(point, color) in pixels {
+            if bounds.contains(point) {
+                let (x, y) = (point.x as u16, point.y as u16);
+                self.display.set_address_window(x, y, x, y)?;
+                self.display.write_command(Instruction::RAMWR)?;
+                self.display
+                    .write_pixels_rgb888(core::iter::once(rgb888(color)))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn fill_contiguous<I>(&mut self, area: &Rectangle, colors: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Self::Color>,
+    {
+        if let Some(bottom_right) = area.bottom_right() {
+            let count = (area.size.width * area.size.height) as usize;
+            let colors = colors.into_iter().take(count).map(rgb888);
+
+            self.display.set_address_window(
+                area.top_left.x as u16,
+                area.top_left.y as u16,
+                bottom_right.x as u16,
+                bottom_right.y as u16,
+            )?;
+            self.display.write_command(Instruction::RAMWR)?;
+            self.display.write_pixels_rgb888(colors)
         } else {
             // nothing to draw
             Ok(())
         }
     }
 
-    fn clear(&mut self, color: Rgb565) -> Result<(), Self::Error>
+    fn fill_solid(&mut self, area: &Rectangle, color: Self::Color) -> Result<(), Self::Error> {
+        let area = area.intersection(&self.display.framebuffer_bounding_box());
+        let count = (area.size.width * area.size.height) as usize;
+        self.fill_contiguous(&area, core::iter::repeat(color).take(count))
+    }
+}
+
+impl<DI, OUT, PinE, C> OriginDimensions for ColorTarget<'_, DI, OUT, C>
+where
+    DI: DisplayInterface,
+    OUT: OutputPin<Error = PinE>,
+{
+    fn size(&self) -> Size {
+        self.display.size()
+    }
+}
+
+///
+/// Draw target encoding fills through a caller supplied scratch buffer, see
+/// [`ST7789::scratch_target`].
+///
+/// `fill_contiguous` and `fill_solid` go out in bus writes as large as the buffer, with a
+/// big enough one a full-screen clear is limited by the bus speed only. Single pixels are
+/// drawn like on the display itself.
+///
+pub struct ScratchTarget<'a, DI, OUT>
+where
+    DI: DisplayInterface,
+    OUT: OutputPin,
+{
+    display: &'a mut ST7789<DI, OUT>,
+    buffer: &'a mut [u8],
+}
+
+impl<DI, OUT, PinE> ST7789<DI, OUT>
+where
+    DI: DisplayInterface,
+    OUT: OutputPin<Error = PinE>,
+{
+    ///
+    /// Returns a draw target filling areas through a scratch buffer
+    ///
+    /// # Arguments
+    ///
+    /// * `buffer` - scratch buffer of any size holding at least two pixels
+    ///
+    pub fn scratch_target<'a>(&'a mut self, buffer: &'a mut [u8]) -> ScratchTarget<'a, DI, OUT> {
+        ScratchTarget {
+            display: self,
+            buffer,
+        }
+    }
+}
+
+impl<DI, OUT, PinE> DrawTarget for ScratchTarget<'_, DI, OUT>
+where
+    DI: DisplayInterface,
+    OUT: OutputPin<Error = PinE>,
+{
+    type Error = Error<PinE>;
+    type Color = Rgb565;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
     where
-        Self: Sized,
+        I: IntoIterator<Item = Pixel<Self::Color>>,
     {
-        let colors = core::iter::repeat(RawU16::from(color).into_inner()).take(240 * 320); // blank entire HW RAM contents
+        self.display.draw_iter(pixels)
+    }
 
-        match self.orientation {
-            Orientation::Portrait | Orientation::PortraitSwapped => {
-                self.set_pixels(0, 0, 239, 319, colors)
-            }
-            Orientation::Landscape | Orientation::LandscapeSwapped => {
-                self.set_pixels(0, 0, 319, 239, colors)
-            }
+    fn fill_contiguous<I>(&mut self, area: &Rectangle, colors: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Self::Color>,
+    {
+        if let Some(bottom_right) = area.bottom_right() {
+            let count = (area.size.width * area.size.height) as usize;
+            let colors = colors.into_iter().take(count).map(|c| c.into_storage());
+
+            self.display.set_pixels_buffered(
+                area.top_left.x as u16,
+                area.top_left.y as u16,
+                bottom_right.x as u16,
+                bottom_right.y as u16,
+                colors,
+                self.buffer,
+            )
+        } else {
+            // nothing to draw
+            Ok(())
         }
     }
+
+    fn fill_solid(&mut self, area: &Rectangle, color: Self::Color) -> Result<(), Self::Error> {
+        let area = area.intersection(&self.display.framebuffer_bounding_box());
+
+        if let Some(bottom_right) = area.bottom_right() {
+            self.display.fill_solid_buffered(
+                area.top_left.x as u16,
+                area.top_left.y as u16,
+                bottom_right.x as u16,
+                bottom_right.y as u16,
+                color.into_storage(),
+                self.buffer,
+            )
+        } else {
+            // nothing to draw
+            Ok(())
+        }
+    }
+
+    fn clear(&mut self, color: Rgb565) -> Result<(), Self::Error> {
+        // blank entire HW RAM contents
+        let area = self.display.framebuffer_bounding_box();
+        self.fill_solid(&area, color)
+    }
 }
 
-impl<SPI, OUT, PinE> OriginDimensions for ST7789<SPI, OUT>
+impl<DI, OUT, PinE> OriginDimensions for ScratchTarget<'_, DI, OUT>
 where
-    SPI: spi::Write<u8>,
+    DI: DisplayInterface,
     OUT: OutputPin<Error = PinE>,
 {
     fn size(&self) -> Size {
-        Size::new(self.size_x.into(), self.size_y.into()) // visible area, not RAM-pixel size
+        self.display.size()
     }
 }
 
+// Scales the channels of a color to 8 bits
+fn rgb888<C: RgbColor>(color: C) -> [u8; 3] {
+    let scale = |value: u8, max: u8| {
+        let max = u32::from(max);
+        ((u32::from(value) * 255 + max / 2) / max) as u8
+    };
+    [
+        scale(color.r(), C::MAX_R),
+        scale(color.g(), C::MAX_G),
+        scale(color.b(), C::MAX_B),
+    ]
+}
+
 pub trait BlitTarget {
-	type Error;
+    type Error;
 
-	fn blit(&mut self, sx: u16, sy: u16, ex: u16, ey: u16, data: &[u8]) -> Result<(), Self::Error>;
+    fn blit(&mut self, sx: u16, sy: u16, ex: u16, ey: u16, data: &[u8]) -> Result<(), Self::Error>;
 }
 
-impl<SPI, OUT, PinE> BlitTarget for ST7789<SPI, OUT>
+impl<DI, OUT, PinE> BlitTarget for ST7789<DI, OUT>
 where
-    SPI: spi::Write<u8>,
+    DI: DisplayInterface,
     OUT: OutputPin<Error = PinE>,
 {
-	type Error = Error<PinE>;
+    type Error = Error<PinE>;
 
-	fn blit(&mut self, sx: u16, sy: u16, ex: u16, ey: u16, data: &[u8]) -> Result<(), Self::Error> {
-		self.blit_pixels(sx, sy, ex, ey, data)
-	}
+    fn blit(&mut self, sx: u16, sy: u16, ex: u16, ey: u16, data: &[u8]) -> Result<(), Self::Error> {
+        self.blit_pixels(sx, sy, ex, ey, data)
+    }
 }
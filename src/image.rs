@@ -0,0 +1,100 @@
+///
+/// Image file format for screenshots.
+///
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ImageFormat {
+    /// Binary portable pixmap (P6)
+    Ppm,
+    /// 24 bits per pixel Windows bitmap, stored top-down
+    Bmp,
+}
+
+// Widest row the controller can output
+const MAX_WIDTH: usize = 320;
+const BMP_HEADER_LEN: u32 = 14 + 40;
+
+impl ImageFormat {
+    ///
+    /// Encodes an image row by row into `sink`
+    ///
+    /// # Arguments
+    ///
+    /// * `width` - image width in pixels, at most 320
+    /// * `height` - image height in pixels
+    /// * `row` - fills the given buffer with the RGB888 bytes of row `y`
+    /// * `sink` - receives the encoded bytes
+    ///
+    pub(crate) fn encode<E>(
+        self,
+        width: u16,
+        height: u16,
+        mut row: impl FnMut(u16, &mut [u8]) -> Result<(), E>,
+        sink: &mut impl FnMut(&[u8]),
+    ) -> Result<(), E> {
+        assert!(usize::from(width) <= MAX_WIDTH, "image too wide");
+
+        let row_len = usize::from(width) * 3;
+        // BMP rows are padded to a multiple of 4 bytes
+        let stride = match self {
+            Self::Ppm => row_len,
+            Self::Bmp => (row_len + 3) & !3,
+        };
+        self.write_header(width, height, stride, sink);
+
+        let mut buf = [0; MAX_WIDTH * 3 + 3];
+        for y in 0..height {
+            row(y, &mut buf[..row_len])?;
+            if self == Self::Bmp {
+                for pixel in buf[..row_len].chunks_exact_mut(3) {
+                    pixel.swap(0, 2);
+                }
+                buf[row_len..stride].fill(0);
+            }
+            sink(&buf[..stride]);
+        }
+
+        Ok(())
+    }
+
+    fn write_header(self, width: u16, height: u16, stride: usize, sink: &mut impl FnMut(&[u8])) {
+        match self {
+            Self::Ppm => {
+                sink(b"P6\n");
+                sink(decimal(&mut [0; 5], width));
+                sink(b" ");
+                sink(decimal(&mut [0; 5], height));
+                sink(b"\n255\n");
+            }
+            Self::Bmp => {
+                let image_len = stride as u32 * u32::from(height);
+                let mut header = [0; BMP_HEADER_LEN as usize];
+                header[0..2].copy_from_slice(b"BM");
+                header[2..6].copy_from_slice(&(BMP_HEADER_LEN + image_len).to_le_bytes());
+                header[10..14].copy_from_slice(&BMP_HEADER_LEN.to_le_bytes());
+                header[14..18].copy_from_slice(&40u32.to_le_bytes());
+                header[18..22].copy_from_slice(&i32::from(width).to_le_bytes());
+                // negative height for top to bottom rows
+                header[22..26].copy_from_slice(&(-i32::from(height)).to_le_bytes());
+                header[26..28].copy_from_slice(&1u16.to_le_bytes()); // planes
+                header[28..30].copy_from_slice(&24u16.to_le_bytes()); // bits per pixel
+                header[34..38].copy_from_slice(&image_len.to_le_bytes());
+                header[38..42].copy_from_slice(&2835u32.to_le_bytes()); // 72 DPI
+                header[42..46].copy_from_slice(&2835u32.to_le_bytes());
+                sink(&header);
+            }
+        }
+    }
+}
+
+// Formats a number as ASCII digits
+fn decimal(buf: &mut [u8; 5], mut value: u16) -> &[u8] {
+    let mut start = buf.len();
+    loop {
+        start -= 1;
+        buf[start] = b'0' + (value % 10) as u8;
+        value /= 10;
+        if value == 0 {
+            return &buf[start..];
+        }
+    }
+}
@@ -0,0 +1,178 @@
+use crate::Instruction;
+
+///
+/// A single step of an initialization sequence.
+///
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum InitStep<'a> {
+    /// Send a command followed by its parameters
+    Command(Instruction, &'a [u8]),
+    /// Send a command byte not listed in [`Instruction`] (e.g. vendor specific) followed by its parameters
+    Raw(u8, &'a [u8]),
+    /// Wait for the given number of microseconds
+    Delay(u32),
+}
+
+///
+/// Commands, parameters and delays sent by [`crate::ST7789::init_with`] after the hard reset.
+///
+/// Boards differ in power settings, gamma tables and whether the panel needs display
+/// inversion, so a few common profiles are provided and custom ones can be built from
+/// a slice of [`InitStep`]s:
+///
+/// ```
+/// use st7789::{InitSequence, InitStep, Instruction};
+///
+/// const MY_BOARD: InitSequence = InitSequence::new(&[
+///     InitStep::Command(Instruction::SWRESET, &[]),
+///     InitStep::Delay(150_000),
+///     InitStep::Command(Instruction::SLPOUT, &[]),
+///     InitStep::Delay(120_000),
+///     InitStep::Command(Instruction::COLMOD, &[0x55]),
+///     InitStep::Command(Instruction::INVOFF, &[]),
+///     InitStep::Command(Instruction::DISPON, &[]),
+/// ]);
+/// ```
+///
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct InitSequence<'a> {
+    steps: &'a [InitStep<'a>],
+}
+
+impl<'a> InitSequence<'a> {
+    ///
+    /// Creates a new sequence from the given steps
+    ///
+    pub const fn new(steps: &'a [InitStep<'a>]) -> Self {
+        Self { steps }
+    }
+
+    ///
+    /// Returns the steps of the sequence
+    ///
+    pub fn steps(&self) -> &'a [InitStep<'a>] {
+        self.steps
+    }
+}
+
+impl InitSequence<'static> {
+    ///
+    /// Default sequence used by [`crate::ST7789::init`], for IPS panels needing display inversion.
+    /// Based on <https://github.com/Nitrokey/picolcd114/blob/8bf6d71a95a3492ef337e67e1e54d5cd559fb090/src/lib.rs>
+    ///
+    pub const DEFAULT: Self = Self::new(&[
+        InitStep::Command(Instruction::MADCTL, &[0x70]),
+        InitStep::Command(Instruction::COLMOD, &[0x55]), // 16bpp
+        InitStep::Command(Instruction::PORCTRL, &[0x0c, 0x0c, 0x00, 0x33, 0x33]), // reset default
+        InitStep::Command(Instruction::GCTRL, &[0x35]),  // reset default
+        InitStep::Command(Instruction::VCOMS, &[0x19]),
+        InitStep::Command(Instruction::LCMCTRL, &[0x2c]), // reset default
+        // reset default, but 2nd data byte missing (default: 0xff)
+        InitStep::Command(Instruction::VDVVRHEN, &[0x01]),
+        InitStep::Command(Instruction::VRHS, &[0x12]),
+        InitStep::Command(Instruction::VDVS, &[0x20]), // reset default
+        InitStep::Command(Instruction::FRCTRL2, &[0x0f]), // reset default
+        InitStep::Command(Instruction::PWCTRL1, &[0xa4, 0xa1]), // reset default
+        InitStep::Command(
+            Instruction::PVGAMCTRL,
+            &[
+                0xd0, 0x04, 0x0d, 0x11, 0x13, 0x2b, 0x3f, 0x54, 0x4c, 0x18, 0x0d, 0x0b, 0x1f, 0x23,
+            ],
+        ),
+        InitStep::Command(
+            Instruction::NVGAMCTRL,
+            &[
+                0xd0, 0x04, 0x0c, 0x11, 0x13, 0x2c, 0x3f, 0x44, 0x51, 0x2f, 0x1f, 0x1f, 0x20, 0x23,
+            ],
+        ),
+        InitStep::Command(Instruction::INVON, &[]),
+        InitStep::Command(Instruction::SLPOUT, &[]),
+        InitStep::Command(Instruction::DISPON, &[]),
+        InitStep::Delay(10_000),
+    ]);
+
+    ///
+    /// Longer sequence used by earlier versions of this driver, with a software reset,
+    /// a delay after every command and explicit power settings.
+    ///
+    pub const LEGACY: Self = Self::new(&[
+        InitStep::Command(Instruction::SWRESET, &[]), // reset display
+        InitStep::Delay(150_000),
+        InitStep::Command(Instruction::RAMWR, &[]), // Init ram
+        InitStep::Delay(10_000),
+        InitStep::Command(Instruction::SLPOUT, &[]), // turn off sleep
+        InitStep::Delay(10_000),
+        InitStep::Command(Instruction::INVOFF, &[]), // turn off invert
+        // vertical scroll definition, the bytes v0.6 sent
+        InitStep::Command(Instruction::VSCRDER, &[0x00, 0x00, 0x14, 0x00, 0x00, 0x00]),
+        // left -> right, bottom -> top RGB
+        InitStep::Command(Instruction::MADCTL, &[0b0000_0000]),
+        InitStep::Command(Instruction::COLMOD, &[0b0101_0101]), // 16bit 65k colors
+        InitStep::Command(Instruction::INVON, &[]),             // hack?
+        InitStep::Delay(10_000),
+        InitStep::Command(Instruction::NORON, &[]), // turn on display
+        InitStep::Delay(10_000),
+        InitStep::Command(Instruction::DISPON, &[]), // turn on display
+        InitStep::Delay(10_000),
+        InitStep::Command(Instruction::PORCTRL, &[0x0c, 0x0c, 0x00, 0x33, 0x33]), // porch control
+        InitStep::Delay(10_000),
+        InitStep::Command(Instruction::GCTRL, &[0x35]), // gate control
+        InitStep::Delay(10_000),
+        InitStep::Command(Instruction::VCOMS, &[0x37]), // VCOM Setting
+        InitStep::Delay(10_000),
+        InitStep::Command(Instruction::LCMCTRL, &[0x2c]), // LCM Control
+        InitStep::Delay(10_000),
+        // VDV and VRH Command Enable reset default, but 2nd data byte missing (default: 0xff)
+        InitStep::Command(Instruction::VDVVRHEN, &[0x01]),
+        InitStep::Delay(10_000),
+        InitStep::Command(Instruction::VRHS, &[0x12]), // VRH set
+        InitStep::Delay(10_000),
+        InitStep::Command(Instruction::VDVS, &[0x20]), // VDV SET
+        InitStep::Delay(10_000),
+        InitStep::Command(Instruction::FRCTRL2, &[0x0f]), // Frame Rate Control in Normal Mode
+        InitStep::Delay(10_000),
+        InitStep::Command(Instruction::PWCTRL1, &[0xa4, 0xa1]), // Power Control 1
+        InitStep::Delay(10_000),
+        InitStep::Command(
+            Instruction::PVGAMCTRL,
+            &[
+                0xd0, 0x04, 0x0d, 0x11, 0x13, 0x2b, 0x3f, 0x54, 0x4c, 0x18, 0x0d, 0x0b, 0x1f, 0x23,
+            ],
+        ), // Positive Voltage Gamma Control
+        InitStep::Delay(10_000),
+        InitStep::Command(
+            Instruction::NVGAMCTRL,
+            &[
+                0xd0, 0x04, 0x0c, 0x11, 0x13, 0x2c, 0x3f, 0x44, 0x51, 0x2f, 0x1f, 0x1f, 0x20, 0x23,
+            ],
+        ), // Negative Voltage Gamma Control
+        InitStep::Delay(10_000),
+        InitStep::Command(Instruction::INVON, &[]), // hack?
+        InitStep::Delay(10_000),
+        InitStep::Command(Instruction::DISPON, &[]), // Turn on display
+        InitStep::Delay(10_000),
+    ]);
+
+    ///
+    /// Software reset keeping the controller's reset defaults, for TN panels which
+    /// show correct colors without display inversion.
+    ///
+    pub const MINIMAL: Self = Self::new(&[
+        InitStep::Command(Instruction::SWRESET, &[]),
+        InitStep::Delay(150_000),
+        InitStep::Command(Instruction::SLPOUT, &[]),
+        InitStep::Delay(120_000),
+        InitStep::Command(Instruction::MADCTL, &[0x00]),
+        InitStep::Command(Instruction::COLMOD, &[0x55]), // 16bpp
+        InitStep::Command(Instruction::INVOFF, &[]),
+        InitStep::Command(Instruction::NORON, &[]),
+        InitStep::Command(Instruction::DISPON, &[]),
+        InitStep::Delay(10_000),
+    ]);
+}
+
+impl Default for InitSequence<'static> {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
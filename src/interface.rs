@@ -0,0 +1,519 @@
+use embedded_hal::{digital::OutputPin, spi::SpiDevice};
+
+use crate::bus::BusChunks;
+use crate::{DataFormat, Instruction, SpiError};
+
+///
+/// Bus the display is wired to, writing commands and the data following them.
+///
+/// The crate implements it for SPI with a DC pin ([`SpiInterface`]), the 3-wire serial
+/// interface without one ([`ThreeWireInterface`]) and the 8 and 16-bit MCU 8080 parallel
+/// interface bit-banged through GPIOs ([`ParallelInterface`]).
+///
+pub trait DisplayInterface {
+    ///
+    /// Writes a command byte
+    ///
+    fn write_command(&mut self, command: u8) -> Result<(), SpiError>;
+
+    ///
+    /// Writes data following the last command
+    ///
+    fn write_data(&mut self, data: DataFormat<'_>) -> Result<(), SpiError>;
+
+    ///
+    /// Writes a command byte followed by its parameters.
+    ///
+    /// Interfaces able to, send both in one bus transaction. By default the command and
+    /// the parameters are written one after the other.
+    ///
+    fn send_command(&mut self, command: u8, params: &[u8]) -> Result<(), SpiError> {
+        self.write_command(command)?;
+        if params.is_empty() {
+            return Ok(());
+        }
+        self.write_data(DataFormat::U8(params))
+    }
+
+    ///
+    /// Starts writing a buffer of data following the last command, handing the buffer to
+    /// the interface.
+    ///
+    /// Interfaces able to write in the background, e.g. with DMA, keep the buffer, start
+    /// the transfer and return `Ok(None)` right away. [`Self::poll_write`] hands the buffer
+    /// back once the transfer is done, no other call is made in between. By default the
+    /// buffer is written in one go and handed back right away. If the write can't be
+    /// started the buffer comes back with the error.
+    ///
+    fn start_write(
+        &mut self,
+        data: &'static mut [u8],
+    ) -> Result<Option<&'static mut [u8]>, (SpiError, &'static mut [u8])> {
+        match self.write_data(DataFormat::U8(data)) {
+            Ok(()) => Ok(Some(data)),
+            Err(error) => Err((error, data)),
+        }
+    }
+
+    ///
+    /// Returns [`nb::Error::WouldBlock`] while a write kept by [`Self::start_write`] is
+    /// still running, then hands the buffer back, with the error if the transfer failed.
+    ///
+    /// Only called after [`Self::start_write`] kept the buffer, interfaces doing so have to
+    /// implement it. The default is never called and reports the write as running.
+    ///
+    fn poll_write(&mut self) -> nb::Result<&'static mut [u8], (SpiError, &'static mut [u8])> {
+        Err(nb::Error::WouldBlock)
+    }
+}
+
+///
+/// Bus the display's data output is wired to, for register and frame memory readback.
+///
+/// A read is a single transfer: the command followed by the clock cycles the response is
+/// shifted in with.
+///
+pub trait ReadInterface: DisplayInterface {
+    ///
+    /// Returns the number of bits the command takes on the bus before the response
+    ///
+    fn command_bits(&self) -> usize;
+
+    ///
+    /// Sends the command in the first [`Self::command_bits`] bits of `buf` and clocks the
+    /// rest of it in
+    ///
+    fn read(&mut self, command: u8, buf: &mut [u8]) -> Result<(), SpiError>;
+}
+
+///
+/// Bytes of [`DataFormat`] in the order they go on the bus
+///
+pub(crate) enum DataBytes<'a> {
+    Bytes(core::slice::Iter<'a, u8>),
+    BytesIter(&'a mut dyn Iterator<Item = u8>),
+    // Words, their byte order and the second byte of the last word
+    Words(Words<'a>, fn(u16) -> [u8; 2], Option<u8>),
+}
+
+pub(crate) enum Words<'a> {
+    Slice(core::slice::Iter<'a, u16>),
+    Iter(&'a mut dyn Iterator<Item = u16>),
+}
+
+impl<'a> DataBytes<'a> {
+    #[allow(unreachable_patterns)]
+    pub(crate) fn new(data: DataFormat<'a>) -> Result<Self, SpiError> {
+        let words = |slice: &'a [u16], order: fn(u16) -> [u8; 2]| {
+            Self::Words(Words::Slice(slice.iter()), order, None)
+        };
+        Ok(match data {
+            DataFormat::U8(slice) => Self::Bytes(slice.iter()),
+            DataFormat::U16(slice) => words(slice, u16::to_ne_bytes),
+            DataFormat::U16LE(slice) => words(slice, u16::to_le_bytes),
+            DataFormat::U16BE(slice) => words(slice, u16::to_be_bytes),
+            DataFormat::U8Iter(iter) => Self::BytesIter(iter),
+            DataFormat::U16LEIter(iter) => Self::Words(Words::Iter(iter), u16::to_le_bytes, None),
+            DataFormat::U16BEIter(iter) => Self::Words(Words::Iter(iter), u16::to_be_bytes, None),
+            _ => return Err(SpiError::DataFormatNotImplemented),
+        })
+    }
+}
+
+impl Iterator for DataBytes<'_> {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<u8> {
+        match self {
+            Self::Bytes(bytes) => bytes.next().copied(),
+            Self::BytesIter(bytes) => bytes.next(),
+            Self::Words(words, order, second) => second.take().or_else(|| {
+                let word = match words {
+                    Words::Slice(words) => words.next().copied(),
+                    Words::Iter(words) => words.next(),
+                }?;
+                let [first, last] = order(word);
+                *second = Some(last);
+                Some(first)
+            }),
+        }
+    }
+}
+
+///
+/// 4-wire SPI interface with a data/command select pin.
+///
+/// The [`SpiDevice`] asserts CS around every transaction, and a transaction is a fixed
+/// list of bus operations: there is no way to drive the DC pin between two of them. A
+/// command is therefore one transaction with DC low, the parameters or data following
+/// it another one with DC high. The controller takes bytes sent while DC is high as
+/// parameters of the last command, no matter how often CS is deasserted in between.
+///
+/// Data in a slice, such as command parameters and [`crate::ST7789::blit_pixels`], is
+/// written in a single transaction. Data from an iterator is encoded through a small
+/// buffer on the stack and written a buffer at a time, since a transaction has to hold
+/// all of its data up front. [`crate::ST7789::set_pixels_buffered`] and
+/// [`crate::ST7789::scratch_target`] write through a caller supplied buffer instead.
+///
+pub struct SpiInterface<SPI, DC> {
+    pub(crate) spi: SPI,
+    pub(crate) dc: DC,
+}
+
+impl<SPI, DC> SpiInterface<SPI, DC> {
+    ///
+    /// Creates a new interface
+    ///
+    /// # Arguments
+    ///
+    /// * `spi` - SPI bus the display is connected to
+    /// * `dc` - data/command select pin
+    ///
+    pub fn new(spi: SPI, dc: DC) -> Self {
+        Self { spi, dc }
+    }
+
+    ///
+    /// Returns the SPI bus and the DC pin
+    ///
+    pub fn release(self) -> (SPI, DC) {
+        (self.spi, self.dc)
+    }
+}
+
+impl<SPI, DC> SpiInterface<SPI, DC>
+where
+    SPI: SpiDevice,
+    DC: OutputPin,
+{
+    #[allow(unreachable_patterns)]
+    fn send_u8(&mut self, words: DataFormat<'_>) -> Result<(), SpiError> {
+        match words {
+            DataFormat::U8(slice) => self.spi.write(slice).map_err(|_| SpiError::BusWriteError),
+            DataFormat::U16(slice) => {
+                use byte_slice_cast::*;
+                self.spi
+                    .write(slice.as_byte_slice())
+                    .map_err(|_| SpiError::BusWriteError)
+            }
+            DataFormat::U16LE(slice) => {
+                use byte_slice_cast::*;
+                for v in slice.iter_mut() {
+                    *v = v.to_le();
+                }
+                self.spi
+                    .write(slice.as_byte_slice())
+                    .map_err(|_| SpiError::BusWriteError)
+            }
+            DataFormat::U16BE(slice) => {
+                use byte_slice_cast::*;
+                for v in slice.iter_mut() {
+                    *v = v.to_be();
+                }
+                self.spi
+                    .write(slice.as_byte_slice())
+                    .map_err(|_| SpiError::BusWriteError)
+            }
+            DataFormat::U8Iter(iter) => {
+                let mut chunks = BusChunks::new(iter);
+                while let Some(chunk) = chunks.next_chunk() {
+                    self.spi.write(chunk).map_err(|_| SpiError::BusWriteError)?;
+                }
+                Ok(())
+            }
+            DataFormat::U16LEIter(iter) => {
+                use byte_slice_cast::*;
+                let mut buf = [0; 32];
+                let mut i = 0;
+
+                for v in iter.map(u16::to_le) {
+                    buf[i] = v;
+                    i += 1;
+
+                    if i == buf.len() {
+                        self.spi
+                            .write(buf.as_byte_slice())
+                            .map_err(|_| SpiError::BusWriteError)?;
+                        i = 0;
+                    }
+                }
+
+                if i > 0 {
+                    self.spi
+                        .write(buf[..i].as_byte_slice())
+                        .map_err(|_| SpiError::BusWriteError)?;
+                }
+
+                Ok(())
+            }
+            DataFormat::U16BEIter(iter) => {
+                use byte_slice_cast::*;
+                let mut buf = [0; 64];
+                let mut i = 0;
+                let len = buf.len();
+
+                for v in iter.map(u16::to_be) {
+                    buf[i] = v;
+                    i += 1;
+
+                    if i == len {
+                        self.spi
+                            .write(buf.as_byte_slice())
+                            .map_err(|_| SpiError::BusWriteError)?;
+                        i = 0;
+                    }
+                }
+
+                if i > 0 {
+                    self.spi
+                        .write(buf[..i].as_byte_slice())
+                        .map_err(|_| SpiError::BusWriteError)?;
+                }
+
+                Ok(())
+            }
+            _ => Err(SpiError::DataFormatNotImplemented),
+        }
+    }
+}
+
+impl<SPI, DC> DisplayInterface for SpiInterface<SPI, DC>
+where
+    SPI: SpiDevice,
+    DC: OutputPin,
+{
+    fn write_command(&mut self, command: u8) -> Result<(), SpiError> {
+        // 1 = data, 0 = command
+        self.dc.set_low().map_err(|_| SpiError::DCError)?;
+        self.send_u8(DataFormat::U8(&[command]))
+    }
+
+    fn write_data(&mut self, data: DataFormat<'_>) -> Result<(), SpiError> {
+        // 1 = data, 0 = command
+        self.dc.set_high().map_err(|_| SpiError::DCError)?;
+        self.send_u8(data)
+    }
+}
+
+impl<SPI, DC> ReadInterface for SpiInterface<SPI, DC>
+where
+    SPI: SpiDevice,
+    DC: OutputPin,
+{
+    fn command_bits(&self) -> usize {
+        8
+    }
+
+    fn read(&mut self, command: u8, buf: &mut [u8]) -> Result<(), SpiError> {
+        self.dc.set_low().map_err(|_| SpiError::DCError)?;
+        buf[0] = command;
+        self.spi
+            .transfer_in_place(buf)
+            .map_err(|_| SpiError::BusWriteError)
+    }
+}
+
+///
+/// 3-wire (9-bit) serial interface for displays without a DC pin.
+///
+/// Every byte is prefixed with its D/C bit and the 9-bit frames are sent as a continuous
+/// bit stream over a regular 8-bit SPI bus, padded to whole bytes at the end of every
+/// transaction.
+///
+/// A command and its parameters go out in one transaction, as the D/C bit travels with
+/// every frame. Pixel data is encoded a buffer of 128 frames at a time and every buffer
+/// is written in a transaction of its own, ending on a frame boundary.
+///
+pub struct ThreeWireInterface<SPI> {
+    pub(crate) spi: SPI,
+}
+
+impl<SPI> ThreeWireInterface<SPI> {
+    ///
+    /// Creates a new interface
+    ///
+    /// # Arguments
+    ///
+    /// * `spi` - SPI bus the display is connected to
+    ///
+    pub fn new(spi: SPI) -> Self {
+        Self { spi }
+    }
+
+    ///
+    /// Returns the SPI bus
+    ///
+    pub fn release(self) -> SPI {
+        self.spi
+    }
+}
+
+impl<SPI> ThreeWireInterface<SPI>
+where
+    SPI: SpiDevice,
+{
+    fn send_9bit(&mut self, dc: bool, bytes: &mut dyn Iterator<Item = u8>) -> Result<(), SpiError> {
+        let mut chunks = BusChunks::nine_bit(dc, bytes);
+        while let Some(chunk) = chunks.next_chunk() {
+            self.spi.write(chunk).map_err(|_| SpiError::BusWriteError)?;
+        }
+        Ok(())
+    }
+}
+
+impl<SPI> DisplayInterface for ThreeWireInterface<SPI>
+where
+    SPI: SpiDevice,
+{
+    fn write_command(&mut self, command: u8) -> Result<(), SpiError> {
+        self.send_9bit(false, &mut Some(command).into_iter())
+    }
+
+    fn write_data(&mut self, data: DataFormat<'_>) -> Result<(), SpiError> {
+        self.send_9bit(true, &mut DataBytes::new(data)?)
+    }
+
+    fn send_command(&mut self, command: u8, params: &[u8]) -> Result<(), SpiError> {
+        // up to 127 parameters fit into the first chunk, more than any command takes
+        let mut chunks = BusChunks::nine_bit_command(command, params.iter().copied());
+        while let Some(chunk) = chunks.next_chunk() {
+            self.spi.write(chunk).map_err(|_| SpiError::BusWriteError)?;
+        }
+        Ok(())
+    }
+}
+
+impl<SPI> ReadInterface for ThreeWireInterface<SPI>
+where
+    SPI: SpiDevice,
+{
+    fn command_bits(&self) -> usize {
+        9
+    }
+
+    fn read(&mut self, command: u8, buf: &mut [u8]) -> Result<(), SpiError> {
+        // 9-bit frame with a low D/C bit
+        buf[0] = command >> 1;
+        buf[1] = command << 7;
+        self.spi
+            .transfer_in_place(buf)
+            .map_err(|_| SpiError::BusWriteError)
+    }
+}
+
+///
+/// MCU 8080 parallel interface with `N` data lines, bit-banged through GPIOs.
+///
+/// Every byte or word is put on the data lines `D0` to `DN-1` and latched by the rising
+/// edge of the WR strobe. Only data lines whose level changes are driven. RD has to be
+/// tied high and CS low, reading back is not supported.
+///
+/// On the 16-bit bus ([`Parallel16Bit`]) commands and parameters use the lower eight lines,
+/// frame memory writes send a RGB565 pixel per strobe, so the display has to use
+/// [`crate::PixelFormat::Rgb565`].
+///
+pub struct ParallelInterface<P, const N: usize> {
+    data: [P; N],
+    wr: P,
+    dc: P,
+    // Level of the data lines, `None` before the first write
+    last: Option<u16>,
+    // Frame memory write in progress, sending words on the 16-bit bus
+    words: bool,
+}
+
+///
+/// 8-bit parallel interface, see [`ParallelInterface`]
+///
+pub type Parallel8Bit<P> = ParallelInterface<P, 8>;
+
+///
+/// 16-bit parallel interface, see [`ParallelInterface`]
+///
+pub type Parallel16Bit<P> = ParallelInterface<P, 16>;
+
+impl<P, const N: usize> ParallelInterface<P, N>
+where
+    P: OutputPin,
+{
+    const WIDTH: () = assert!(N == 8 || N == 16, "the parallel bus has 8 or 16 data lines");
+
+    ///
+    /// Creates a new interface
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - data lines, `D0` first
+    /// * `wr` - write strobe, latching the data on its rising edge
+    /// * `dc` - data/command select pin
+    ///
+    pub fn new(data: [P; N], wr: P, dc: P) -> Self {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::WIDTH;
+        Self {
+            data,
+            wr,
+            dc,
+            last: None,
+            words: false,
+        }
+    }
+
+    ///
+    /// Returns the data lines, the WR and the DC pin
+    ///
+    pub fn release(self) -> ([P; N], P, P) {
+        (self.data, self.wr, self.dc)
+    }
+
+    fn write_word(&mut self, word: u16) -> Result<(), SpiError> {
+        let changed = self.last.map_or(u16::MAX, |last| last ^ word);
+        for (bit, pin) in self.data.iter_mut().enumerate() {
+            if changed & (1 << bit) == 0 {
+                continue;
+            }
+            let level = if word & (1 << bit) != 0 {
+                pin.set_high()
+            } else {
+                pin.set_low()
+            };
+            level.map_err(|_| SpiError::BusWriteError)?;
+        }
+        self.last = Some(word);
+
+        self.wr.set_low().map_err(|_| SpiError::BusWriteError)?;
+        self.wr.set_high().map_err(|_| SpiError::BusWriteError)
+    }
+
+    fn write_bytes(&mut self, bytes: &mut dyn Iterator<Item = u8>) -> Result<(), SpiError> {
+        while let Some(byte) = bytes.next() {
+            let word = if self.words {
+                // two bytes make up a pixel, big endian like on the serial interface
+                u16::from_be_bytes([byte, bytes.next().unwrap_or(0)])
+            } else {
+                u16::from(byte)
+            };
+            self.write_word(word)?;
+        }
+        Ok(())
+    }
+}
+
+impl<P, const N: usize> DisplayInterface for ParallelInterface<P, N>
+where
+    P: OutputPin,
+{
+    fn write_command(&mut self, command: u8) -> Result<(), SpiError> {
+        self.words =
+            N > 8 && (command == Instruction::RAMWR as u8 || command == Instruction::RAMWRC as u8);
+        // 1 = data, 0 = command
+        self.dc.set_low().map_err(|_| SpiError::DCError)?;
+        self.write_word(u16::from(command))
+    }
+
+    fn write_data(&mut self, data: DataFormat<'_>) -> Result<(), SpiError> {
+        // 1 = data, 0 = command
+        self.dc.set_high().map_err(|_| SpiError::DCError)?;
+        self.write_bytes(&mut DataBytes::new(data)?)
+    }
+}
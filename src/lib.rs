@@ -3,53 +3,105 @@
 #![allow(clippy::type_complexity)]
 //! This crate provides a ST7789 driver to connect to TFT displays.
 
+#[cfg(feature = "async")]
+pub mod asynch;
+mod backlight;
+mod brightness;
+mod bus;
+#[cfg(feature = "chart")]
+mod chart;
+mod color;
+#[cfg(feature = "console")]
+mod console;
+#[cfg(feature = "eh02")]
+pub mod eh02;
 pub mod enums;
+mod frame_rate;
+#[cfg(feature = "graphics")]
+mod framebuffer;
+mod gamma;
 #[cfg(feature = "graphics")]
 mod graphics;
+#[cfg(any(feature = "graphics", feature = "testing"))]
+mod image;
+mod init;
+mod interface;
+mod madctl;
+mod panel;
+mod power;
+mod readback;
+#[cfg(feature = "graphics")]
+mod screenshot;
+mod scroll;
+mod state;
+mod transfer;
+pub mod typestate;
 
 #[cfg(feature = "batch")]
 mod batch;
 
+#[cfg(feature = "testing")]
+pub mod testing;
+
+pub use crate::backlight::{Backlight, BacklightOutput, GpioBacklight, PwmBacklight};
+pub use crate::brightness::BrightnessControl;
+#[cfg(feature = "chart")]
+pub use crate::chart::StripChart;
+use crate::color::PixelBytes;
+#[cfg(feature = "console")]
+pub use crate::console::{Console, ConsoleWriter};
 pub use crate::enums::{
-    BacklightState, DataFormat, Error, Instruction, Orientation, SpiError, TearingEffect,
+    BacklightState, CabcMode, DataFormat, DisplayMode, Error, FrameRate, Instruction, Orientation,
+    PixelFormat, SpiError, TearingEffect,
 };
-use core::iter::once;
-use embedded_hal::{
-    blocking::{delay::DelayUs, spi},
-    digital::v2::OutputPin,
+pub use crate::frame_rate::{Porch, PorchConfig};
+#[cfg(feature = "graphics")]
+pub use crate::framebuffer::{DirtyRect, Framebuffer};
+pub use crate::gamma::GammaCurve;
+#[cfg(feature = "graphics")]
+pub use crate::graphics::{BlitTarget, ColorTarget, ScratchTarget};
+#[cfg(any(feature = "graphics", feature = "testing"))]
+pub use crate::image::ImageFormat;
+pub use crate::init::{InitSequence, InitStep};
+pub use crate::interface::{
+    DisplayInterface, Parallel16Bit, Parallel8Bit, ParallelInterface, ReadInterface, SpiInterface,
+    ThreeWireInterface,
 };
+pub use crate::madctl::MemoryAccessControl;
+pub use crate::panel::{DisplayModel, PanelConfig};
+pub use crate::power::PowerState;
+pub use crate::readback::{DisplayId, DisplayStatus, PowerMode};
+#[cfg(feature = "graphics")]
+pub use crate::screenshot::PixelReader;
+pub use crate::scroll::ScrollRegion;
+use crate::state::DisplayState;
+pub use crate::transfer::BlitTransfer;
+use core::iter::once;
+use embedded_hal::{delay::DelayNs, digital::OutputPin};
 
 ///
 /// ST7789 driver to connect to TFT displays.
 ///
-pub struct ST7789<SPI, OUT>
+pub struct ST7789<DI, OUT>
 where
-    SPI: spi::Write<u8>,
+    DI: DisplayInterface,
     OUT: OutputPin,
 {
     // Display interface
-    spi: SPI,
+    di: DI,
     // Reset pin.
     rst: Option<OUT>,
     // Backlight pin,
     bl: Option<OUT>,
-    // Data pin,
-    dc: Option<OUT>,
-    cs: Option<OUT>,
-    // Visible size (x, y)
-    size_x: u16,
-    size_y: u16,
-    // Current orientation
-    orientation: Orientation,
-    x_start: u16, // what row idx to translate 0 to (for 240x240 displays in PortaitSwapped)
-    y_start: u16, // what col idx to translate 0 to (for 240x240 displays in LandscapeSwapped)
+    // Panel geometry and the state the commands sent left the display in
+    state: DisplayState,
 }
 
 // type Result_ = core::result::Result<(), DisplayError>;
 
-impl<SPI, OUT, PinE> ST7789<SPI, OUT>
+impl<DI, OUT, PinE> ST7789<DI, OUT>
 where
-    SPI: spi::Write<u8>,
+    DI: DisplayInterface,
     OUT: OutputPin<Error = PinE>,
 {
     ///
@@ -63,26 +115,31 @@ where
     /// * `size_x` - x axis resolution of the display in pixels
     /// * `size_y` - y axis resolution of the display in pixels
     ///
-    pub fn new(
-        spi: SPI,
+    pub fn new(di: DI, rst: Option<OUT>, bl: Option<OUT>, size_x: u16, size_y: u16) -> Self {
+        Self::with_panel(di, rst, bl, PanelConfig::new(size_x, size_y))
+    }
+
+    ///
+    /// Creates a new ST7789 driver instance for a panel with known geometry
+    ///
+    /// # Arguments
+    ///
+    /// * `di` - a display interface for talking with the display
+    /// * `rst` - display hard reset pin
+    /// * `bl` - backlight pin
+    /// * `panel` - panel geometry, either a [`DisplayModel`] or a custom [`PanelConfig`]
+    ///
+    pub fn with_panel(
+        di: DI,
         rst: Option<OUT>,
         bl: Option<OUT>,
-        dc: Option<OUT>,
-        // cs: Option<OUT>,
-        size_x: u16,
-        size_y: u16,
+        panel: impl Into<PanelConfig>,
     ) -> Self {
         Self {
-            spi,
+            di,
             rst,
             bl,
-            dc,
-            cs: None,
-            size_x,
-            size_y,
-            orientation: Orientation::default(),
-            x_start: 0,
-            y_start: 0,
+            state: DisplayState::new(panel.into()),
         }
     }
 
@@ -93,7 +150,23 @@ where
     ///
     /// * `delay_source` - mutable reference to a delay provider
     ///
-    pub fn init(&mut self, delay_source: &mut impl DelayUs<u32>) -> Result<(), Error<PinE>> {
+    pub fn init(&mut self, delay_source: &mut impl DelayNs) -> Result<(), Error<PinE>> {
+        self.init_with(&InitSequence::DEFAULT, delay_source)
+    }
+
+    ///
+    /// Runs commands to initialize the display using a custom initialization sequence
+    ///
+    /// # Arguments
+    ///
+    /// * `sequence` - commands, parameters and delays to send after the hard reset
+    /// * `delay_source` - mutable reference to a delay provider
+    ///
+    pub fn init_with(
+        &mut self,
+        sequence: &InitSequence<'_>,
+        delay_source: &mut impl DelayNs,
+    ) -> Result<(), Error<PinE>> {
         self.hard_reset(delay_source)?;
         if let Some(bl) = self.bl.as_mut() {
             bl.set_low().map_err(Error::Pin)?;
@@ -101,117 +174,35 @@ where
             bl.set_high().map_err(Error::Pin)?;
         }
 
-        /* self.write_command(Instruction::SWRESET)?; // reset display
-        delay_source.delay_us(150_000);
-        self.write_command(Instruction::RAMWR)?; // Init ram
-        delay_source.delay_us(10_000);
-        self.write_command(Instruction::SLPOUT)?; // turn off sleep
-        delay_source.delay_us(10_000);
-        self.write_command(Instruction::INVOFF)?; // turn off invert
-        self.write_command(Instruction::VSCRDER)?; // vertical scroll definition
-        self.write_data(&[0u8, 0u8, 0x14u8, 0u8, 0u8, 0u8])?; // 0 TSA, 320 VSA, 0 BSA
-        self.write_command(Instruction::MADCTL)?; // left -> right, bottom -> top RGB
-        self.write_data(&[0b0000_0000])?;
-        self.write_command(Instruction::COLMOD)?; // 16bit 65k colors
-        self.write_data(&[0b0101_0101])?;
-        self.write_command(Instruction::INVON)?; // hack?
-        delay_source.delay_us(10_000);
-        self.write_command(Instruction::NORON)?; // turn on display
-        delay_source.delay_us(10_000);
-        self.write_command(Instruction::DISPON)?; // turn on display
-        delay_source.delay_us(10_000);
-        self.write_command(Instruction::PORCTRL)?; // porch control
-        self.write_data(&[0x0Cu8, 0x0Cu8, 0x00u8, 0x33u8, 0x33u8])?;
-        delay_source.delay_us(10_000);
-        self.write_command(Instruction::GCTRL)?; // gate control
-        self.write_data(&[0x35u8])?;
-        delay_source.delay_us(10_000);
-        self.write_command(Instruction::VCOMS)?; // VCOM Setting
-        self.write_data(&[0x37u8])?;
-        delay_source.delay_us(10_000);
-        self.write_command(Instruction::LCMCTRL)?; // LCM Control
-        self.write_data(&[0x2Cu8])?;
-        delay_source.delay_us(10_000);
-        self.write_command(Instruction::VDVVRHEN)?; // VDV and VRH Command Enable reset default, but 2nd data byte missing (default: 0xff)
-        self.write_data(&[0x01u8])?;
-        delay_source.delay_us(10_000);
-        self.write_command(Instruction::VRHS)?; // VRH set
-        self.write_data(&[0x12u8])?;
-        delay_source.delay_us(10_000);
-        self.write_command(Instruction::VDVS)?; // VDV SET
-        self.write_data(&[0x20u8])?;
-        delay_source.delay_us(10_000);
-        /* self.write_command(Instruction::FRCTRL1)?; // Frame Rate Control in Partial Mode
-        self.write_data(&[0x00u8, 0x0Fu8, 0x0Fu8])?;
-        delay_source.delay_us(10_000); */
-        self.write_command(Instruction::FRCTRL2)?; // Frame Rate Control in Normal Mode
-        self.write_data(&[0x0Fu8])?;
-        delay_source.delay_us(10_000);
-        self.write_command(Instruction::PWCTRL1)?; // Power Control 1
-        self.write_data(&[0xA4u8, 0xA1u8])?;
-        delay_source.delay_us(10_000);
-        self.write_command(Instruction::PVGAMCTRL)?; // Positive Voltage Gamma Control
-        self.write_data(&[
-            0xD0u8, 0x04u8, 0x0Du8, 0x11u8, 0x13u8, 0x2Bu8, 0x3Fu8, 0x54u8, 0x4Cu8, 0x18u8, 0x0Du8,
-            0x0Bu8, 0x1Fu8, 0x23u8,
-        ])?;
-        delay_source.delay_us(10_000);
-        self.write_command(Instruction::NVGAMCTRL)?; // Negative Voltage Gamma Control
-        self.write_data(&[
-            0xD0u8, 0x04u8, 0x0Cu8, 0x11u8, 0x13u8, 0x2Cu8, 0x3Fu8, 0x44u8, 0x51u8, 0x2Fu8, 0x1Fu8,
-            0x1Fu8, 0x20u8, 0x23u8,
-        ])?;
-        delay_source.delay_us(10_000);
-        self.write_command(Instruction::INVON)?; // hack?
-        delay_source.delay_us(10_000);
-        self.write_command(Instruction::DISPON)?; // Turn on display
-        delay_source.delay_us(10_000); */
-        // https://github.com/Nitrokey/picolcd114/blob/8bf6d71a95a3492ef337e67e1e54d5cd559fb090/src/lib.rs
-        self.write_command(Instruction::MADCTL)?;
-        self.write_data(&[0x70])?;
-        self.write_command(Instruction::COLMOD)?;
-        self.write_data(&[0x55])?; // 16bpp
-        self.write_command(Instruction::PORCTRL)?;
-        self.write_data(&[0x0c, 0x0c, 0x00, 0x33, 0x33])?; // reset default
-        self.write_command(Instruction::GCTRL)?;
-        self.write_data(&[0x35])?; // reset default
-        self.write_command(Instruction::VCOMS)?;
-        self.write_data(&[0x19])?;
-        self.write_command(Instruction::LCMCTRL)?;
-        self.write_data(&[0x2c])?; // reset default
-        self.write_command(Instruction::VDVVRHEN)?;
-        self.write_data(&[0x01])?; // reset default, but 2nd data byte missing (default: 0xff)
-        self.write_command(Instruction::VRHS)?;
-        self.write_data(&[0x12])?;
-        self.write_command(Instruction::VDVS)?;
-        self.write_data(&[0x20])?; // reset default
-        self.write_command(Instruction::FRCTRL2)?;
-        self.write_data(&[0x0f])?; // reset default
-        self.write_command(Instruction::PWCTRL1)?;
-        self.write_data(&[0xa4, 0xa1])?; // reset default
-        self.write_command(Instruction::PVGAMCTRL)?;
-        self.write_data(&[
-            0xd0, 0x04, 0x0d, 0x11, 0x13, 0x2b, 0x3f, 0x54, 0x4c, 0x18, 0x0d, 0x0b, 0x1f, 0x23,
-        ])?;
-        self.write_command(Instruction::NVGAMCTRL)?;
-        self.write_data(&[
-            0xd0, 0x04, 0x0c, 0x11, 0x13, 0x2c, 0x3f, 0x44, 0x51, 0x2f, 0x1f, 0x1f, 0x20, 0x23,
-        ])?;
-        self.write_command(Instruction::INVON)?;
-        self.write_command(Instruction::SLPOUT)?;
-        self.write_command(Instruction::DISPON)?;
-        delay_source.delay_us(10_000);
+        for step in sequence.steps() {
+            match *step {
+                InitStep::Command(instruction, params) => {
+                    self.send_command(instruction, params)?;
+                    // keep track of the state the sequence leaves the display in
+                    self.state.track_command(instruction, params);
+                }
+                InitStep::Raw(command, params) => {
+                    // whatever it does, the address window may not be what we think it is
+                    self.state.forget_window();
+                    self.di
+                        .send_command(command, params)
+                        .map_err(|_| Error::DisplayError)?;
+                }
+                InitStep::Delay(us) => delay_source.delay_us(us),
+            }
+        }
         Ok(())
     }
 
     ///
-    /// Performs a hard reset using the RST pin sequence
+    /// Performs a hard reset using the RST pin sequence.
+    /// The tracked state returns to the register defaults, with the display asleep.
     ///
     /// # Arguments
     ///
     /// * `delay_source` - mutable reference to a delay provider
     ///
-    pub fn hard_reset(&mut self, delay_source: &mut impl DelayUs<u32>) -> Result<(), Error<PinE>> {
+    pub fn hard_reset(&mut self, delay_source: &mut impl DelayNs) -> Result<(), Error<PinE>> {
         if let Some(rst) = self.rst.as_mut() {
             rst.set_high().map_err(Error::Pin)?;
             delay_source.delay_us(10); // ensure the pin change will get registered
@@ -219,6 +210,7 @@ where
             delay_source.delay_us(10); // ensure the pin change will get registered
             rst.set_high().map_err(Error::Pin)?;
             delay_source.delay_us(10); // ensure the pin change will get registered
+            self.state.reset();
         }
 
         Ok(())
@@ -227,7 +219,7 @@ where
     pub fn set_backlight(
         &mut self,
         state: BacklightState,
-        delay_source: &mut impl DelayUs<u32>,
+        delay_source: &mut impl DelayNs,
     ) -> Result<(), Error<PinE>> {
         if let Some(bl) = self.bl.as_mut() {
             match state {
@@ -239,36 +231,126 @@ where
         Ok(())
     }
 
+    ///
+    /// Returns the panel geometry
+    ///
+    pub fn panel(&self) -> PanelConfig {
+        self.state.panel
+    }
+
     ///
     /// Returns currently set orientation
     ///
     pub fn orientation(&self) -> Orientation {
-        self.orientation
+        self.state.madctl.orientation()
+    }
+
+    ///
+    /// Returns currently set memory access control value
+    ///
+    pub fn memory_access_control(&self) -> MemoryAccessControl {
+        self.state.madctl
     }
 
     ///
     /// Sets display orientation
     ///
-    pub fn set_orientation(&mut self, orientation: Orientation) -> Result<(), Error<PinE>> {
-        self.write_command(Instruction::MADCTL)?;
-        self.write_data(&[orientation as u8])?;
-        self.orientation = orientation;
-        let (xs, ys) = match (self.size_y, self.orientation) {
-            (_, Orientation::Portrait) => (0, 0), // gap would be at the bottom
-            (_, Orientation::Landscape) => (0, 0), // gap would be at the right
-            (sy, Orientation::PortraitSwapped) => (0, 320 - sy),
-            (sy, Orientation::LandscapeSwapped) => (320 - sy, 0),
-        };
-        self.x_start = xs;
-        self.y_start = ys;
+    /// # Arguments
+    ///
+    /// * `madctl` - an [`Orientation`] or a [`MemoryAccessControl`] value for mirroring and BGR panels
+    ///
+    pub fn set_orientation(
+        &mut self,
+        madctl: impl Into<MemoryAccessControl>,
+    ) -> Result<(), Error<PinE>> {
+        let madctl = madctl.into();
+        self.send_command(Instruction::MADCTL, &[madctl.bits()])?;
+        self.state.apply_madctl(madctl);
+        Ok(())
+    }
+
+    ///
+    /// Returns the current display mode
+    ///
+    pub fn display_mode(&self) -> DisplayMode {
+        self.state.mode
+    }
+
+    ///
+    /// Returns the interface pixel format pixel data is sent in
+    ///
+    /// This is RGB565 as set by [`InitSequence::DEFAULT`], sequences setting COLMOD or
+    /// resetting the display are tracked by [`ST7789::init_with`].
+    ///
+    pub fn pixel_format(&self) -> PixelFormat {
+        self.state.pixel_format
+    }
+
+    ///
+    /// Sets the interface pixel format, colors are converted to it when drawing
+    ///
+    /// RGB444 packs two pixels into three bytes and cuts the bus traffic by a quarter,
+    /// RGB666 sends three bytes per pixel and shows smoother gradients.
+    ///
+    /// # Arguments
+    ///
+    /// * `format` - pixel format to use for the RGB and the control interface
+    ///
+    pub fn set_pixel_format(&mut self, format: PixelFormat) -> Result<(), Error<PinE>> {
+        self.send_command(Instruction::COLMOD, &[format.colmod()])?;
+        self.state.pixel_format = format;
         Ok(())
     }
+
+    ///
+    /// Sets the lines shown in partial mode
+    ///
+    /// The partial area always spans whole panel lines. In landscape orientations these run
+    /// top to bottom, so `start_row` and `end_row` select columns there.
+    ///
+    /// # Arguments
+    ///
+    /// * `start_row` - first line of the partial area
+    /// * `end_row` - last line of the partial area, inclusive
+    ///
+    pub fn set_partial_area(&mut self, start_row: u16, end_row: u16) -> Result<(), Error<PinE>> {
+        let params = self
+            .state
+            .partial_area(start_row, end_row)
+            .ok_or(Error::InvalidArgument)?;
+
+        self.send_command(Instruction::PTLAR, &params)
+    }
+
+    ///
+    /// Turns on partial mode, only the lines set by [`Self::set_partial_area`] are shown
+    ///
+    pub fn enter_partial_mode(&mut self) -> Result<(), Error<PinE>> {
+        self.write_command(Instruction::PTLON)?;
+        self.state.mode = DisplayMode::Partial;
+        Ok(())
+    }
+
+    ///
+    /// Turns off partial mode, the whole panel is shown again
+    ///
+    pub fn enter_normal_mode(&mut self) -> Result<(), Error<PinE>> {
+        self.write_command(Instruction::NORON)?;
+        self.state.mode = DisplayMode::Normal;
+        Ok(())
+    }
+
     ///
     /// Blits raw pixel data to the display. The burden of choosing the correct
     /// pixel format is completely on the caller - on the other hand, this is
     /// probably the only way to get acceptable (or *any*, for that matter)
     /// DMA performance.
     ///
+    /// The data has to be encoded in the active [`PixelFormat`], see
+    /// [`PixelFormat::data_len`] for the expected length. A wrong data length or an area
+    /// reaching past the end of the address space is an [`Error::InvalidArgument`], an
+    /// empty area sends nothing.
+    ///
     /// # Arguments
     ///
     /// * `sx` - x coordinate start
@@ -287,12 +369,14 @@ where
     ) -> Result<(), Error<PinE>> {
         use DataFormat::U8;
 
-        if data.len() != (dx * dy * 2) as usize {
-            return Err(Error::DisplayError);
-        }
-        self.set_address_window(sx, sy, sx + dx - 1, sy + dy - 1)?;
+        let Some((ex, ey)) = self.state.blit_area(sx, sy, dx, dy, data.len())? else {
+            return Ok(());
+        };
+        self.set_address_window(sx, sy, ex, ey)?;
         self.write_command(Instruction::RAMWR)?;
-        self.send_data(U8(data)).map_err(|_| Error::DisplayError)
+        self.di
+            .write_data(U8(data))
+            .map_err(|_| Error::DisplayError)
     }
     ///
     /// Sets a pixel color at the given coords.
@@ -306,14 +390,12 @@ where
     pub fn set_pixel(&mut self, x: u16, y: u16, color: u16) -> Result<(), Error<PinE>> {
         self.set_address_window(x, y, x, y)?;
         self.write_command(Instruction::RAMWR)?;
-        self.send_data(DataFormat::U16BEIter(&mut once(color)))
-            .map_err(|_| Error::DisplayError)?;
-
-        Ok(())
+        self.write_pixels(once(color))
     }
 
     ///
     /// Sets pixel colors in given rectangle bounds.
+    /// The RGB565 colors are converted to the active [`PixelFormat`].
     ///
     /// # Arguments
     ///
@@ -336,7 +418,48 @@ where
     {
         self.set_address_window(sx, sy, ex, ey)?;
         self.write_command(Instruction::RAMWR)?;
-        self.send_data(DataFormat::U16BEIter(&mut colors.into_iter()))
+        self.write_pixels(colors.into_iter())
+    }
+
+    ///
+    /// Continues the last frame memory write with more pixel colors, using RAMWRC.
+    /// The colors go into the address window of the preceding write, e.g. [`Self::set_pixels`],
+    /// following the last pixel written, so a window can be filled across several calls.
+    /// The RGB565 colors are converted to the active [`PixelFormat`].
+    ///
+    /// # Arguments
+    ///
+    /// * `colors` - anything that can provide `IntoIterator<Item = u16>` to iterate over pixel data
+    ///
+    pub fn continue_pixels<T>(&mut self, colors: T) -> Result<(), Error<PinE>>
+    where
+        T: IntoIterator<Item = u16>,
+    {
+        self.write_command(Instruction::RAMWRC)?;
+        self.write_pixels(colors.into_iter())
+    }
+
+    // Sends RGB565 colors in the active pixel format
+    fn write_pixels(&mut self, mut colors: impl Iterator<Item = u16>) -> Result<(), Error<PinE>> {
+        let result = match self.state.pixel_format {
+            PixelFormat::Rgb565 => self.di.write_data(DataFormat::U16BEIter(&mut colors)),
+            format => {
+                let mut bytes = PixelBytes::new(format, colors.map(color::rgb565_to_rgb888));
+                self.di.write_data(DataFormat::U8Iter(&mut bytes))
+            }
+        };
+        result.map_err(|_| Error::DisplayError)
+    }
+
+    // Sends colors with 8 bits per channel in the active pixel format
+    #[cfg(feature = "graphics")]
+    pub(crate) fn write_pixels_rgb888(
+        &mut self,
+        colors: impl Iterator<Item = [u8; 3]>,
+    ) -> Result<(), Error<PinE>> {
+        let mut bytes = PixelBytes::new(self.state.pixel_format, colors);
+        self.di
+            .write_data(DataFormat::U8Iter(&mut bytes))
             .map_err(|_| Error::DisplayError)
     }
 
@@ -347,64 +470,61 @@ where
     /// * `offset` - scroll offset in pixels
     ///
     pub fn set_scroll_offset(&mut self, offset: u16) -> Result<(), Error<PinE>> {
-        self.write_command(Instruction::VSCAD)?;
-        self.write_data(&offset.to_be_bytes())
+        self.send_command(Instruction::VSCAD, &offset.to_be_bytes())
     }
 
     ///
-    /// Release resources allocated to this driver back.
-    /// This returns the display interface and the RST pin deconstructing the driver.
+    /// Defines the vertical scrolling area between fixed top and bottom areas
+    ///
+    /// # Arguments
     ///
-    pub fn release(self) -> (SPI, Option<OUT>, Option<OUT>) {
-        (self.spi, self.rst, self.bl)
+    /// * `top_fixed` - number of frame memory rows in the fixed top area
+    /// * `scroll_height` - number of frame memory rows in the scrolling area
+    /// * `bottom_fixed` - number of frame memory rows in the fixed bottom area, the three have to add up to 320
+    ///
+    pub fn define_scroll_area(
+        &mut self,
+        top_fixed: u16,
+        scroll_height: u16,
+        bottom_fixed: u16,
+    ) -> Result<ScrollRegion, Error<PinE>> {
+        let region = ScrollRegion::new(top_fixed, scroll_height, bottom_fixed)
+            .ok_or(Error::InvalidArgument)?;
+
+        self.send_command(Instruction::VSCRDER, &region.vscrder())?;
+        Ok(region)
     }
 
-    fn write_command(&mut self, command: Instruction) -> Result<(), Error<PinE>> {
-        self.send_commands(DataFormat::U8Iter(&mut once(command as u8)))
-            .map_err(|_| Error::DisplayError)?;
-        Ok(())
+    ///
+    /// Scrolls the display to the current offset of a scroll region
+    ///
+    /// # Arguments
+    ///
+    /// * `region` - region returned by [`Self::define_scroll_area`]
+    ///
+    pub fn scroll(&mut self, region: &ScrollRegion) -> Result<(), Error<PinE>> {
+        self.set_scroll_offset(region.start_address())
     }
 
-    fn write_data(&mut self, data: &[u8]) -> Result<(), Error<PinE>> {
-        self.send_data(DataFormat::U8Iter(&mut data.iter().cloned()))
-            .map_err(|_| Error::DisplayError)
+    ///
+    /// Release resources allocated to this driver back.
+    /// This returns the display interface and the RST pin deconstructing the driver.
+    ///
+    pub fn release(self) -> (DI, Option<OUT>, Option<OUT>) {
+        (self.di, self.rst, self.bl)
     }
-    fn send_commands(&mut self, cmds: DataFormat<'_>) -> Result<(), SpiError> {
-        // Assert chip select pin
-        if let Some(cs) = self.cs.as_mut() {
-            cs.set_low().map_err(|_| SpiError::CSError)?;
-        }
-        // 1 = data, 0 = command
-        if let Some(dc) = self.dc.as_mut() {
-            dc.set_low().map_err(|_| SpiError::DCError)?;
-        }
 
-        // Send words over SPI
-        let result = self.send_u8(cmds);
-        // Deassert chip select pin
-        if let Some(cs) = self.cs.as_mut() {
-            cs.set_high().ok();
-        }
-        result
+    fn write_command(&mut self, command: Instruction) -> Result<(), Error<PinE>> {
+        self.di
+            .write_command(command as u8)
+            .map_err(|_| Error::DisplayError)
     }
 
-    fn send_data(&mut self, buf: DataFormat<'_>) -> Result<(), SpiError> {
-        // Assert chip select pin
-        if let Some(cs) = self.cs.as_mut() {
-            cs.set_low().map_err(|_| SpiError::CSError)?;
-        }
-        // 1 = data, 0 = command
-        if let Some(dc) = self.dc.as_mut() {
-            dc.set_high().map_err(|_| SpiError::DCError)?;
-        }
-
-        // Send words over SPI
-        let result = self.send_u8(buf);
-        // Deassert chip select pin
-        if let Some(cs) = self.cs.as_mut() {
-            cs.set_high().ok();
-        }
-        result
+    // Sends a command with its parameters
+    fn send_command(&mut self, command: Instruction, params: &[u8]) -> Result<(), Error<PinE>> {
+        self.di
+            .send_command(command as u8, params)
+            .map_err(|_| Error::DisplayError)
     }
     // Sets the address window for the display.
     fn set_address_window(
@@ -420,133 +540,28 @@ where
         self.write_command(Instruction::RASET)?;
         self.write_data(&sy.to_be_bytes())?;
         self.write_data(&ey.to_be_bytes()) */
-        self.write_command(Instruction::CASET)?;
-        self.write_data(&(sx + self.x_start).to_be_bytes())?;
-        self.write_data(&(ex + self.x_start).to_be_bytes())?;
-        self.write_command(Instruction::RASET)?;
-        self.write_data(&(sy + self.y_start).to_be_bytes())?;
-        self.write_data(&(ey + self.y_start).to_be_bytes())
+        for (instruction, params) in self
+            .state
+            .window_commands(sx, sy, ex, ey)
+            .into_iter()
+            .flatten()
+        {
+            let result = self.send_command(instruction, &params);
+            if result.is_err() {
+                // the display may hold either window now
+                self.state.forget_window();
+                return result;
+            }
+            self.state.track_command(instruction, &params);
+        }
+        Ok(())
     }
 
     ///
     /// Configures the tearing effect output.
     ///
     pub fn set_tearing_effect(&mut self, tearing_effect: TearingEffect) -> Result<(), Error<PinE>> {
-        match tearing_effect {
-            TearingEffect::Off => self.write_command(Instruction::TEOFF),
-            TearingEffect::Vertical => {
-                self.write_command(Instruction::TEON)?;
-                self.write_data(&[0])
-            }
-            TearingEffect::HorizontalAndVertical => {
-                self.write_command(Instruction::TEON)?;
-                self.write_data(&[1])
-            }
-        }
-    }
-
-    #[allow(unreachable_patterns)]
-    fn send_u8(&mut self, words: DataFormat<'_>) -> Result<(), SpiError> {
-        match words {
-            DataFormat::U8(slice) => self.spi.write(slice).map_err(|_| SpiError::BusWriteError),
-            DataFormat::U16(slice) => {
-                use byte_slice_cast::*;
-                self.spi
-                    .write(slice.as_byte_slice())
-                    .map_err(|_| SpiError::BusWriteError)
-            }
-            DataFormat::U16LE(slice) => {
-                use byte_slice_cast::*;
-                for v in slice.as_mut() {
-                    *v = v.to_le();
-                }
-                self.spi
-                    .write(slice.as_byte_slice())
-                    .map_err(|_| SpiError::BusWriteError)
-            }
-            DataFormat::U16BE(slice) => {
-                use byte_slice_cast::*;
-                for v in slice.as_mut() {
-                    *v = v.to_be();
-                }
-                self.spi
-                    .write(slice.as_byte_slice())
-                    .map_err(|_| SpiError::BusWriteError)
-            }
-            DataFormat::U8Iter(iter) => {
-                let mut buf = [0; 32];
-                let mut i = 0;
-
-                for v in iter.into_iter() {
-                    buf[i] = v;
-                    i += 1;
-
-                    if i == buf.len() {
-                        self.spi.write(&buf).map_err(|_| SpiError::BusWriteError)?;
-                        i = 0;
-                    }
-                }
-
-                if i > 0 {
-                    self.spi
-                        .write(&buf[..i])
-                        .map_err(|_| SpiError::BusWriteError)?;
-                }
-
-                Ok(())
-            }
-            DataFormat::U16LEIter(iter) => {
-                use byte_slice_cast::*;
-                let mut buf = [0; 32];
-                let mut i = 0;
-
-                for v in iter.map(u16::to_le) {
-                    buf[i] = v;
-                    i += 1;
-
-                    if i == buf.len() {
-                        self.spi
-                            .write(&buf.as_byte_slice())
-                            .map_err(|_| SpiError::BusWriteError)?;
-                        i = 0;
-                    }
-                }
-
-                if i > 0 {
-                    self.spi
-                        .write(&buf[..i].as_byte_slice())
-                        .map_err(|_| SpiError::BusWriteError)?;
-                }
-
-                Ok(())
-            }
-            DataFormat::U16BEIter(iter) => {
-                use byte_slice_cast::*;
-                let mut buf = [0; 64];
-                let mut i = 0;
-                let len = buf.len();
-
-                for v in iter.map(u16::to_be) {
-                    buf[i] = v;
-                    i += 1;
-
-                    if i == len {
-                        self.spi
-                            .write(&buf.as_byte_slice())
-                            .map_err(|_| SpiError::BusWriteError)?;
-                        i = 0;
-                    }
-                }
-
-                if i > 0 {
-                    self.spi
-                        .write(&buf[..i].as_byte_slice())
-                        .map_err(|_| SpiError::BusWriteError)?;
-                }
-
-                Ok(())
-            }
-            _ => Err(SpiError::DataFormatNotImplemented),
-        }
+        let (instruction, params) = tearing_effect.command();
+        self.send_command(instruction, params)
     }
 }
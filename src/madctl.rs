@@ -0,0 +1,169 @@
+use crate::Orientation;
+
+// MADCTL bits
+const MY: u8 = 0b1000_0000;
+const MX: u8 = 0b0100_0000;
+const MV: u8 = 0b0010_0000;
+const ML: u8 = 0b0001_0000;
+const RGB: u8 = 0b0000_1000;
+const MH: u8 = 0b0000_0100;
+
+///
+/// Memory data access control (MADCTL) register value.
+///
+/// Composes the address order, exchange, refresh order and color order bits. Any
+/// [`Orientation`] converts into it, the builder methods then add mirroring or BGR order:
+///
+/// ```
+/// use st7789::{MemoryAccessControl, Orientation};
+///
+/// // landscape on a BGR wired panel, mirrored left to right
+/// let madctl = MemoryAccessControl::from(Orientation::Landscape)
+///     .bgr(true)
+///     .flip_horizontal();
+/// assert_eq!(madctl.bits(), 0b1110_1000);
+/// ```
+///
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct MemoryAccessControl(u8);
+
+impl MemoryAccessControl {
+    ///
+    /// Creates a new value with all bits cleared, which is portrait orientation in RGB order
+    ///
+    pub const fn new() -> Self {
+        Self(0)
+    }
+
+    ///
+    /// Creates a new value from the raw register byte
+    ///
+    pub const fn from_bits(bits: u8) -> Self {
+        Self(bits & !0b0000_0011)
+    }
+
+    ///
+    /// Returns the raw register byte
+    ///
+    pub const fn bits(self) -> u8 {
+        self.0
+    }
+
+    ///
+    /// Sets MY, bottom to top page address order
+    ///
+    pub const fn row_address_order(self, reversed: bool) -> Self {
+        self.with(MY, reversed)
+    }
+
+    ///
+    /// Sets MX, right to left column address order
+    ///
+    pub const fn column_address_order(self, reversed: bool) -> Self {
+        self.with(MX, reversed)
+    }
+
+    ///
+    /// Sets MV, exchanging rows and columns
+    ///
+    pub const fn row_column_exchange(self, exchanged: bool) -> Self {
+        self.with(MV, exchanged)
+    }
+
+    ///
+    /// Sets ML, refreshing the panel bottom to top instead of top to bottom
+    ///
+    pub const fn line_address_order(self, reversed: bool) -> Self {
+        self.with(ML, reversed)
+    }
+
+    ///
+    /// Sets the RGB bit, sending color components in BGR order for BGR wired panels
+    ///
+    pub const fn bgr(self, bgr: bool) -> Self {
+        self.with(RGB, bgr)
+    }
+
+    ///
+    /// Sets MH, latching display data right to left instead of left to right
+    ///
+    pub const fn display_data_latch_order(self, reversed: bool) -> Self {
+        self.with(MH, reversed)
+    }
+
+    ///
+    /// Mirrors the picture left to right in the current orientation
+    ///
+    pub const fn flip_horizontal(self) -> Self {
+        if self.is_exchanged() {
+            Self(self.0 ^ MY)
+        } else {
+            Self(self.0 ^ MX)
+        }
+    }
+
+    ///
+    /// Mirrors the picture top to bottom in the current orientation
+    ///
+    pub const fn flip_vertical(self) -> Self {
+        if self.is_exchanged() {
+            Self(self.0 ^ MX)
+        } else {
+            Self(self.0 ^ MY)
+        }
+    }
+
+    ///
+    /// Returns true if the page (row) address order is reversed
+    ///
+    pub const fn is_row_order_reversed(self) -> bool {
+        self.0 & MY != 0
+    }
+
+    ///
+    /// Returns true if the column address order is reversed
+    ///
+    pub const fn is_column_order_reversed(self) -> bool {
+        self.0 & MX != 0
+    }
+
+    ///
+    /// Returns true if rows and columns are exchanged, i.e. in landscape orientations
+    ///
+    pub const fn is_exchanged(self) -> bool {
+        self.0 & MV != 0
+    }
+
+    ///
+    /// Returns true if color components are sent in BGR order
+    ///
+    pub const fn is_bgr(self) -> bool {
+        self.0 & RGB != 0
+    }
+
+    ///
+    /// Returns the orientation closest to this value, ignoring horizontal mirroring
+    ///
+    pub const fn orientation(self) -> Orientation {
+        match (self.is_exchanged(), self.is_row_order_reversed()) {
+            (false, false) => Orientation::Portrait,
+            (false, true) => Orientation::PortraitSwapped,
+            (true, false) => Orientation::Landscape,
+            (true, true) => Orientation::LandscapeSwapped,
+        }
+    }
+
+    const fn with(self, bit: u8, set: bool) -> Self {
+        if set {
+            Self(self.0 | bit)
+        } else {
+            Self(self.0 & !bit)
+        }
+    }
+}
+
+impl From<Orientation> for MemoryAccessControl {
+    fn from(orientation: Orientation) -> Self {
+        Self(orientation as u8)
+    }
+}
@@ -0,0 +1,144 @@
+use crate::MemoryAccessControl;
+
+/// Number of columns in the controller's frame memory
+pub(crate) const GRAM_WIDTH: u16 = 240;
+/// Number of rows in the controller's frame memory
+pub(crate) const GRAM_HEIGHT: u16 = 320;
+
+///
+/// Geometry of a panel and where it sits in the controller's 240x320 frame memory.
+///
+/// All values are given for the native portrait orientation. Panels smaller than the
+/// frame memory are usually wired to a window in the middle of it, so every orientation
+/// needs its own column/row start.
+///
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct PanelConfig {
+    /// Visible width in pixels
+    pub width: u16,
+    /// Visible height in pixels
+    pub height: u16,
+    /// First frame memory column wired to the panel
+    pub col_offset: u16,
+    /// First frame memory row wired to the panel
+    pub row_offset: u16,
+}
+
+impl PanelConfig {
+    ///
+    /// Creates a new panel config wired to the top left corner of the frame memory
+    ///
+    /// # Arguments
+    ///
+    /// * `width` - visible width in pixels in portrait orientation
+    /// * `height` - visible height in pixels in portrait orientation
+    ///
+    pub const fn new(width: u16, height: u16) -> Self {
+        Self {
+            width,
+            height,
+            col_offset: 0,
+            row_offset: 0,
+        }
+    }
+
+    ///
+    /// Returns the config with the given frame memory offsets
+    ///
+    /// # Arguments
+    ///
+    /// * `col_offset` - first frame memory column wired to the panel
+    /// * `row_offset` - first frame memory row wired to the panel
+    ///
+    pub const fn with_offset(self, col_offset: u16, row_offset: u16) -> Self {
+        Self {
+            col_offset,
+            row_offset,
+            ..self
+        }
+    }
+
+    ///
+    /// Returns the visible size (x, y) in the given orientation
+    ///
+    /// # Arguments
+    ///
+    /// * `madctl` - an [`crate::Orientation`] or a full [`MemoryAccessControl`] value
+    ///
+    pub fn size(&self, madctl: impl Into<MemoryAccessControl>) -> (u16, u16) {
+        if madctl.into().is_exchanged() {
+            (self.height, self.width)
+        } else {
+            (self.width, self.height)
+        }
+    }
+
+    ///
+    /// Returns the address offsets (x_start, y_start) to add to coordinates in the given orientation
+    ///
+    /// # Arguments
+    ///
+    /// * `madctl` - an [`crate::Orientation`] or a full [`MemoryAccessControl`] value
+    ///
+    pub fn offsets(&self, madctl: impl Into<MemoryAccessControl>) -> (u16, u16) {
+        let madctl = madctl.into();
+        // a reversed axis counts from the far side of the panel
+        let col_start = if madctl.is_column_order_reversed() {
+            GRAM_WIDTH.saturating_sub(self.width + self.col_offset)
+        } else {
+            self.col_offset
+        };
+        let row_start = if madctl.is_row_order_reversed() {
+            GRAM_HEIGHT.saturating_sub(self.height + self.row_offset)
+        } else {
+            self.row_offset
+        };
+
+        if madctl.is_exchanged() {
+            (row_start, col_start)
+        } else {
+            (col_start, row_start)
+        }
+    }
+}
+
+///
+/// Known ST7789 modules with their panel geometry.
+///
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DisplayModel {
+    /// 240x240 1.3" and 1.54" modules (Pimoroni, Adafruit, PineTime)
+    Pimoroni240x240,
+    /// 135x240 1.14" modules (TTGO T-Display, Waveshare Pico LCD 1.14, Adafruit)
+    Ttgo135x240,
+    /// 240x280 1.69" modules with rounded corners (Waveshare)
+    Waveshare240x280,
+    /// 172x320 1.47" modules with rounded corners (Waveshare)
+    Waveshare172x320,
+    /// 170x320 1.9" modules (Adafruit, LilyGo T-Display S3)
+    Generic170x320,
+    /// 240x320 2.0", 2.4" and 2.8" modules using the whole frame memory
+    Generic240x320,
+}
+
+impl DisplayModel {
+    ///
+    /// Returns the panel geometry of the module
+    ///
+    pub const fn config(self) -> PanelConfig {
+        match self {
+            Self::Pimoroni240x240 => PanelConfig::new(240, 240),
+            Self::Ttgo135x240 => PanelConfig::new(135, 240).with_offset(52, 40),
+            Self::Waveshare240x280 => PanelConfig::new(240, 280).with_offset(0, 20),
+            Self::Waveshare172x320 => PanelConfig::new(172, 320).with_offset(34, 0),
+            Self::Generic170x320 => PanelConfig::new(170, 320).with_offset(35, 0),
+            Self::Generic240x320 => PanelConfig::new(240, 320),
+        }
+    }
+}
+
+impl From<DisplayModel> for PanelConfig {
+    fn from(model: DisplayModel) -> Self {
+        model.config()
+    }
+}
@@ -0,0 +1,122 @@
+use embedded_hal::{delay::DelayNs, digital::OutputPin};
+
+use crate::{DisplayInterface, Error, Instruction, ST7789};
+
+// Wait after SLPIN, SLPOUT may only follow 120 ms later (other commands after 5 ms)
+pub(crate) const SLEEP_IN_DELAY_US: u32 = 120_000;
+// Wait after SLPOUT for the supply voltages and clocks to settle
+pub(crate) const SLEEP_OUT_DELAY_US: u32 = 120_000;
+
+///
+/// Power state of the display as tracked by the driver.
+///
+/// The default is the state after a reset: asleep with the display off.
+///
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct PowerState {
+    /// Display is in sleep mode, the DC/DC converter and oscillator are off
+    pub sleeping: bool,
+    /// Display output is on
+    pub display_on: bool,
+    /// Idle mode is on, showing 8 colors only
+    pub idle_mode: bool,
+}
+
+impl Default for PowerState {
+    fn default() -> Self {
+        Self {
+            sleeping: true,
+            display_on: false,
+            idle_mode: false,
+        }
+    }
+}
+
+impl<DI, OUT, PinE> ST7789<DI, OUT>
+where
+    DI: DisplayInterface,
+    OUT: OutputPin<Error = PinE>,
+{
+    ///
+    /// Returns the power state the driver put the display in
+    ///
+    pub fn power_state(&self) -> PowerState {
+        self.state.power
+    }
+
+    ///
+    /// Puts the display to sleep, cutting the current drawn to the standby level.
+    /// The frame memory keeps its contents.
+    ///
+    /// Waits 120 ms before returning, the time the controller needs before it can be
+    /// woken up again.
+    ///
+    /// # Arguments
+    ///
+    /// * `delay_source` - mutable reference to a delay provider
+    ///
+    pub fn sleep(&mut self, delay_source: &mut impl DelayNs) -> Result<(), Error<PinE>> {
+        if self.state.power.sleeping {
+            return Err(Error::InvalidState);
+        }
+        self.write_command(Instruction::SLPIN)?;
+        self.state.track_command(Instruction::SLPIN, &[]);
+        delay_source.delay_us(SLEEP_IN_DELAY_US);
+        Ok(())
+    }
+
+    ///
+    /// Wakes the display up from sleep, waiting 120 ms for it to become ready
+    ///
+    /// # Arguments
+    ///
+    /// * `delay_source` - mutable reference to a delay provider
+    ///
+    pub fn wake(&mut self, delay_source: &mut impl DelayNs) -> Result<(), Error<PinE>> {
+        if !self.state.power.sleeping {
+            return Err(Error::InvalidState);
+        }
+        self.write_command(Instruction::SLPOUT)?;
+        self.state.track_command(Instruction::SLPOUT, &[]);
+        delay_source.delay_us(SLEEP_OUT_DELAY_US);
+        Ok(())
+    }
+
+    ///
+    /// Turns the display output on, showing the frame memory
+    ///
+    pub fn display_on(&mut self) -> Result<(), Error<PinE>> {
+        self.write_command(Instruction::DISPON)?;
+        self.state.track_command(Instruction::DISPON, &[]);
+        Ok(())
+    }
+
+    ///
+    /// Turns the display output off, showing a blank screen. The frame memory can still
+    /// be written.
+    ///
+    pub fn display_off(&mut self) -> Result<(), Error<PinE>> {
+        self.write_command(Instruction::DISPOFF)?;
+        self.state.track_command(Instruction::DISPOFF, &[]);
+        Ok(())
+    }
+
+    ///
+    /// Turns idle mode on or off. Idle mode shows 8 colors using only the most significant
+    /// bit of every channel and lowers the power consumption.
+    ///
+    /// # Arguments
+    ///
+    /// * `idle` - true to turn idle mode on
+    ///
+    pub fn idle_mode(&mut self, idle: bool) -> Result<(), Error<PinE>> {
+        let instruction = if idle {
+            Instruction::IDMON
+        } else {
+            Instruction::IDMOFF
+        };
+        self.write_command(instruction)?;
+        self.state.track_command(instruction, &[]);
+        Ok(())
+    }
+}
@@ -0,0 +1,186 @@
+use embedded_hal::digital::OutputPin;
+
+use crate::{
+    Error, Instruction, MemoryAccessControl, PixelFormat, ReadInterface, TearingEffect, ST7789,
+};
+
+///
+/// Display identification as read by RDDID.
+///
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct DisplayId {
+    /// LCD module manufacturer ID
+    pub manufacturer: u8,
+    /// LCD module/driver version ID
+    pub version: u8,
+    /// LCD module/driver ID
+    pub module: u8,
+}
+
+///
+/// Display status as read by RDDST.
+///
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct DisplayStatus {
+    /// Booster voltage is on
+    pub booster_on: bool,
+    /// Memory access control, including the orientation
+    pub madctl: MemoryAccessControl,
+    /// Interface pixel format, `None` for reserved values
+    pub pixel_format: Option<PixelFormat>,
+    /// Idle mode is on
+    pub idle_mode: bool,
+    /// Partial mode is on
+    pub partial_mode: bool,
+    /// Display is in sleep mode
+    pub sleeping: bool,
+    /// Normal display mode is on
+    pub normal_mode: bool,
+    /// Vertical scrolling is on
+    pub scrolling: bool,
+    /// Display inversion is on
+    pub inverted: bool,
+    /// Display is on
+    pub display_on: bool,
+    /// Tearing effect output
+    pub tearing_effect: TearingEffect,
+    /// Selected gamma curve
+    pub gamma_curve: u8,
+}
+
+///
+/// Display power mode as read by RDDPM.
+///
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct PowerMode {
+    /// Booster voltage is on
+    pub booster_on: bool,
+    /// Idle mode is on
+    pub idle_mode: bool,
+    /// Partial mode is on
+    pub partial_mode: bool,
+    /// Display is in sleep mode
+    pub sleeping: bool,
+    /// Normal display mode is on
+    pub normal_mode: bool,
+    /// Display is on
+    pub display_on: bool,
+}
+
+impl From<[u8; 4]> for DisplayStatus {
+    fn from(bytes: [u8; 4]) -> Self {
+        let bits = u32::from_be_bytes(bytes);
+        let bit = |n: u32| bits & (1 << n) != 0;
+
+        Self {
+            booster_on: bit(31),
+            // D30..D25 are MY, MX, MV, ML, RGB and MH
+            madctl: MemoryAccessControl::from_bits(bytes[0] << 1),
+            pixel_format: PixelFormat::try_from(bytes[1] >> 4 & 0b111).ok(),
+            idle_mode: bit(19),
+            partial_mode: bit(18),
+            sleeping: !bit(17),
+            normal_mode: bit(16),
+            scrolling: bit(15),
+            inverted: bit(13),
+            display_on: bit(10),
+            tearing_effect: match (bit(9), bit(5)) {
+                (false, _) => TearingEffect::Off,
+                (true, false) => TearingEffect::Vertical,
+                (true, true) => TearingEffect::HorizontalAndVertical,
+            },
+            gamma_curve: (bits >> 6 & 0b111) as u8,
+        }
+    }
+}
+
+impl From<u8> for PowerMode {
+    fn from(bits: u8) -> Self {
+        let bit = |n: u8| bits & (1 << n) != 0;
+
+        Self {
+            booster_on: bit(7),
+            idle_mode: bit(6),
+            partial_mode: bit(5),
+            sleeping: !bit(4),
+            normal_mode: bit(3),
+            display_on: bit(2),
+        }
+    }
+}
+
+///
+/// Register readback, for displays with their data output wired to the bus.
+///
+/// Reads go out as a single transaction: the command byte (or 9-bit frame without a DC pin)
+/// followed by the dummy clock cycle some registers need and the register value, with CS
+/// asserted throughout. On displays sharing one data line for both directions the
+/// [`ReadInterface`] implementation has to turn the line around after the command.
+///
+impl<DI, OUT, PinE> ST7789<DI, OUT>
+where
+    DI: ReadInterface,
+    OUT: OutputPin<Error = PinE>,
+{
+    ///
+    /// Reads the manufacturer, version and module IDs
+    ///
+    pub fn read_display_id(&mut self) -> Result<DisplayId, Error<PinE>> {
+        let [manufacturer, version, module] = self.read_register(Instruction::RDDID, 1)?;
+        Ok(DisplayId {
+            manufacturer,
+            version,
+            module,
+        })
+    }
+
+    ///
+    /// Reads the display status
+    ///
+    pub fn read_display_status(&mut self) -> Result<DisplayStatus, Error<PinE>> {
+        self.read_register(Instruction::RDDST, 1)
+            .map(DisplayStatus::from)
+    }
+
+    ///
+    /// Reads the display power mode
+    ///
+    pub fn read_power_mode(&mut self) -> Result<PowerMode, Error<PinE>> {
+        let [bits] = self.read_register(Instruction::RDDPM, 0)?;
+        Ok(PowerMode::from(bits))
+    }
+
+    ///
+    /// Reads the memory access control value the display is using
+    ///
+    pub fn read_memory_access_control(&mut self) -> Result<MemoryAccessControl, Error<PinE>> {
+        let [bits] = self.read_register(Instruction::RDDMADCTL, 0)?;
+        Ok(MemoryAccessControl::from_bits(bits))
+    }
+
+    // Sends a read command and returns the N bytes clocked in after `dummy_bits`
+    fn read_register<const N: usize>(
+        &mut self,
+        instruction: Instruction,
+        dummy_bits: usize,
+    ) -> Result<[u8; N], Error<PinE>> {
+        let mut buf = [0; 6];
+        let offset = self.di.command_bits() + dummy_bits;
+        let len = (offset + N * 8).div_ceil(8);
+
+        self.di
+            .read(instruction as u8, &mut buf[..len])
+            .map_err(|_| Error::DisplayError)?;
+        let read = &buf;
+
+        let mut value = [0; N];
+        for (i, byte) in value.iter_mut().enumerate() {
+            let (index, shift) = ((offset + i * 8) / 8, (offset + i * 8) % 8);
+            *byte = read[index] << shift;
+            if shift > 0 {
+                *byte |= read[index + 1] >> (8 - shift);
+            }
+        }
+        Ok(value)
+    }
+}
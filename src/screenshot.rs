@@ -0,0 +1,195 @@
+use embedded_graphics_core::pixelcolor::{Rgb565, Rgb888};
+use embedded_graphics_core::prelude::{Dimensions, Point, RgbColor, Size};
+use embedded_graphics_core::primitives::Rectangle;
+use embedded_hal::digital::OutputPin;
+
+use crate::{Error, ImageFormat, Instruction, ReadInterface, ST7789};
+
+// Bytes clocked in per transaction while reading pixels
+const CHUNK_LEN: usize = 48;
+
+///
+/// Decodes a pixel in the 18-bit format memory reads use, 6 bits per channel in the
+/// upper bits of each byte
+///
+pub(crate) fn decode_rgb666(bytes: [u8; 3]) -> Rgb565 {
+    Rgb565::new(bytes[0] >> 3, bytes[1] >> 2, bytes[2] >> 3)
+}
+
+///
+/// Iterator over the pixels of an area read back from the frame memory,
+/// see [`ST7789::read_pixels`].
+///
+pub struct PixelReader<'a, DI, OUT>
+where
+    DI: ReadInterface,
+    OUT: OutputPin,
+{
+    display: &'a mut ST7789<DI, OUT>,
+    // Pixels left to read
+    remaining: usize,
+    started: bool,
+    buf: [u8; CHUNK_LEN],
+    // Pixels of the current chunk not decoded yet
+    chunk: usize,
+    // Bit offset of the next pixel in `buf`
+    offset: usize,
+}
+
+impl<DI, OUT, PinE> PixelReader<'_, DI, OUT>
+where
+    DI: ReadInterface,
+    OUT: OutputPin<Error = PinE>,
+{
+    // Reads the next chunk in one transaction, the first one starts the memory read with
+    // RAMRD and the following ones continue it with RAMRDC
+    fn refill(&mut self) -> Result<(), Error<PinE>> {
+        let command = if self.started {
+            Instruction::RAMRDC
+        } else {
+            Instruction::RAMRD
+        } as u8;
+        self.started = true;
+        self.buf = [0; CHUNK_LEN];
+        // a dummy read cycle precedes the pixel data
+        self.offset = self.display.di.command_bits() + 8;
+        self.chunk = self.remaining.min((CHUNK_LEN * 8 - self.offset) / 24);
+
+        let len = (self.offset + self.chunk * 24).div_ceil(8);
+        self.display
+            .di
+            .read(command, &mut self.buf[..len])
+            .map_err(|_| Error::DisplayError)
+    }
+
+    fn read_pixel(&mut self) -> Result<Rgb565, Error<PinE>> {
+        if self.chunk == 0 {
+            self.refill()?;
+        }
+
+        let mut pixel = [0; 3];
+        for byte in pixel.iter_mut() {
+            let (index, shift) = (self.offset / 8, self.offset % 8);
+            *byte = self.buf[index] << shift;
+            if shift > 0 {
+                *byte |= self.buf[index + 1] >> (8 - shift);
+            }
+            self.offset += 8;
+        }
+        self.chunk -= 1;
+        Ok(decode_rgb666(pixel))
+    }
+}
+
+impl<DI, OUT, PinE> Iterator for PixelReader<'_, DI, OUT>
+where
+    DI: ReadInterface,
+    OUT: OutputPin<Error = PinE>,
+{
+    type Item = Result<Rgb565, Error<PinE>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        let result = self.read_pixel();
+        self.remaining = if result.is_ok() {
+            self.remaining - 1
+        } else {
+            0
+        };
+        Some(result)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<DI, OUT, PinE> ExactSizeIterator for PixelReader<'_, DI, OUT>
+where
+    DI: ReadInterface,
+    OUT: OutputPin<Error = PinE>,
+{
+}
+
+///
+/// Frame memory readback, for displays with their data output wired to the bus.
+///
+/// A [`PixelReader`] reads in chunks of one transaction each. CS is deasserted between
+/// them, so every chunk after the first continues the memory read with RAMRDC.
+///
+impl<DI, OUT, PinE> ST7789<DI, OUT>
+where
+    DI: ReadInterface,
+    OUT: OutputPin<Error = PinE>,
+{
+    ///
+    /// Reads back the pixels of an area, row by row.
+    ///
+    /// The address window is set right away, the memory read starts with the first pixel
+    /// taken from the returned iterator.
+    ///
+    /// # Arguments
+    ///
+    /// * `area` - area to read, has to lie within the display
+    ///
+    pub fn read_pixels(
+        &mut self,
+        area: Rectangle,
+    ) -> Result<PixelReader<'_, DI, OUT>, Error<PinE>> {
+        if area.intersection(&self.bounding_box()) != area {
+            return Err(Error::InvalidArgument);
+        }
+
+        let remaining = (area.size.width * area.size.height) as usize;
+        if let Some(bottom_right) = area.bottom_right() {
+            self.set_address_window(
+                area.top_left.x as u16,
+                area.top_left.y as u16,
+                bottom_right.x as u16,
+                bottom_right.y as u16,
+            )?;
+        }
+
+        Ok(PixelReader {
+            display: self,
+            remaining,
+            started: false,
+            buf: [0; CHUNK_LEN],
+            chunk: 0,
+            offset: 0,
+        })
+    }
+
+    ///
+    /// Captures what the display is showing as an image, reading the frame memory row by row
+    ///
+    /// # Arguments
+    ///
+    /// * `format` - image file format
+    /// * `sink` - receives the encoded image bytes in order
+    ///
+    pub fn screenshot(
+        &mut self,
+        format: ImageFormat,
+        mut sink: impl FnMut(&[u8]),
+    ) -> Result<(), Error<PinE>> {
+        let size = self.bounding_box().size;
+
+        format.encode(
+            size.width as u16,
+            size.height as u16,
+            |y, row| {
+                let area = Rectangle::new(Point::new(0, y.into()), Size::new(size.width, 1));
+                for (pixel, color) in row.chunks_exact_mut(3).zip(self.read_pixels(area)?) {
+                    let color = Rgb888::from(color?);
+                    pixel.copy_from_slice(&[color.r(), color.g(), color.b()]);
+                }
+                Ok(())
+            },
+            &mut sink,
+        )
+    }
+}
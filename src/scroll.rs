@@ -0,0 +1,144 @@
+use crate::panel::GRAM_HEIGHT;
+
+///
+/// Vertical scrolling area with fixed top and bottom areas, as set by VSCRDER.
+///
+/// Hardware scrolling rotates the frame memory rows of the scrolling area through the
+/// lines in between the fixed areas. The helper keeps track of the scroll offset and maps
+/// the panel line content should appear on to the frame memory row it has to be drawn to:
+///
+/// ```
+/// use st7789::ScrollRegion;
+///
+/// // 16 line header, 16 line footer
+/// let mut region = ScrollRegion::new(16, 288, 16).unwrap();
+/// region.scroll_by(10);
+/// assert_eq!(region.gram_row(0), 0); // header stays put
+/// assert_eq!(region.gram_row(16), 26); // first scrolling line
+/// assert_eq!(region.gram_row(303), 25); // last scrolling line wraps around
+/// assert_eq!(region.gram_row(310), 310); // footer stays put
+/// ```
+///
+/// Rows are frame memory rows in [`crate::Orientation::Portrait`], which is the display
+/// row plus the panel's row offset.
+///
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ScrollRegion {
+    top_fixed: u16,
+    scroll_height: u16,
+    bottom_fixed: u16,
+    offset: u16,
+}
+
+impl ScrollRegion {
+    ///
+    /// Creates a new region scrolled to the top, returns `None` unless the areas add up to
+    /// the 320 frame memory rows and the scrolling area is not empty
+    ///
+    /// # Arguments
+    ///
+    /// * `top_fixed` - number of rows in the fixed top area
+    /// * `scroll_height` - number of rows in the scrolling area
+    /// * `bottom_fixed` - number of rows in the fixed bottom area
+    ///
+    pub const fn new(top_fixed: u16, scroll_height: u16, bottom_fixed: u16) -> Option<Self> {
+        if scroll_height == 0
+            || top_fixed as u32 + scroll_height as u32 + bottom_fixed as u32 != GRAM_HEIGHT as u32
+        {
+            return None;
+        }
+
+        Some(Self {
+            top_fixed,
+            scroll_height,
+            bottom_fixed,
+            offset: 0,
+        })
+    }
+
+    ///
+    /// Returns the number of rows in the fixed top area
+    ///
+    pub fn top_fixed(&self) -> u16 {
+        self.top_fixed
+    }
+
+    ///
+    /// Returns the number of rows in the scrolling area
+    ///
+    pub fn scroll_height(&self) -> u16 {
+        self.scroll_height
+    }
+
+    ///
+    /// Returns the number of rows in the fixed bottom area
+    ///
+    pub fn bottom_fixed(&self) -> u16 {
+        self.bottom_fixed
+    }
+
+    ///
+    /// Returns the number of lines the content is scrolled up by
+    ///
+    pub fn offset(&self) -> u16 {
+        self.offset
+    }
+
+    ///
+    /// Scrolls the content to the given offset, wrapping around the scrolling area
+    ///
+    pub fn set_offset(&mut self, offset: u16) {
+        self.offset = offset % self.scroll_height;
+    }
+
+    ///
+    /// Scrolls the content up by the given number of lines, or down if negative
+    ///
+    pub fn scroll_by(&mut self, lines: i32) {
+        let offset = (i32::from(self.offset) + lines).rem_euclid(i32::from(self.scroll_height));
+        self.offset = offset as u16;
+    }
+
+    ///
+    /// Returns true if the given panel line is part of the scrolling area
+    ///
+    pub fn is_scrolling(&self, line: u16) -> bool {
+        line >= self.top_fixed && line < self.top_fixed + self.scroll_height
+    }
+
+    ///
+    /// Returns the vertical scroll start address (VSCAD) for the current offset
+    ///
+    pub fn start_address(&self) -> u16 {
+        self.top_fixed + self.offset
+    }
+
+    ///
+    /// Returns the frame memory row shown on the given panel line
+    ///
+    pub fn gram_row(&self, line: u16) -> u16 {
+        if !self.is_scrolling(line) {
+            return line;
+        }
+        self.top_fixed + (line - self.top_fixed + self.offset) % self.scroll_height
+    }
+
+    // VSCRDER parameters defining the areas
+    pub(crate) fn vscrder(&self) -> [u8; 6] {
+        let [t0, t1] = self.top_fixed.to_be_bytes();
+        let [h0, h1] = self.scroll_height.to_be_bytes();
+        let [b0, b1] = self.bottom_fixed.to_be_bytes();
+        [t0, t1, h0, h1, b0, b1]
+    }
+}
+
+impl Default for ScrollRegion {
+    fn default() -> Self {
+        Self {
+            top_fixed: 0,
+            scroll_height: GRAM_HEIGHT,
+            bottom_fixed: 0,
+            offset: 0,
+        }
+    }
+}
@@ -0,0 +1,186 @@
+use crate::frame_rate::FrameTiming;
+use crate::panel::GRAM_HEIGHT;
+use crate::{
+    DisplayMode, Error, FrameRate, Instruction, MemoryAccessControl, PanelConfig, PixelFormat,
+    PorchConfig, PowerState,
+};
+
+///
+/// Panel geometry and the state the commands sent so far left the display in.
+///
+/// Both the blocking and the async driver keep one and encode the parameters of state
+/// dependent commands through it.
+///
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct DisplayState {
+    // Panel geometry
+    pub(crate) panel: PanelConfig,
+    // Current memory access control, including orientation
+    pub(crate) madctl: MemoryAccessControl,
+    // Normal or partial display mode
+    pub(crate) mode: DisplayMode,
+    // Interface pixel format pixel data is encoded in
+    pub(crate) pixel_format: PixelFormat,
+    // Sleep, display on and idle mode
+    pub(crate) power: PowerState,
+    // Frame rate and porch settings
+    pub(crate) frame: FrameTiming,
+    pub(crate) x_start: u16, // what col idx to translate 0 to, see `PanelConfig::offsets`
+    pub(crate) y_start: u16, // what row idx to translate 0 to, see `PanelConfig::offsets`
+    // CASET and RASET parameters the display holds, `None` when unknown
+    window: [Option<[u8; 4]>; 2],
+}
+
+impl DisplayState {
+    pub(crate) fn new(panel: PanelConfig) -> Self {
+        let madctl = MemoryAccessControl::default();
+        let (x_start, y_start) = panel.offsets(madctl);
+
+        Self {
+            panel,
+            madctl,
+            mode: DisplayMode::Normal,
+            pixel_format: PixelFormat::Rgb565,
+            power: PowerState::default(),
+            frame: FrameTiming::default(),
+            x_start,
+            y_start,
+            window: [None; 2],
+        }
+    }
+
+    // Updates the orientation and address offsets
+    pub(crate) fn apply_madctl(&mut self, madctl: MemoryAccessControl) {
+        self.madctl = madctl;
+        let (xs, ys) = self.panel.offsets(madctl);
+        self.x_start = xs;
+        self.y_start = ys;
+    }
+
+    // Returns to the register defaults after a hardware or software reset
+    pub(crate) fn reset(&mut self) {
+        self.apply_madctl(MemoryAccessControl::default());
+        self.mode = DisplayMode::Normal;
+        self.pixel_format = PixelFormat::Rgb666;
+        self.power = PowerState::default();
+        self.frame = FrameTiming::default();
+        self.forget_window();
+    }
+
+    // Updates the state after sending a command
+    pub(crate) fn track_command(&mut self, instruction: Instruction, params: &[u8]) {
+        match (instruction, params) {
+            (Instruction::SWRESET, _) => self.reset(),
+            (Instruction::COLMOD, &[colmod]) => {
+                if let Ok(format) = PixelFormat::try_from(colmod & 0x07) {
+                    self.pixel_format = format;
+                }
+            }
+            (Instruction::MADCTL, &[madctl]) => {
+                self.apply_madctl(MemoryAccessControl::from_bits(madctl))
+            }
+            (Instruction::PTLON, _) => self.mode = DisplayMode::Partial,
+            (Instruction::NORON, _) => self.mode = DisplayMode::Normal,
+            (Instruction::SLPIN, _) => self.power.sleeping = true,
+            (Instruction::SLPOUT, _) => self.power.sleeping = false,
+            (Instruction::DISPOFF, _) => self.power.display_on = false,
+            (Instruction::DISPON, _) => self.power.display_on = true,
+            (Instruction::IDMOFF, _) => self.power.idle_mode = false,
+            (Instruction::IDMON, _) => self.power.idle_mode = true,
+            (Instruction::FRCTRL2, &[rtna]) => self.frame.normal = FrameRate::from_bits(rtna),
+            (Instruction::FRCTRL1, &[control, idle, partial]) => {
+                self.frame.separate = (control & 0x10 != 0)
+                    .then(|| (FrameRate::from_bits(idle), FrameRate::from_bits(partial)));
+            }
+            (Instruction::PORCTRL, &[a, b, c, d, e]) => {
+                self.frame.porch = PorchConfig::from_bytes([a, b, c, d, e])
+            }
+            (Instruction::CASET, &[a, b, c, d]) => self.window[0] = Some([a, b, c, d]),
+            (Instruction::RASET, &[a, b, c, d]) => self.window[1] = Some([a, b, c, d]),
+            _ => {}
+        }
+    }
+
+    // CASET and RASET parameters of an address window, inclusive
+    pub(crate) fn address_window(&self, sx: u16, sy: u16, ex: u16, ey: u16) -> [[u8; 4]; 2] {
+        let [sx0, sx1] = (sx + self.x_start).to_be_bytes();
+        let [ex0, ex1] = (ex + self.x_start).to_be_bytes();
+        let [sy0, sy1] = (sy + self.y_start).to_be_bytes();
+        let [ey0, ey1] = (ey + self.y_start).to_be_bytes();
+        [[sx0, sx1, ex0, ex1], [sy0, sy1, ey0, ey1]]
+    }
+
+    // CASET and RASET with the parameters of an address window, left out where the display
+    // already holds them
+    pub(crate) fn window_commands(
+        &self,
+        sx: u16,
+        sy: u16,
+        ex: u16,
+        ey: u16,
+    ) -> [Option<(Instruction, [u8; 4])>; 2] {
+        let [caset, raset] = self.address_window(sx, sy, ex, ey);
+        let changed = |held: Option<[u8; 4]>, params| (held != Some(params)).then_some(params);
+        [
+            changed(self.window[0], caset).map(|params| (Instruction::CASET, params)),
+            changed(self.window[1], raset).map(|params| (Instruction::RASET, params)),
+        ]
+    }
+
+    // Forgets the address window, after a reset or when a write to the display failed
+    pub(crate) fn forget_window(&mut self) {
+        self.window = [None; 2];
+    }
+
+    // End coordinates of a blit of `len` bytes to the `dx` by `dy` pixels at `sx`, `sy`,
+    // `None` for an empty area
+    pub(crate) fn blit_area<PinE>(
+        &self,
+        sx: u16,
+        sy: u16,
+        dx: u16,
+        dy: u16,
+        len: usize,
+    ) -> Result<Option<(u16, u16)>, Error<PinE>> {
+        let pixels = usize::from(dx) * usize::from(dy);
+        if len != self.pixel_format.data_len(pixels) {
+            return Err(Error::InvalidArgument);
+        }
+        if pixels == 0 {
+            return Ok(None);
+        }
+        match (sx.checked_add(dx - 1), sy.checked_add(dy - 1)) {
+            (Some(ex), Some(ey)) => Ok(Some((ex, ey))),
+            _ => Err(Error::InvalidArgument),
+        }
+    }
+
+    // PTLAR parameters of a partial area, `None` if it is out of range
+    //
+    // The partial area always spans whole panel lines. In landscape orientations these run
+    // top to bottom, so the rows are columns there.
+    pub(crate) fn partial_area(&self, start_row: u16, end_row: u16) -> Option<[u8; 4]> {
+        if start_row > end_row || end_row >= self.panel.height {
+            return None;
+        }
+
+        let offset = if self.madctl.is_exchanged() {
+            self.x_start
+        } else {
+            self.y_start
+        };
+        let (mut start, mut end) = (start_row + offset, end_row + offset);
+        if self.madctl.is_row_order_reversed() {
+            (start, end) = (GRAM_HEIGHT - 1 - end, GRAM_HEIGHT - 1 - start);
+        }
+
+        let [s0, s1] = start.to_be_bytes();
+        let [e0, e1] = end.to_be_bytes();
+        Some([s0, s1, e0, e1])
+    }
+
+    // Refresh rate in Hz in the current mode
+    pub(crate) fn refresh_rate(&self) -> u16 {
+        self.frame.refresh_rate(self.power.idle_mode, self.mode)
+    }
+}
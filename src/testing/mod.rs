@@ -0,0 +1,653 @@
+//! Mock SPI bus, pins and delay that record everything the driver sends.
+//!
+//! All mocks created from one [`MockBus`] share a single event log, so the recorded bytes
+//! carry the DC and CS pin levels they were sent with. [`MockBus::commands`] decodes the log
+//! into a sequence of [`Command`]s which makes it possible to test the driver without a panel.
+//! Displays without a DC pin are tested with [`MockBus::spi_3wire`], which decodes the 9-bit
+//! frames of the 3-wire serial interface.
+//!
+//! ```
+//! use st7789::testing::{Command, MockBus, PinRole};
+//! use st7789::{Instruction, SpiInterface, ST7789};
+//!
+//! let bus = MockBus::new();
+//! let di = SpiInterface::new(bus.spi(), bus.pin(PinRole::Dc));
+//! let mut display = ST7789::new(di, Some(bus.pin(PinRole::Reset)), None, 240, 240);
+//! display.set_scroll_offset(10).unwrap();
+//! assert_eq!(bus.commands(), [Command::new(Instruction::VSCAD, &[0, 10])]);
+//! ```
+//!
+//! On top of that, [`Simulator`] models the controller's frame memory and renders what the
+//! panel would show.
+extern crate std;
+
+mod simulator;
+
+use core::convert::Infallible;
+use core::fmt;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, VecDeque};
+use std::rc::Rc;
+use std::vec::Vec;
+
+use embedded_hal::delay::DelayNs;
+use embedded_hal::digital::{self, OutputPin};
+use embedded_hal::pwm::{self, SetDutyCycle};
+use embedded_hal::spi::{self, Operation, SpiDevice};
+
+use crate::Instruction;
+
+pub use self::simulator::{Simulator, GRAM_HEIGHT, GRAM_WIDTH};
+
+///
+/// What a mock pin is wired to.
+///
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PinRole {
+    /// Data/command select, low for commands
+    Dc,
+    /// Chip select, active low
+    Cs,
+    /// Hardware reset, active low
+    Reset,
+    /// Backlight enable
+    Backlight,
+    /// Data line of a parallel bus, see [`MockBus::data_pins`]
+    Data(u8),
+    /// Write strobe of a parallel bus, latching the data lines on its rising edge
+    Wr,
+}
+
+///
+/// A single recorded bus event.
+///
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Event {
+    /// A byte written to SPI with the DC and CS levels at that time (`None` if never driven).
+    /// On a 3-wire bus this is a decoded 9-bit frame and `dc` is its D/C bit, on a parallel
+    /// bus the byte latched by a WR strobe, or one of the two bytes of a 16-bit word.
+    Byte {
+        /// The byte written
+        byte: u8,
+        /// DC level, high for data
+        dc: Option<bool>,
+        /// CS level, low when selected
+        cs: Option<bool>,
+    },
+    /// A pin level change
+    Pin {
+        /// The pin that changed
+        role: PinRole,
+        /// The new level
+        high: bool,
+    },
+    /// A delay in microseconds
+    Delay(u32),
+    /// A PWM duty cycle change, in units of the channel's maximum duty
+    Duty(u16),
+}
+
+///
+/// A command byte followed by its parameter bytes, decoded from the event log.
+///
+#[derive(Clone, PartialEq, Eq)]
+pub struct Command {
+    /// Raw command byte
+    pub opcode: u8,
+    /// Data bytes sent after the command
+    pub params: Vec<u8>,
+}
+
+impl Command {
+    ///
+    /// Creates a new command, mostly used as the expected value in assertions
+    ///
+    pub fn new(instruction: Instruction, params: &[u8]) -> Self {
+        Self {
+            opcode: instruction as u8,
+            params: params.to_vec(),
+        }
+    }
+
+    ///
+    /// Returns the instruction matching the opcode, if known
+    ///
+    pub fn instruction(&self) -> Option<Instruction> {
+        Instruction::try_from(self.opcode).ok()
+    }
+}
+
+impl fmt::Debug for Command {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.instruction() {
+            Some(instruction) => write!(f, "{:?}", instruction)?,
+            None => write!(f, "0x{:02X}", self.opcode)?,
+        }
+        write!(f, " {:02X?}", self.params)
+    }
+}
+
+#[derive(Default)]
+struct State {
+    events: Vec<Event>,
+    dc: Option<bool>,
+    cs: Option<bool>,
+    registers: BTreeMap<u8, Vec<u8>>,
+    // Bits of a register read not clocked out yet
+    read_out: VecDeque<bool>,
+    // Bytes of RAMRD already clocked out in whole pixels, where RAMRDC continues
+    memory_read: usize,
+    // Command of the read in progress and the data bits it clocked out
+    reading: Option<(u8, usize)>,
+    // Width and level of the parallel bus data lines
+    bus_width: usize,
+    data: u16,
+    wr: Option<bool>,
+    // Last command latched from the parallel bus
+    command: Option<u8>,
+}
+
+impl State {
+    // Records what the parallel bus latches on the rising edge of WR
+    fn latch(&mut self) {
+        let (dc, cs) = (self.dc, self.cs);
+        let [high, low] = self.data.to_be_bytes();
+        if dc == Some(false) {
+            self.command = Some(low);
+        }
+        let memory_write = matches!(
+            self.command.map(Instruction::try_from),
+            Some(Ok(Instruction::RAMWR | Instruction::RAMWRC))
+        );
+        let words = self.bus_width > 8 && dc == Some(true) && memory_write;
+        if words {
+            self.events.push(Event::Byte { byte: high, dc, cs });
+        }
+        self.events.push(Event::Byte { byte: low, dc, cs });
+    }
+}
+
+///
+/// Shared recorder all mocks write their events into.
+///
+#[derive(Clone, Default)]
+pub struct MockBus {
+    state: Rc<RefCell<State>>,
+}
+
+impl MockBus {
+    ///
+    /// Creates a new bus with an empty event log
+    ///
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    ///
+    /// Returns a mock SPI peripheral recording into this bus
+    ///
+    pub fn spi(&self) -> MockSpi {
+        MockSpi {
+            bus: self.clone(),
+            nine_bit: false,
+        }
+    }
+
+    ///
+    /// Returns a mock SPI peripheral of a display without a DC pin, decoding the 9-bit
+    /// frames of the 3-wire serial interface
+    ///
+    /// Every write is decoded on its own, trailing bits that don't make up a whole frame
+    /// are dropped like the controller does when CS is deasserted.
+    ///
+    pub fn spi_3wire(&self) -> MockSpi {
+        MockSpi {
+            bus: self.clone(),
+            nine_bit: true,
+        }
+    }
+
+    ///
+    /// Returns a mock output pin recording into this bus
+    ///
+    /// # Arguments
+    ///
+    /// * `role` - what the pin is connected to on the display side
+    ///
+    pub fn pin(&self, role: PinRole) -> MockPin {
+        MockPin {
+            bus: self.clone(),
+            role,
+        }
+    }
+
+    ///
+    /// Returns the data lines `D0` to `DN-1` of a parallel bus, `N` being 8 or 16
+    ///
+    /// Together with a [`PinRole::Wr`] pin every WR strobe records the latched byte. After
+    /// RAMWR or RAMWRC on the 16-bit bus it records both bytes of the latched word, high byte first.
+    /// Data lines and WR don't record pin events.
+    ///
+    pub fn data_pins<const N: usize>(&self) -> [MockPin; N] {
+        self.state.borrow_mut().bus_width = N;
+        core::array::from_fn(|line| self.pin(PinRole::Data(line as u8)))
+    }
+
+    ///
+    /// Returns a mock PWM channel recording duty cycle changes into this bus
+    ///
+    /// # Arguments
+    ///
+    /// * `max_duty` - duty cycle value for a fully on output
+    ///
+    pub fn pwm(&self, max_duty: u16) -> MockPwm {
+        MockPwm {
+            bus: self.clone(),
+            max_duty,
+            duty: 0,
+        }
+    }
+
+    ///
+    /// Returns the last duty cycle set on a mock PWM channel, if any
+    ///
+    pub fn duty(&self) -> Option<u16> {
+        self.state
+            .borrow()
+            .events
+            .iter()
+            .rev()
+            .find_map(|event| match event {
+                Event::Duty(duty) => Some(*duty),
+                _ => None,
+            })
+    }
+
+    ///
+    /// Returns a mock delay provider recording into this bus
+    ///
+    pub fn delay(&self) -> MockDelay {
+        MockDelay { bus: self.clone() }
+    }
+
+    ///
+    /// Returns a copy of all recorded events
+    ///
+    pub fn events(&self) -> Vec<Event> {
+        self.state.borrow().events.clone()
+    }
+
+    ///
+    /// Returns all bytes written to SPI, regardless of pin levels
+    ///
+    pub fn bytes(&self) -> Vec<u8> {
+        self.state
+            .borrow()
+            .events
+            .iter()
+            .filter_map(|event| match event {
+                Event::Byte { byte, .. } => Some(*byte),
+                _ => None,
+            })
+            .collect()
+    }
+
+    ///
+    /// Decodes the written bytes into commands and their parameters.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a byte was sent while DC was never driven, or if data was sent before any command.
+    ///
+    pub fn commands(&self) -> Vec<Command> {
+        let mut commands: Vec<Command> = Vec::new();
+
+        for event in self.state.borrow().events.iter() {
+            if let Event::Byte { byte, dc, .. } = *event {
+                match dc {
+                    Some(false) => commands.push(Command {
+                        opcode: byte,
+                        params: Vec::new(),
+                    }),
+                    Some(true) => commands
+                        .last_mut()
+                        .expect("data byte sent before any command")
+                        .params
+                        .push(byte),
+                    None => panic!("byte 0x{:02X} sent without a DC level", byte),
+                }
+            }
+        }
+
+        commands
+    }
+
+    ///
+    /// Returns the sum of all recorded delays in microseconds
+    ///
+    pub fn total_delay_us(&self) -> u64 {
+        self.state
+            .borrow()
+            .events
+            .iter()
+            .map(|event| match event {
+                Event::Delay(us) => u64::from(*us),
+                _ => 0,
+            })
+            .sum()
+    }
+
+    ///
+    /// Sets the value returned when the driver reads a register, unset registers read as zeros
+    ///
+    /// # Arguments
+    ///
+    /// * `instruction` - the read command, e.g. [`Instruction::RDDID`]
+    /// * `value` - the bytes clocked out after the command and any dummy cycle
+    ///
+    pub fn set_register(&self, instruction: Instruction, value: &[u8]) {
+        self.state
+            .borrow_mut()
+            .registers
+            .insert(instruction as u8, value.to_vec());
+    }
+
+    ///
+    /// Forgets all recorded events, keeping the current pin levels
+    ///
+    pub fn clear(&self) {
+        self.state.borrow_mut().events.clear();
+    }
+
+    fn record(&self, event: Event) {
+        self.state.borrow_mut().events.push(event);
+    }
+}
+
+///
+/// Mock SPI peripheral, see [`MockBus::spi`] and [`MockBus::spi_3wire`].
+///
+pub struct MockSpi {
+    bus: MockBus,
+    nine_bit: bool,
+}
+
+impl MockSpi {
+    fn record_write(&self, words: &[u8]) {
+        let mut state = self.bus.state.borrow_mut();
+        let (dc, cs) = (state.dc, state.cs);
+        state.read_out.clear();
+        if !self.nine_bit {
+            state
+                .events
+                .extend(words.iter().map(|&byte| Event::Byte { byte, dc, cs }));
+            return;
+        }
+
+        let bit = |i: usize| (words[i / 8] >> (7 - i % 8)) & 1;
+        for frame in 0..words.len() * 8 / 9 {
+            let start = frame * 9;
+            let byte = (1..9).fold(0, |byte, i| (byte << 1) | bit(start + i));
+            state.events.push(Event::Byte {
+                byte,
+                dc: Some(bit(start) == 1),
+                cs,
+            });
+        }
+    }
+
+    // Records the command and answers with the register value set by `MockBus::set_register`,
+    // preceded by a dummy bit for the multi-byte RDDID and RDDST reads and a dummy byte for
+    // RAMRD and RAMRDC. Until the value is clocked out completely, further transfers within
+    // the transaction continue the read.
+    fn answer_read(&self, words: &mut [u8]) {
+        let mut state = self.bus.state.borrow_mut();
+        let mut bits = 0..words.len() * 8;
+
+        if state.read_out.is_empty() {
+            let (command, dc, command_bits) = if self.nine_bit {
+                let frame = u16::from_be_bytes([words[0], words[1]]) >> 7;
+                (frame as u8, Some(frame & 0x100 != 0), 9)
+            } else {
+                (words[0], state.dc, 8)
+            };
+            let cs = state.cs;
+            state.events.push(Event::Byte {
+                byte: command,
+                dc,
+                cs,
+            });
+
+            let instruction = Instruction::try_from(command);
+            let dummy_bits = match instruction {
+                Ok(Instruction::RDDID) | Ok(Instruction::RDDST) => 1,
+                Ok(Instruction::RAMRD) | Ok(Instruction::RAMRDC) => 8,
+                _ => 0,
+            };
+            let value = match instruction {
+                Ok(Instruction::RAMRD) => {
+                    state.memory_read = 0;
+                    state.registers.get(&command).cloned().unwrap_or_default()
+                }
+                Ok(Instruction::RAMRDC) => {
+                    let ramrd = state.registers.get(&(Instruction::RAMRD as u8));
+                    let start = state.memory_read;
+                    ramrd.map_or(Vec::new(), |data| data[start.min(data.len())..].to_vec())
+                }
+                _ => state.registers.get(&command).cloned().unwrap_or_default(),
+            };
+            state.read_out = core::iter::repeat(false)
+                .take(dummy_bits)
+                .chain(
+                    value
+                        .iter()
+                        .flat_map(|byte| (0..8).rev().map(move |i| byte >> i & 1 != 0)),
+                )
+                .collect();
+            state.reading = Some((command, 0));
+            bits.start = command_bits + dummy_bits;
+            for _ in 0..dummy_bits {
+                state.read_out.pop_front();
+            }
+            let mask_bits = command_bits..bits.start;
+            for bit in mask_bits {
+                words[bit / 8] &= !(0x80 >> (bit % 8));
+            }
+        }
+
+        for bit in bits {
+            let mask = 0x80 >> (bit % 8);
+            if state.read_out.pop_front().unwrap_or(false) {
+                words[bit / 8] |= mask;
+            } else {
+                words[bit / 8] &= !mask;
+            }
+            if let Some((_, data_bits)) = state.reading.as_mut() {
+                *data_bits += 1;
+            }
+        }
+    }
+
+    // Deasserts CS, which ends a read in progress
+    fn deselect(&self) {
+        let mut state = self.bus.state.borrow_mut();
+        if let Some((command, data_bits)) = state.reading.take() {
+            let pixels_bytes = data_bits / 24 * 3;
+            match Instruction::try_from(command) {
+                Ok(Instruction::RAMRD) | Ok(Instruction::RAMRDC) => {
+                    state.memory_read += pixels_bytes
+                }
+                _ => {}
+            }
+        }
+        state.read_out.clear();
+        state.cs = Some(true);
+        state.events.push(Event::Pin {
+            role: PinRole::Cs,
+            high: true,
+        });
+    }
+}
+
+impl spi::ErrorType for MockSpi {
+    type Error = Infallible;
+}
+
+///
+/// Frames every transaction with CS events like a device driving its own chip select pin.
+///
+impl SpiDevice for MockSpi {
+    fn transaction(&mut self, operations: &mut [Operation<'_, u8>]) -> Result<(), Self::Error> {
+        {
+            let mut state = self.bus.state.borrow_mut();
+            state.cs = Some(false);
+            state.events.push(Event::Pin {
+                role: PinRole::Cs,
+                high: false,
+            });
+        }
+        for operation in operations {
+            match operation {
+                Operation::Write(words) => self.record_write(words),
+                Operation::Read(words) => {
+                    words.fill(0);
+                    self.answer_read(words)
+                }
+                Operation::Transfer(read, write) => {
+                    let len = read.len().min(write.len());
+                    read[..len].copy_from_slice(&write[..len]);
+                    read[len..].fill(0);
+                    self.answer_read(read)
+                }
+                Operation::TransferInPlace(words) => self.answer_read(words),
+                Operation::DelayNs(ns) => self.bus.record(Event::Delay(ns.div_ceil(1_000))),
+            }
+        }
+        self.deselect();
+        Ok(())
+    }
+}
+
+#[cfg(feature = "async")]
+impl embedded_hal_async::spi::SpiDevice for MockSpi {
+    async fn transaction(
+        &mut self,
+        operations: &mut [Operation<'_, u8>],
+    ) -> Result<(), Self::Error> {
+        SpiDevice::transaction(self, operations)
+    }
+}
+
+///
+/// Mock output pin, see [`MockBus::pin`].
+///
+pub struct MockPin {
+    bus: MockBus,
+    role: PinRole,
+}
+
+impl MockPin {
+    fn set(&mut self, high: bool) {
+        {
+            let mut state = self.bus.state.borrow_mut();
+            state.read_out.clear();
+            match self.role {
+                PinRole::Dc => state.dc = Some(high),
+                PinRole::Cs => state.cs = Some(high),
+                PinRole::Reset | PinRole::Backlight => {}
+                PinRole::Data(line) => {
+                    state.data = (state.data & !(1 << line)) | (u16::from(high) << line);
+                    return;
+                }
+                PinRole::Wr => {
+                    if high && state.wr == Some(false) {
+                        state.latch();
+                    }
+                    state.wr = Some(high);
+                    return;
+                }
+            }
+        }
+        self.bus.record(Event::Pin {
+            role: self.role,
+            high,
+        });
+    }
+}
+
+impl digital::ErrorType for MockPin {
+    type Error = Infallible;
+}
+
+impl OutputPin for MockPin {
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        self.set(false);
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        self.set(true);
+        Ok(())
+    }
+}
+
+///
+/// Mock PWM channel, see [`MockBus::pwm`].
+///
+pub struct MockPwm {
+    bus: MockBus,
+    max_duty: u16,
+    duty: u16,
+}
+
+impl pwm::ErrorType for MockPwm {
+    type Error = Infallible;
+}
+
+impl SetDutyCycle for MockPwm {
+    fn max_duty_cycle(&self) -> u16 {
+        self.max_duty
+    }
+
+    fn set_duty_cycle(&mut self, duty: u16) -> Result<(), Self::Error> {
+        self.duty = duty;
+        self.bus.record(Event::Duty(duty));
+        Ok(())
+    }
+}
+
+///
+/// Mock delay provider, see [`MockBus::delay`].
+///
+pub struct MockDelay {
+    bus: MockBus,
+}
+
+impl DelayNs for MockDelay {
+    fn delay_ns(&mut self, ns: u32) {
+        self.bus.record(Event::Delay(ns.div_ceil(1_000)));
+    }
+
+    fn delay_us(&mut self, us: u32) {
+        self.bus.record(Event::Delay(us));
+    }
+
+    fn delay_ms(&mut self, ms: u32) {
+        self.bus.record(Event::Delay(ms * 1_000));
+    }
+}
+
+#[cfg(feature = "async")]
+impl embedded_hal_async::delay::DelayNs for MockDelay {
+    async fn delay_ns(&mut self, ns: u32) {
+        DelayNs::delay_ns(self, ns)
+    }
+
+    async fn delay_us(&mut self, us: u32) {
+        DelayNs::delay_us(self, us)
+    }
+
+    async fn delay_ms(&mut self, ms: u32) {
+        DelayNs::delay_ms(self, ms)
+    }
+}
@@ -0,0 +1,341 @@
+//! Headless model of the ST7789 frame memory.
+//!
+//! [`Simulator`] interprets the commands recorded by a [`MockBus`] the way the controller
+//! does and renders the visible part of its 240x320 GRAM into an RGB buffer, so drawing,
+//! orientation and scrolling can be checked against golden images without a panel.
+extern crate std;
+
+use std::vec::Vec;
+
+use super::{Command, MockBus};
+use crate::{ImageFormat, Instruction};
+
+/// Number of columns in the controller's frame memory
+pub const GRAM_WIDTH: u16 = 240;
+/// Number of rows in the controller's frame memory
+pub const GRAM_HEIGHT: u16 = 320;
+
+// MADCTL bits
+const MY: u8 = 0b1000_0000;
+const MX: u8 = 0b0100_0000;
+const MV: u8 = 0b0010_0000;
+const BGR: u8 = 0b0000_1000;
+
+///
+/// Software model of the controller's frame memory and the registers that affect
+/// what ends up on the panel.
+///
+/// Pixels are stored with 6 bits per channel like the real GRAM. Power state is not
+/// modelled, the panel always shows the memory contents.
+///
+pub struct Simulator {
+    // Frame memory, 6 bits per channel
+    gram: Vec<[u8; 3]>,
+    // Visible panel size and its position in GRAM
+    width: u16,
+    height: u16,
+    col_offset: u16,
+    row_offset: u16,
+    // True for panels which show correct colors only with INVON (most IPS modules)
+    inverted_panel: bool,
+    // True for panels with red and blue subpixels swapped, which need MADCTL BGR order
+    bgr_panel: bool,
+    // Register shadows
+    madctl: u8,
+    colmod: u8,
+    inverted: bool,
+    columns: (u16, u16),
+    rows: (u16, u16),
+    scroll_area: (u16, u16, u16),
+    scroll_start: u16,
+    // Write pointer in logical (MADCTL-transformed) coordinates
+    cursor: (u16, u16),
+    // Bytes of an incomplete pixel carried over between writes
+    pending: Vec<u8>,
+}
+
+impl Simulator {
+    ///
+    /// Creates a new simulator with black GRAM and reset register values
+    ///
+    /// # Arguments
+    ///
+    /// * `width` - visible panel width in pixels
+    /// * `height` - visible panel height in pixels
+    /// * `col_offset` - first GRAM column wired to the panel
+    /// * `row_offset` - first GRAM row wired to the panel
+    ///
+    pub fn new(width: u16, height: u16, col_offset: u16, row_offset: u16) -> Self {
+        assert!(col_offset + width <= GRAM_WIDTH && row_offset + height <= GRAM_HEIGHT);
+
+        Self {
+            gram: std::vec![[0; 3]; GRAM_WIDTH as usize * GRAM_HEIGHT as usize],
+            width,
+            height,
+            col_offset,
+            row_offset,
+            inverted_panel: false,
+            bgr_panel: false,
+            madctl: 0,
+            colmod: 0x66,
+            inverted: false,
+            columns: (0, GRAM_WIDTH - 1),
+            rows: (0, GRAM_HEIGHT - 1),
+            scroll_area: (0, GRAM_HEIGHT, 0),
+            scroll_start: 0,
+            cursor: (0, 0),
+            pending: Vec::new(),
+        }
+    }
+
+    ///
+    /// Models a panel that shows correct colors only with display inversion on,
+    /// which is the case for most IPS modules
+    ///
+    pub fn with_inverted_panel(mut self) -> Self {
+        self.inverted_panel = true;
+        self
+    }
+
+    ///
+    /// Models a panel with red and blue subpixels swapped, which shows correct colors
+    /// only with BGR order set in MADCTL
+    ///
+    pub fn with_bgr_panel(mut self) -> Self {
+        self.bgr_panel = true;
+        self
+    }
+
+    ///
+    /// Returns the visible panel size
+    ///
+    pub fn size(&self) -> (u16, u16) {
+        (self.width, self.height)
+    }
+
+    ///
+    /// Executes and forgets all commands recorded by the bus so far
+    ///
+    pub fn consume(&mut self, bus: &MockBus) {
+        for command in bus.commands() {
+            self.execute(&command);
+        }
+        bus.clear();
+    }
+
+    ///
+    /// Executes a single command, unknown or unmodelled commands are ignored
+    ///
+    pub fn execute(&mut self, command: &Command) {
+        let params = &command.params[..];
+        let word = |i: usize| u16::from_be_bytes([params[i], params[i + 1]]);
+
+        match command.instruction() {
+            Some(Instruction::SWRESET) => {
+                // registers go back to their reset values, memory contents are kept
+                let gram = core::mem::take(&mut self.gram);
+                *self = Self {
+                    gram,
+                    inverted_panel: self.inverted_panel,
+                    bgr_panel: self.bgr_panel,
+                    ..Self::new(self.width, self.height, self.col_offset, self.row_offset)
+                };
+            }
+            Some(Instruction::CASET) if params.len() >= 4 => self.columns = (word(0), word(2)),
+            Some(Instruction::RASET) if params.len() >= 4 => self.rows = (word(0), word(2)),
+            Some(Instruction::MADCTL) if !params.is_empty() => self.madctl = params[0],
+            Some(Instruction::COLMOD) if !params.is_empty() => self.colmod = params[0],
+            Some(Instruction::INVON) => self.inverted = true,
+            Some(Instruction::INVOFF) => self.inverted = false,
+            Some(Instruction::VSCRDER) if params.len() >= 6 => {
+                self.scroll_area = (word(0), word(2), word(4))
+            }
+            Some(Instruction::VSCAD) if params.len() >= 2 => self.scroll_start = word(0),
+            Some(Instruction::RAMWR) => {
+                self.cursor = (self.columns.0, self.rows.0);
+                self.pending.clear();
+                self.write_pixels(params);
+            }
+            Some(Instruction::RAMWRC) => self.write_pixels(params),
+            _ => {}
+        }
+    }
+
+    ///
+    /// Returns the RGB888 color shown at the given panel coords
+    ///
+    pub fn pixel(&self, x: u16, y: u16) -> [u8; 3] {
+        self.visible(x, y).map(|c| (c << 2) | (c >> 4))
+    }
+
+    ///
+    /// Returns the color shown at the given panel coords as a raw RGB565 value
+    ///
+    pub fn pixel_rgb565(&self, x: u16, y: u16) -> u16 {
+        let [r, g, b] = self.visible(x, y);
+        (u16::from(r >> 1) << 11) | (u16::from(g) << 5) | u16::from(b >> 1)
+    }
+
+    ///
+    /// Returns the visible panel as RGB888 bytes, row by row
+    ///
+    pub fn to_rgb(&self) -> Vec<u8> {
+        let mut rgb = Vec::with_capacity(self.width as usize * self.height as usize * 3);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                rgb.extend_from_slice(&self.pixel(x, y));
+            }
+        }
+        rgb
+    }
+
+    ///
+    /// Returns the visible panel as a binary PPM (P6) image
+    ///
+    pub fn to_ppm(&self) -> Vec<u8> {
+        self.to_image(ImageFormat::Ppm)
+    }
+
+    ///
+    /// Returns the visible panel as an image file, encoded like the driver's screenshots
+    ///
+    pub fn to_image(&self, format: ImageFormat) -> Vec<u8> {
+        let mut image = Vec::new();
+        format
+            .encode(
+                self.width,
+                self.height,
+                |y, row| {
+                    for (x, pixel) in (0..self.width).zip(row.chunks_exact_mut(3)) {
+                        pixel.copy_from_slice(&self.pixel(x, y));
+                    }
+                    Ok::<(), core::convert::Infallible>(())
+                },
+                &mut |bytes| image.extend_from_slice(bytes),
+            )
+            .unwrap();
+        image
+    }
+
+    ///
+    /// Returns what a memory read (RAMRD) of the current address window clocks out after
+    /// the dummy cycle, 6 bits per channel in the upper bits of each byte
+    ///
+    pub fn read_memory(&self) -> Vec<u8> {
+        let mut data = Vec::new();
+        for y in self.rows.0..=self.rows.1 {
+            for x in self.columns.0..=self.columns.1 {
+                let color = self
+                    .gram_index(x, y)
+                    .map_or([0; 3], |index| self.gram[index]);
+                data.extend(color.map(|c| c << 2));
+            }
+        }
+        data
+    }
+
+    // Stores pixel data at the write pointer in the active pixel format
+    fn write_pixels(&mut self, data: &[u8]) {
+        let mut bytes = core::mem::take(&mut self.pending);
+        bytes.extend_from_slice(data);
+
+        let chunk = match self.colmod & 0x07 {
+            0x03 => 3, // two 12-bit pixels
+            0x05 => 2,
+            _ => 3,
+        };
+        let mut chunks = bytes.chunks_exact(chunk);
+        for c in &mut chunks {
+            match self.colmod & 0x07 {
+                0x03 => {
+                    let expand = |n: u8| (n << 2) | (n >> 2);
+                    self.store([expand(c[0] >> 4), expand(c[0] & 0x0F), expand(c[1] >> 4)]);
+                    self.store([expand(c[1] & 0x0F), expand(c[2] >> 4), expand(c[2] & 0x0F)]);
+                }
+                0x05 => {
+                    let raw = u16::from_be_bytes([c[0], c[1]]);
+                    let r = (raw >> 11) as u8;
+                    let g = ((raw >> 5) & 0x3F) as u8;
+                    let b = (raw & 0x1F) as u8;
+                    self.store([(r << 1) | (r >> 4), g, (b << 1) | (b >> 4)]);
+                }
+                _ => self.store([c[0] >> 2, c[1] >> 2, c[2] >> 2]),
+            }
+        }
+        self.pending = chunks.remainder().to_vec();
+
+        // a 12-bit pixel is stored as soon as it is complete, even without a second one
+        if self.colmod & 0x07 == 0x03 && self.pending.len() == 2 {
+            let expand = |n: u8| (n << 2) | (n >> 2);
+            let c = core::mem::take(&mut self.pending);
+            self.store([expand(c[0] >> 4), expand(c[0] & 0x0F), expand(c[1] >> 4)]);
+        }
+    }
+
+    // Stores one pixel and advances the write pointer within the address window
+    fn store(&mut self, color: [u8; 3]) {
+        let (x, y) = self.cursor;
+        if let Some(index) = self.gram_index(x, y) {
+            self.gram[index] = color;
+        }
+
+        self.cursor = if x >= self.columns.1 {
+            let next = if y >= self.rows.1 { self.rows.0 } else { y + 1 };
+            (self.columns.0, next)
+        } else {
+            (x + 1, y)
+        };
+    }
+
+    // Maps logical coords to a GRAM index according to MADCTL
+    fn gram_index(&self, x: u16, y: u16) -> Option<usize> {
+        let (col, row) = if self.madctl & MV != 0 {
+            (y, x)
+        } else {
+            (x, y)
+        };
+        if col >= GRAM_WIDTH || row >= GRAM_HEIGHT {
+            return None;
+        }
+        let col = if self.madctl & MX != 0 {
+            GRAM_WIDTH - 1 - col
+        } else {
+            col
+        };
+        let row = if self.madctl & MY != 0 {
+            GRAM_HEIGHT - 1 - row
+        } else {
+            row
+        };
+
+        Some(row as usize * GRAM_WIDTH as usize + col as usize)
+    }
+
+    // Maps a panel line to the GRAM row shown on it, taking scrolling into account
+    fn scrolled_row(&self, line: u16) -> u16 {
+        let (top, height, _) = self.scroll_area;
+        if line < top || line >= top + height || height == 0 {
+            return line;
+        }
+        let start = i32::from(self.scroll_start) - i32::from(top);
+        let offset = (start + i32::from(line - top)).rem_euclid(i32::from(height));
+        top + offset as u16
+    }
+
+    // Returns the 6-bit color shown at the given panel coords
+    fn visible(&self, x: u16, y: u16) -> [u8; 3] {
+        assert!(x < self.width && y < self.height, "pixel outside the panel");
+
+        let row = self.scrolled_row(self.row_offset + y);
+        let col = self.col_offset + x;
+        let [mut r, g, mut b] = self.gram[row as usize * GRAM_WIDTH as usize + col as usize];
+        if (self.madctl & BGR != 0) != self.bgr_panel {
+            core::mem::swap(&mut r, &mut b);
+        }
+        if self.inverted != self.inverted_panel {
+            [0x3F - r, 0x3F - g, 0x3F - b]
+        } else {
+            [r, g, b]
+        }
+    }
+}
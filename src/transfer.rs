@@ -0,0 +1,262 @@
+use core::iter::repeat;
+
+use embedded_hal::digital::OutputPin;
+
+use crate::color::{self, PixelBytes};
+use crate::{DataFormat, DisplayInterface, Error, Instruction, PixelFormat, ST7789};
+
+///
+/// Scratch buffer writes and background blits.
+///
+/// Pixels drawn from an iterator are encoded through a small buffer on the stack, so every
+/// few dozen pixels make up one bus write. With a buffer supplied by the caller they go
+/// out in writes as large as the buffer, and a solid fill is encoded only once and then
+/// written over and over.
+///
+impl<DI, OUT, PinE> ST7789<DI, OUT>
+where
+    DI: DisplayInterface,
+    OUT: OutputPin<Error = PinE>,
+{
+    ///
+    /// Sets pixel colors in given rectangle bounds, encoding them through a scratch buffer.
+    /// The RGB565 colors are converted to the active [`crate::PixelFormat`].
+    ///
+    /// # Arguments
+    ///
+    /// * `sx` - x coordinate start
+    /// * `sy` - y coordinate start
+    /// * `ex` - x coordinate end
+    /// * `ey` - y coordinate end
+    /// * `colors` - anything that can provide `IntoIterator<Item = u16>` to iterate over pixel data
+    /// * `buffer` - scratch buffer of any size holding at least two pixels, every bus write
+    ///   takes up to all of it
+    ///
+    pub fn set_pixels_buffered<T>(
+        &mut self,
+        sx: u16,
+        sy: u16,
+        ex: u16,
+        ey: u16,
+        colors: T,
+        buffer: &mut [u8],
+    ) -> Result<(), Error<PinE>>
+    where
+        T: IntoIterator<Item = u16>,
+    {
+        let buffer = self.scratch(buffer)?;
+        self.set_address_window(sx, sy, ex, ey)?;
+        self.write_command(Instruction::RAMWR)?;
+
+        let colors = colors.into_iter().map(color::rgb565_to_rgb888);
+        let mut bytes = PixelBytes::new(self.state.pixel_format, colors);
+        loop {
+            let len = buffer
+                .iter_mut()
+                .zip(&mut bytes)
+                .map(|(slot, byte)| *slot = byte)
+                .count();
+            if len > 0 {
+                self.di
+                    .write_data(DataFormat::U8(&buffer[..len]))
+                    .map_err(|_| Error::DisplayError)?;
+            }
+            if len < buffer.len() {
+                return Ok(());
+            }
+        }
+    }
+
+    ///
+    /// Fills the given rectangle bounds with one color, writing a scratch buffer full of it
+    /// as often as needed.
+    /// The RGB565 color is converted to the active [`crate::PixelFormat`].
+    ///
+    /// # Arguments
+    ///
+    /// * `sx` - x coordinate start
+    /// * `sy` - y coordinate start
+    /// * `ex` - x coordinate end
+    /// * `ey` - y coordinate end
+    /// * `color` - the Rgb565 color value
+    /// * `buffer` - scratch buffer of any size holding at least two pixels, every bus write
+    ///   takes up to all of it
+    ///
+    pub fn fill_solid_buffered(
+        &mut self,
+        sx: u16,
+        sy: u16,
+        ex: u16,
+        ey: u16,
+        color: u16,
+        buffer: &mut [u8],
+    ) -> Result<(), Error<PinE>> {
+        if ex < sx || ey < sy {
+            return Err(Error::InvalidArgument);
+        }
+        let buffer = self.scratch(buffer)?;
+        let pixels =
+            (usize::from(ex) - usize::from(sx) + 1) * (usize::from(ey) - usize::from(sy) + 1);
+        let mut remaining = self.state.pixel_format.data_len(pixels);
+
+        // whole pixel pairs, so every write starts at the same point of the pattern
+        let format = self.state.pixel_format;
+        let filled = buffer
+            .len()
+            .min(remaining.next_multiple_of(format.data_len(2)));
+        let pattern = PixelBytes::new(format, repeat(color::rgb565_to_rgb888(color)));
+        for (slot, byte) in buffer[..filled].iter_mut().zip(pattern) {
+            *slot = byte;
+        }
+
+        self.set_address_window(sx, sy, ex, ey)?;
+        self.write_command(Instruction::RAMWR)?;
+        while remaining > 0 {
+            let len = remaining.min(filled);
+            remaining -= len;
+            if remaining == 0 && format == PixelFormat::Rgb444 && pixels % 2 == 1 {
+                // a lone last pixel leaves the low nibble unused
+                buffer[len - 1] &= 0xF0;
+            }
+            self.di
+                .write_data(DataFormat::U8(&buffer[..len]))
+                .map_err(|_| Error::DisplayError)?;
+        }
+        Ok(())
+    }
+
+    ///
+    /// Starts blitting raw pixel data to the display, handing the buffer to the display
+    /// interface for the whole transfer.
+    ///
+    /// Display interfaces writing in the background, e.g. with DMA, return right away, see
+    /// [`DisplayInterface::start_write`]. The returned [`BlitTransfer`] gives the buffer
+    /// back once the transfer is done, right away for an empty area. On a wrong data length
+    /// or an area reaching past the end of the address space the buffer comes back with the
+    /// error.
+    ///
+    /// # Arguments
+    ///
+    /// * `sx` - x coordinate start
+    /// * `sy` - y coordinate start
+    /// * `dx` - width
+    /// * `dy` - height
+    /// * `data` - raw pixel data in the active [`crate::PixelFormat`]
+    ///
+    pub fn blit_pixels_nb(
+        &mut self,
+        sx: u16,
+        sy: u16,
+        dx: u16,
+        dy: u16,
+        data: &'static mut [u8],
+    ) -> Result<BlitTransfer<'_, DI, OUT>, (Error<PinE>, &'static mut [u8])> {
+        let (ex, ey) = match self.state.blit_area(sx, sy, dx, dy, data.len()) {
+            Ok(Some(end)) => end,
+            Ok(None) => {
+                // nothing to send, the transfer is done as soon as it is polled
+                return Ok(BlitTransfer {
+                    display: self,
+                    data: Some(data),
+                    running: false,
+                    failed: false,
+                });
+            }
+            Err(error) => return Err((error, data)),
+        };
+        let window = self
+            .set_address_window(sx, sy, ex, ey)
+            .and_then(|_| self.write_command(Instruction::RAMWR));
+        if let Err(error) = window {
+            return Err((error, data));
+        }
+        match self.di.start_write(data) {
+            Ok(data) => Ok(BlitTransfer {
+                display: self,
+                running: data.is_none(),
+                data,
+                failed: false,
+            }),
+            Err((_, data)) => Err((Error::DisplayError, data)),
+        }
+    }
+
+    // Cuts the scratch buffer down to whole pixel pairs in the active pixel format
+    fn scratch<'b>(&self, buffer: &'b mut [u8]) -> Result<&'b mut [u8], Error<PinE>> {
+        let pair = self.state.pixel_format.data_len(2);
+        let len = buffer.len() - buffer.len() % pair;
+        if len == 0 {
+            return Err(Error::InvalidArgument);
+        }
+        Ok(&mut buffer[..len])
+    }
+}
+
+///
+/// Blit started by [`ST7789::blit_pixels_nb`], holding on to the display and the buffer
+/// until the display interface is done with it.
+///
+/// Dropping an unfinished transfer waits for it to end.
+///
+pub struct BlitTransfer<'a, DI, OUT>
+where
+    DI: DisplayInterface,
+    OUT: OutputPin,
+{
+    display: &'a mut ST7789<DI, OUT>,
+    // The buffer once the interface handed it back
+    data: Option<&'static mut [u8]>,
+    // The interface still holds the buffer
+    running: bool,
+    // Writing the buffer failed
+    failed: bool,
+}
+
+impl<DI, OUT, PinE> BlitTransfer<'_, DI, OUT>
+where
+    DI: DisplayInterface,
+    OUT: OutputPin<Error = PinE>,
+{
+    ///
+    /// Returns [`nb::Error::WouldBlock`] while the transfer is still running
+    ///
+    pub fn poll(&mut self) -> nb::Result<(), Error<PinE>> {
+        if self.running {
+            match self.display.di.poll_write() {
+                Ok(data) => self.data = Some(data),
+                Err(nb::Error::WouldBlock) => return Err(nb::Error::WouldBlock),
+                Err(nb::Error::Other((_, data))) => {
+                    self.data = Some(data);
+                    self.failed = true;
+                }
+            }
+            self.running = false;
+        }
+        if self.failed {
+            return Err(nb::Error::Other(Error::DisplayError));
+        }
+        Ok(())
+    }
+
+    ///
+    /// Waits for the transfer to end and returns the buffer with its outcome
+    ///
+    pub fn wait(mut self) -> (&'static mut [u8], Result<(), Error<PinE>>) {
+        let result = nb::block!(self.poll());
+        let data = self.data.take().unwrap_or_default();
+        (data, result)
+    }
+}
+
+impl<DI, OUT> Drop for BlitTransfer<'_, DI, OUT>
+where
+    DI: DisplayInterface,
+    OUT: OutputPin,
+{
+    fn drop(&mut self) {
+        if self.running {
+            // the display may not be used while the interface still writes
+            while let Err(nb::Error::WouldBlock) = self.display.di.poll_write() {}
+        }
+    }
+}
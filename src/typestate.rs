@@ -0,0 +1,495 @@
+//! Driver lifecycle checked at compile time.
+//!
+//! [`ST7789`] here wraps the driver and carries its state in the type: [`ST7789::new`]
+//! returns an [`Uninitialized`] handle, `init` consumes it and returns a [`Ready`] driver,
+//! and `sleep` and `wake` move between [`Ready`] and [`Sleeping`]. Drawing and configuration
+//! are only available while [`Ready`]:
+//!
+//! ```
+//! use embedded_graphics::{pixelcolor::Rgb565, prelude::*};
+//! use st7789::testing::{MockBus, PinRole};
+//! use st7789::typestate::ST7789;
+//! use st7789::SpiInterface;
+//!
+//! let bus = MockBus::new();
+//! let di = SpiInterface::new(bus.spi(), bus.pin(PinRole::Dc));
+//! let display = ST7789::new(di, Some(bus.pin(PinRole::Reset)), None, 240, 240);
+//! // display.clear(Rgb565::BLACK) does not compile before init
+//! let mut display = display.init(&mut bus.delay()).unwrap();
+//! display.clear(Rgb565::BLACK).unwrap();
+//! let display = display.sleep(&mut bus.delay()).unwrap();
+//! let mut display = display.wake(&mut bus.delay()).unwrap();
+//! ```
+//!
+//! A [`Ready`] handle offers the drawing and configuration methods of the dynamic
+//! [`crate::ST7789`]. Resetting, initializing and the power methods are left out, they
+//! would take the display out of the state the type claims. [`ST7789::into_inner`] turns
+//! a handle in any state into the dynamic driver, which has all of them.
+//!
+//! A failed transition returns the error together with the handle in its previous state,
+//! so the display interface and pins aren't lost and the transition can be retried.
+//!
+//! ```compile_fail
+//! # use st7789::testing::{MockBus, PinRole};
+//! # use st7789::typestate::ST7789;
+//! # use st7789::SpiInterface;
+//! # let bus = MockBus::new();
+//! # let di = SpiInterface::new(bus.spi(), bus.pin(PinRole::Dc));
+//! let display = ST7789::new(di, Some(bus.pin(PinRole::Reset)), None, 240, 240);
+//! let mut display = display.init(&mut bus.delay()).unwrap();
+//! // a dark display isn't tracked by the type
+//! display.display_off().unwrap();
+//! ```
+
+use core::fmt::{self, Debug, Formatter};
+use core::marker::PhantomData;
+
+use embedded_hal::{delay::DelayNs, digital::OutputPin};
+
+use crate::{
+    BacklightState, BlitTransfer, BrightnessControl, CabcMode, DisplayInterface, DisplayMode,
+    Error, FrameRate, GammaCurve, InitSequence, MemoryAccessControl, Orientation, PanelConfig,
+    PixelFormat, PorchConfig, PowerState, ScrollRegion, TearingEffect,
+};
+
+mod sealed {
+    pub trait Sealed {
+        // Name of the state in debug output
+        const NAME: &'static str;
+    }
+}
+
+///
+/// Lifecycle state of a [`ST7789`] handle.
+///
+pub trait State: sealed::Sealed {}
+
+///
+/// The display has not been initialized yet.
+///
+/// ```compile_fail
+/// use embedded_graphics::{pixelcolor::Rgb565, prelude::*};
+/// use st7789::testing::{MockBus, PinRole};
+/// use st7789::typestate::ST7789;
+/// use st7789::SpiInterface;
+///
+/// let bus = MockBus::new();
+/// let di = SpiInterface::new(bus.spi(), bus.pin(PinRole::Dc));
+/// let mut display = ST7789::new(di, Some(bus.pin(PinRole::Reset)), None, 240, 240);
+/// display.clear(Rgb565::BLACK).unwrap();
+/// ```
+///
+pub struct Uninitialized;
+
+///
+/// The display is initialized and awake, ready to draw.
+///
+pub struct Ready;
+
+///
+/// The display is asleep, the frame memory keeps its contents.
+///
+pub struct Sleeping;
+
+impl sealed::Sealed for Uninitialized {
+    const NAME: &'static str = "Uninitialized";
+}
+impl sealed::Sealed for Ready {
+    const NAME: &'static str = "Ready";
+}
+impl sealed::Sealed for Sleeping {
+    const NAME: &'static str = "Sleeping";
+}
+impl State for Uninitialized {}
+impl State for Ready {}
+impl State for Sleeping {}
+
+///
+/// ST7789 driver with its lifecycle state `S` in the type, see the [module docs](self).
+///
+pub struct ST7789<DI, OUT, S = Ready>
+where
+    DI: DisplayInterface,
+    OUT: OutputPin,
+    S: State,
+{
+    display: crate::ST7789<DI, OUT>,
+    state: PhantomData<S>,
+}
+
+impl<DI, OUT, S> Debug for ST7789<DI, OUT, S>
+where
+    DI: DisplayInterface,
+    OUT: OutputPin,
+    S: State,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ST7789")
+            .field("state", &format_args!("{}", S::NAME))
+            .field("panel", &self.display.panel())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<DI, OUT, PinE, S> ST7789<DI, OUT, S>
+where
+    DI: DisplayInterface,
+    OUT: OutputPin<Error = PinE>,
+    S: State,
+{
+    fn transition<T: State>(self) -> ST7789<DI, OUT, T> {
+        ST7789 {
+            display: self.display,
+            state: PhantomData,
+        }
+    }
+
+    ///
+    /// Returns the dynamic driver, on which every method can be called in any state.
+    /// Only `sleep` and `wake` check the tracked power state there.
+    ///
+    pub fn into_inner(self) -> crate::ST7789<DI, OUT> {
+        self.display
+    }
+
+    ///
+    /// Releases the display interface and the RST and backlight pins
+    ///
+    pub fn release(self) -> (DI, Option<OUT>, Option<OUT>) {
+        self.display.release()
+    }
+}
+
+impl<DI, OUT, PinE> ST7789<DI, OUT, Uninitialized>
+where
+    DI: DisplayInterface,
+    OUT: OutputPin<Error = PinE>,
+{
+    ///
+    /// Creates a new uninitialized ST7789 driver instance
+    ///
+    /// # Arguments
+    ///
+    /// * `di` - a display interface for talking with the display
+    /// * `rst` - display hard reset pin
+    /// * `bl` - backlight pin
+    /// * `size_x` - x axis resolution of the display in pixels
+    /// * `size_y` - y axis resolution of the display in pixels
+    ///
+    pub fn new(di: DI, rst: Option<OUT>, bl: Option<OUT>, size_x: u16, size_y: u16) -> Self {
+        Self::with_panel(di, rst, bl, PanelConfig::new(size_x, size_y))
+    }
+
+    ///
+    /// Creates a new uninitialized ST7789 driver instance for a panel with known geometry
+    ///
+    /// # Arguments
+    ///
+    /// * `di` - a display interface for talking with the display
+    /// * `rst` - display hard reset pin
+    /// * `bl` - backlight pin
+    /// * `panel` - panel geometry, either a [`crate::DisplayModel`] or a custom [`PanelConfig`]
+    ///
+    pub fn with_panel(
+        di: DI,
+        rst: Option<OUT>,
+        bl: Option<OUT>,
+        panel: impl Into<PanelConfig>,
+    ) -> Self {
+        Self {
+            display: crate::ST7789::with_panel(di, rst, bl, panel),
+            state: PhantomData,
+        }
+    }
+
+    ///
+    /// Runs the default initialization sequence, see [`crate::ST7789::init`].
+    /// On failure the uninitialized handle comes back with the error.
+    ///
+    /// # Arguments
+    ///
+    /// * `delay_source` - mutable reference to a delay provider
+    ///
+    pub fn init(
+        self,
+        delay_source: &mut impl DelayNs,
+    ) -> Result<ST7789<DI, OUT, Ready>, (Error<PinE>, Self)> {
+        self.init_with(&InitSequence::DEFAULT, delay_source)
+    }
+
+    ///
+    /// Runs a custom initialization sequence, see [`crate::ST7789::init_with`].
+    /// Fails with [`Error::InvalidState`] if the sequence leaves the display asleep.
+    /// On failure the uninitialized handle comes back with the error.
+    ///
+    /// # Arguments
+    ///
+    /// * `sequence` - commands, parameters and delays to send after the hard reset
+    /// * `delay_source` - mutable reference to a delay provider
+    ///
+    pub fn init_with(
+        mut self,
+        sequence: &InitSequence<'_>,
+        delay_source: &mut impl DelayNs,
+    ) -> Result<ST7789<DI, OUT, Ready>, (Error<PinE>, Self)> {
+        if let Err(error) = self.display.init_with(sequence, delay_source) {
+            return Err((error, self));
+        }
+        if self.display.power_state().sleeping {
+            return Err((Error::InvalidState, self));
+        }
+        Ok(self.transition())
+    }
+}
+
+// Forwards methods to the dynamic driver, where they are documented
+macro_rules! forward {
+    ($($name:ident(&self $(, $arg:ident: $ty:ty)*) -> $ret:ty;)*) => {$(
+        #[doc = concat!("See [`crate::ST7789::", stringify!($name), "`]")]
+        pub fn $name(&self $(, $arg: $ty)*) -> $ret {
+            self.display.$name($($arg),*)
+        }
+    )*};
+    ($($name:ident(&mut self $(, $arg:ident: $ty:ty)*) -> $ret:ty;)*) => {$(
+        #[doc = concat!("See [`crate::ST7789::", stringify!($name), "`]")]
+        pub fn $name(&mut self $(, $arg: $ty)*) -> $ret {
+            self.display.$name($($arg),*)
+        }
+    )*};
+}
+
+impl<DI, OUT, PinE, S> ST7789<DI, OUT, S>
+where
+    DI: DisplayInterface,
+    OUT: OutputPin<Error = PinE>,
+    S: State,
+{
+    forward! {
+        panel(&self) -> PanelConfig;
+        orientation(&self) -> Orientation;
+        memory_access_control(&self) -> MemoryAccessControl;
+        display_mode(&self) -> DisplayMode;
+        pixel_format(&self) -> PixelFormat;
+        power_state(&self) -> PowerState;
+        frame_rate(&self) -> FrameRate;
+        porch(&self) -> PorchConfig;
+        refresh_rate(&self) -> u16;
+    }
+}
+
+impl<DI, OUT, PinE> ST7789<DI, OUT, Ready>
+where
+    DI: DisplayInterface,
+    OUT: OutputPin<Error = PinE>,
+{
+    forward! {
+        set_backlight(
+            &mut self,
+            state: BacklightState,
+            delay_source: &mut impl DelayNs
+        ) -> Result<(), Error<PinE>>;
+        set_orientation(
+            &mut self,
+            madctl: impl Into<MemoryAccessControl>
+        ) -> Result<(), Error<PinE>>;
+        set_pixel_format(&mut self, format: PixelFormat) -> Result<(), Error<PinE>>;
+        set_partial_area(&mut self, start_row: u16, end_row: u16) -> Result<(), Error<PinE>>;
+        enter_partial_mode(&mut self) -> Result<(), Error<PinE>>;
+        enter_normal_mode(&mut self) -> Result<(), Error<PinE>>;
+        set_tearing_effect(&mut self, tearing_effect: TearingEffect) -> Result<(), Error<PinE>>;
+        set_display_brightness(&mut self, level: u8) -> Result<(), Error<PinE>>;
+        set_brightness_control(&mut self, control: BrightnessControl) -> Result<(), Error<PinE>>;
+        set_cabc_mode(&mut self, mode: CabcMode) -> Result<(), Error<PinE>>;
+        set_cabc_min_brightness(&mut self, level: u8) -> Result<(), Error<PinE>>;
+        set_gamma(
+            &mut self,
+            positive: &GammaCurve,
+            negative: &GammaCurve
+        ) -> Result<(), Error<PinE>>;
+        set_frame_rate(&mut self, rate: FrameRate) -> Result<(), Error<PinE>>;
+        set_idle_partial_frame_rates(
+            &mut self,
+            idle: FrameRate,
+            partial: FrameRate
+        ) -> Result<(), Error<PinE>>;
+        set_porch(&mut self, config: PorchConfig) -> Result<(), Error<PinE>>;
+        set_scroll_offset(&mut self, offset: u16) -> Result<(), Error<PinE>>;
+        define_scroll_area(
+            &mut self,
+            top_fixed: u16,
+            scroll_height: u16,
+            bottom_fixed: u16
+        ) -> Result<ScrollRegion, Error<PinE>>;
+        scroll(&mut self, region: &ScrollRegion) -> Result<(), Error<PinE>>;
+        blit_pixels(
+            &mut self,
+            sx: u16,
+            sy: u16,
+            dx: u16,
+            dy: u16,
+            data: &[u8]
+        ) -> Result<(), Error<PinE>>;
+        set_pixel(&mut self, x: u16, y: u16, color: u16) -> Result<(), Error<PinE>>;
+        set_pixels(
+            &mut self,
+            sx: u16,
+            sy: u16,
+            ex: u16,
+            ey: u16,
+            colors: impl IntoIterator<Item = u16>
+        ) -> Result<(), Error<PinE>>;
+        continue_pixels(
+            &mut self,
+            colors: impl IntoIterator<Item = u16>
+        ) -> Result<(), Error<PinE>>;
+        set_pixels_buffered(
+            &mut self,
+            sx: u16,
+            sy: u16,
+            ex: u16,
+            ey: u16,
+            colors: impl IntoIterator<Item = u16>,
+            buffer: &mut [u8]
+        ) -> Result<(), Error<PinE>>;
+        fill_solid_buffered(
+            &mut self,
+            sx: u16,
+            sy: u16,
+            ex: u16,
+            ey: u16,
+            color: u16,
+            buffer: &mut [u8]
+        ) -> Result<(), Error<PinE>>;
+        blit_pixels_nb(
+            &mut self,
+            sx: u16,
+            sy: u16,
+            dx: u16,
+            dy: u16,
+            data: &'static mut [u8]
+        ) -> Result<BlitTransfer<'_, DI, OUT>, (Error<PinE>, &'static mut [u8])>;
+    }
+
+    ///
+    /// Puts the display to sleep, see [`crate::ST7789::sleep`].
+    /// On failure the handle comes back with the error.
+    ///
+    /// # Arguments
+    ///
+    /// * `delay_source` - mutable reference to a delay provider
+    ///
+    pub fn sleep(
+        mut self,
+        delay_source: &mut impl DelayNs,
+    ) -> Result<ST7789<DI, OUT, Sleeping>, (Error<PinE>, Self)> {
+        match self.display.sleep(delay_source) {
+            Ok(()) => Ok(self.transition()),
+            Err(error) => Err((error, self)),
+        }
+    }
+}
+
+impl<DI, OUT, PinE> ST7789<DI, OUT, Sleeping>
+where
+    DI: DisplayInterface,
+    OUT: OutputPin<Error = PinE>,
+{
+    ///
+    /// Wakes the display up, see [`crate::ST7789::wake`].
+    /// On failure the sleeping handle comes back with the error.
+    ///
+    /// # Arguments
+    ///
+    /// * `delay_source` - mutable reference to a delay provider
+    ///
+    pub fn wake(
+        mut self,
+        delay_source: &mut impl DelayNs,
+    ) -> Result<ST7789<DI, OUT, Ready>, (Error<PinE>, Self)> {
+        match self.display.wake(delay_source) {
+            Ok(()) => Ok(self.transition()),
+            Err(error) => Err((error, self)),
+        }
+    }
+}
+
+#[cfg(feature = "graphics")]
+mod graphics {
+    use embedded_graphics_core::{
+        pixelcolor::Rgb565,
+        prelude::{DrawTarget, OriginDimensions, RgbColor, Size},
+        primitives::Rectangle,
+        Pixel,
+    };
+    use embedded_hal::digital::OutputPin;
+
+    use super::{Ready, ST7789};
+    use crate::{ColorTarget, DisplayInterface, Error, ScratchTarget};
+
+    impl<DI, OUT, PinE> ST7789<DI, OUT, Ready>
+    where
+        DI: DisplayInterface,
+        OUT: OutputPin<Error = PinE>,
+    {
+        ///
+        /// See [`crate::ST7789::color_target`]
+        ///
+        pub fn color_target<C>(&mut self) -> ColorTarget<'_, DI, OUT, C>
+        where
+            C: RgbColor,
+        {
+            self.display.color_target()
+        }
+
+        ///
+        /// See [`crate::ST7789::scratch_target`]
+        ///
+        pub fn scratch_target<'a>(
+            &'a mut self,
+            buffer: &'a mut [u8],
+        ) -> ScratchTarget<'a, DI, OUT> {
+            self.display.scratch_target(buffer)
+        }
+    }
+
+    impl<DI, OUT, PinE> DrawTarget for ST7789<DI, OUT, Ready>
+    where
+        DI: DisplayInterface,
+        OUT: OutputPin<Error = PinE>,
+    {
+        type Error = Error<PinE>;
+        type Color = Rgb565;
+
+        fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+        where
+            I: IntoIterator<Item = Pixel<Self::Color>>,
+        {
+            self.display.draw_iter(pixels)
+        }
+
+        fn fill_contiguous<I>(&mut self, area: &Rectangle, colors: I) -> Result<(), Self::Error>
+        where
+            I: IntoIterator<Item = Self::Color>,
+        {
+            self.display.fill_contiguous(area, colors)
+        }
+
+        fn fill_solid(&mut self, area: &Rectangle, color: Self::Color) -> Result<(), Self::Error> {
+            self.display.fill_solid(area, color)
+        }
+
+        fn clear(&mut self, color: Self::Color) -> Result<(), Self::Error> {
+            self.display.clear(color)
+        }
+    }
+
+    impl<DI, OUT, PinE> OriginDimensions for ST7789<DI, OUT, Ready>
+    where
+        DI: DisplayInterface,
+        OUT: OutputPin<Error = PinE>,
+    {
+        fn size(&self) -> Size {
+            self.display.size()
+        }
+    }
+}
@@ -0,0 +1,172 @@
+use embassy_futures::block_on;
+use st7789::testing::{Event, MockBus, MockPin, MockSpi, PinRole};
+use st7789::{
+    asynch, DisplayInterface, Error, Orientation, PixelFormat, SpiInterface, TearingEffect,
+    ThreeWireInterface, ST7789,
+};
+
+fn async_display(bus: &MockBus) -> asynch::ST7789<SpiInterface<MockSpi, MockPin>, MockPin> {
+    let di = SpiInterface::new(bus.spi(), bus.pin(PinRole::Dc));
+    asynch::ST7789::new(di, Some(bus.pin(PinRole::Reset)), None, 135, 240)
+}
+
+/// Runs the same calls on both drivers and checks they send the same bus traffic
+fn assert_same_traffic<DI, AsyncDI>(
+    bus: &MockBus,
+    mut display: ST7789<DI, MockPin>,
+    mut async_display: asynch::ST7789<AsyncDI, MockPin>,
+) where
+    DI: DisplayInterface,
+    AsyncDI: asynch::DisplayInterface,
+{
+    let colors = || (0..60u16).map(|i| i * 1000);
+    let data = [0x12; 30];
+
+    display.init(&mut bus.delay()).unwrap();
+    display.set_orientation(Orientation::Landscape).unwrap();
+    display.set_pixels(10, 20, 19, 25, colors()).unwrap();
+    display.set_pixel_format(PixelFormat::Rgb444).unwrap();
+    display.set_pixels(10, 20, 19, 25, colors()).unwrap();
+    display.continue_pixels(colors()).unwrap();
+    display.set_pixel(10, 30, 0xFFFF).unwrap();
+    display.blit_pixels(0, 0, 5, 4, &data).unwrap();
+    let region = display.define_scroll_area(40, 240, 40).unwrap();
+    display.scroll(&region).unwrap();
+    display.set_partial_area(10, 100).unwrap();
+    display.enter_partial_mode().unwrap();
+    display.set_tearing_effect(TearingEffect::Vertical).unwrap();
+    display.sleep(&mut bus.delay()).unwrap();
+    let blocking = bus.events();
+    bus.clear();
+
+    block_on(async {
+        async_display.init(&mut bus.delay()).await.unwrap();
+        async_display
+            .set_orientation(Orientation::Landscape)
+            .await
+            .unwrap();
+        async_display
+            .set_pixels(10, 20, 19, 25, colors())
+            .await
+            .unwrap();
+        async_display
+            .set_pixel_format(PixelFormat::Rgb444)
+            .await
+            .unwrap();
+        async_display
+            .set_pixels(10, 20, 19, 25, colors())
+            .await
+            .unwrap();
+        async_display.continue_pixels(colors()).await.unwrap();
+        async_display.set_pixel(10, 30, 0xFFFF).await.unwrap();
+        async_display.blit_pixels(0, 0, 5, 4, &data).await.unwrap();
+        let region = async_display.define_scroll_area(40, 240, 40).await.unwrap();
+        async_display.scroll(&region).await.unwrap();
+        async_display.set_partial_area(10, 100).await.unwrap();
+        async_display.enter_partial_mode().await.unwrap();
+        async_display
+            .set_tearing_effect(TearingEffect::Vertical)
+            .await
+            .unwrap();
+        async_display.sleep(&mut bus.delay()).await.unwrap();
+    });
+
+    let bytes = |events: &[Event]| -> Vec<(u8, Option<bool>)> {
+        events
+            .iter()
+            .filter_map(|event| match *event {
+                Event::Byte { byte, dc, .. } => Some((byte, dc)),
+                _ => None,
+            })
+            .collect()
+    };
+    assert_eq!(bytes(&bus.events()), bytes(&blocking));
+    assert_eq!(async_display.orientation(), display.orientation());
+    assert_eq!(async_display.pixel_format(), display.pixel_format());
+    assert_eq!(async_display.display_mode(), display.display_mode());
+    assert_eq!(async_display.power_state(), display.power_state());
+}
+
+#[test]
+fn async_driver_matches_blocking() {
+    let bus = MockBus::new();
+    let spi = SpiInterface::new(bus.spi(), bus.pin(PinRole::Dc));
+    let display = ST7789::new(spi, Some(bus.pin(PinRole::Reset)), None, 135, 240);
+    assert_same_traffic(&bus, display, async_display(&bus));
+}
+
+#[test]
+fn async_three_wire_matches_blocking() {
+    let bus = MockBus::new();
+    let spi = ThreeWireInterface::new(bus.spi_3wire());
+    let display = ST7789::new(spi, Some(bus.pin(PinRole::Reset)), None, 135, 240);
+    let di = ThreeWireInterface::new(bus.spi_3wire());
+    let async_display = asynch::ST7789::new(di, Some(bus.pin(PinRole::Reset)), None, 135, 240);
+    assert_same_traffic(&bus, display, async_display);
+}
+
+#[test]
+fn async_blit_is_one_write() {
+    let bus = MockBus::new();
+    let mut display = async_display(&bus);
+    let data = [0x34; 2 * 100 * 50];
+
+    block_on(display.blit_pixels(0, 0, 100, 50, &data)).unwrap();
+    let transactions = bus
+        .events()
+        .into_iter()
+        .filter(|event| {
+            *event
+                == Event::Pin {
+                    role: PinRole::Cs,
+                    high: false,
+                }
+        })
+        .count();
+    // CASET, RASET and RAMWR with their parameters
+    assert_eq!(transactions, 6);
+}
+
+#[test]
+fn async_hard_reset_returns_to_register_defaults() {
+    let bus = MockBus::new();
+    let mut display = async_display(&bus);
+
+    block_on(async {
+        display.init(&mut bus.delay()).await.unwrap();
+        display.hard_reset(&mut bus.delay()).await.unwrap();
+        assert!(display.power_state().sleeping);
+        assert_eq!(display.pixel_format(), PixelFormat::Rgb666);
+        assert_eq!(display.orientation(), Orientation::Portrait);
+        display.wake(&mut bus.delay()).await.unwrap();
+    });
+}
+
+#[test]
+fn async_rejects_invalid_arguments_and_state() {
+    let bus = MockBus::new();
+    let mut display = async_display(&bus);
+
+    block_on(async {
+        // not woken up by an init sequence yet
+        assert!(matches!(
+            display.sleep(&mut bus.delay()).await,
+            Err(Error::InvalidState)
+        ));
+        assert!(matches!(
+            display.define_scroll_area(0, 0, 320).await,
+            Err(Error::InvalidArgument)
+        ));
+        assert!(matches!(
+            display.set_partial_area(0, 240).await,
+            Err(Error::InvalidArgument)
+        ));
+        assert!(matches!(
+            display.blit_pixels(u16::MAX, 0, 2, 1, &[0; 4]).await,
+            Err(Error::InvalidArgument)
+        ));
+        // nothing to draw
+        assert!(display.blit_pixels(5, 5, 0, 3, &[]).await.is_ok());
+    });
+    assert!(bus.events().is_empty());
+}
@@ -0,0 +1,87 @@
+use core::time::Duration;
+
+use st7789::testing::{Event, MockBus, PinRole};
+use st7789::{Backlight, GpioBacklight, PwmBacklight};
+
+#[test]
+fn pwm_brightness_scales_to_max_duty() {
+    let bus = MockBus::new();
+    let mut backlight = Backlight::new(PwmBacklight::new(bus.pwm(1000)));
+
+    backlight.set_brightness(255).unwrap();
+    assert_eq!(bus.duty(), Some(1000));
+    backlight.set_brightness(128).unwrap();
+    assert_eq!(bus.duty(), Some(501));
+    backlight.set_brightness(0).unwrap();
+    assert_eq!(bus.duty(), Some(0));
+}
+
+#[test]
+fn gamma_spreads_dark_levels() {
+    let bus = MockBus::new();
+    let mut backlight = Backlight::new(PwmBacklight::new(bus.pwm(1000))).with_gamma(true);
+
+    backlight.set_brightness(255).unwrap();
+    assert_eq!(bus.duty(), Some(1000));
+    // half the perceived brightness takes a quarter of the power
+    backlight.set_brightness(128).unwrap();
+    assert_eq!(bus.duty(), Some(251));
+    backlight.set_brightness(16).unwrap();
+    assert_eq!(bus.duty(), Some(3));
+}
+
+#[test]
+fn fade_steps_on_tick() {
+    let bus = MockBus::new();
+    let mut backlight = Backlight::new(PwmBacklight::new(bus.pwm(255)));
+    backlight.set_brightness(200).unwrap();
+    bus.clear();
+
+    backlight.fade_to(100, Duration::from_millis(100));
+    assert!(backlight.is_fading());
+    assert!(bus.events().is_empty());
+
+    assert!(backlight.tick(Duration::from_millis(25)).unwrap());
+    assert_eq!(backlight.brightness(), 175);
+    assert!(backlight.tick(Duration::from_millis(50)).unwrap());
+    assert_eq!(backlight.brightness(), 125);
+    assert!(!backlight.tick(Duration::from_millis(50)).unwrap());
+    assert_eq!(backlight.brightness(), 100);
+    assert!(!backlight.is_fading());
+    assert_eq!(
+        bus.events(),
+        [Event::Duty(175), Event::Duty(125), Event::Duty(100)]
+    );
+
+    // setting a level stops a fade
+    backlight.fade_to(255, Duration::from_secs(1));
+    backlight.set_brightness(10).unwrap();
+    assert!(!backlight.tick(Duration::from_millis(500)).unwrap());
+    assert_eq!(backlight.brightness(), 10);
+}
+
+#[test]
+fn fade_shorter_than_a_microsecond() {
+    let bus = MockBus::new();
+    let mut backlight = Backlight::new(PwmBacklight::new(bus.pwm(255)));
+
+    backlight.fade_to(200, Duration::from_nanos(800));
+    assert!(backlight.tick(Duration::from_nanos(200)).unwrap());
+    assert_eq!(backlight.brightness(), 50);
+    assert!(!backlight.tick(Duration::from_nanos(600)).unwrap());
+    assert_eq!(backlight.brightness(), 200);
+}
+
+#[test]
+fn gpio_backlight_switches_on_above_zero() {
+    let bus = MockBus::new();
+    let mut backlight = Backlight::new(GpioBacklight::new(bus.pin(PinRole::Backlight)));
+
+    backlight.set_brightness(1).unwrap();
+    backlight.set_brightness(0).unwrap();
+    let pin = |high| Event::Pin {
+        role: PinRole::Backlight,
+        high,
+    };
+    assert_eq!(bus.events(), [pin(true), pin(false)]);
+}
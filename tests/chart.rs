@@ -0,0 +1,124 @@
+use embedded_graphics::{
+    mono_font::ascii::FONT_6X10,
+    pixelcolor::{raw::RawU16, Rgb565},
+    prelude::*,
+};
+use st7789::testing::{MockBus, MockPin, MockSpi, PinRole, Simulator};
+use st7789::{DisplayModel, Instruction, SpiInterface, StripChart, ST7789};
+
+// Label area of FONT_6X10: 6 characters and a 2 pixel gap
+const PLOT_X: u16 = 6 * 6 + 2;
+
+fn setup(
+    bus: &MockBus,
+    model: DisplayModel,
+) -> (ST7789<SpiInterface<MockSpi, MockPin>, MockPin>, Simulator) {
+    let panel = model.config();
+    let mut display = ST7789::with_panel(
+        SpiInterface::new(bus.spi(), bus.pin(PinRole::Dc)),
+        None,
+        None,
+        model,
+    );
+    display.init(&mut bus.delay()).unwrap();
+    let sim = Simulator::new(
+        panel.width,
+        panel.height,
+        panel.col_offset,
+        panel.row_offset,
+    )
+    .with_inverted_panel();
+    (display, sim)
+}
+
+/// Color shown at a landscape screen position, the panel lines run from left to right
+fn screen(sim: &Simulator, x: u16, y: u16) -> Rgb565 {
+    let (width, _) = sim.size();
+    Rgb565::from(RawU16::new(sim.pixel_rgb565(width - 1 - y, x)))
+}
+
+#[test]
+fn strip_chart_costs_one_column_per_sample() {
+    let bus = MockBus::new();
+    let (mut display, _) = setup(&bus, DisplayModel::Pimoroni240x240);
+    let mut chart = StripChart::new(&mut display, &FONT_6X10, [Rgb565::RED], 0.0..=1.0).unwrap();
+    assert_eq!(chart.capacity(), 240 - PLOT_X);
+
+    for i in 0..chart.capacity() {
+        chart.push(&mut display, [f32::from(i % 2)]).unwrap();
+    }
+    bus.clear();
+    chart.push(&mut display, [0.5]).unwrap();
+
+    let commands = bus.commands();
+    let writes: Vec<_> = commands
+        .iter()
+        .filter(|c| c.instruction() == Some(Instruction::RAMWR))
+        .collect();
+    assert_eq!(writes.len(), 1);
+    assert_eq!(writes[0].params.len(), 240 * 2);
+    let scrolls: Vec<_> = commands
+        .iter()
+        .filter(|c| c.instruction() == Some(Instruction::VSCAD))
+        .collect();
+    assert_eq!(scrolls.len(), 1);
+    // the first sample scrolled off, the second is in the first plot column
+    assert_eq!(scrolls[0].params, (PLOT_X + 1).to_be_bytes());
+}
+
+#[test]
+fn strip_chart_scrolls_behind_fixed_labels() {
+    let bus = MockBus::new();
+    let (mut display, mut sim) = setup(&bus, DisplayModel::Ttgo135x240);
+    let mut chart = StripChart::new(
+        &mut display,
+        &FONT_6X10,
+        [Rgb565::RED, Rgb565::GREEN],
+        0.0..=10.0,
+    )
+    .unwrap();
+
+    for i in 0..chart.capacity() + 20 {
+        let green = if i < 10 { 0.0 } else { f32::NAN };
+        chart.push(&mut display, [0.0, green]).unwrap();
+    }
+    chart.push(&mut display, [10.0, f32::NAN]).unwrap();
+    sim.consume(&bus);
+
+    // the newest sample is joined to the one before it
+    let (last, bottom) = (239, 134);
+    assert!((0..=bottom).all(|y| screen(&sim, last, y) == Rgb565::RED));
+    assert_eq!(screen(&sim, last - 1, bottom), Rgb565::RED);
+    assert_eq!(screen(&sim, last - 1, 0), Rgb565::BLACK);
+    // the green samples scrolled off
+    assert!((PLOT_X..=last).all(|x| screen(&sim, x, bottom - 1) != Rgb565::GREEN));
+    // the axis line did not move
+    assert!((0..=bottom).all(|y| screen(&sim, PLOT_X - 1, y) == Rgb565::WHITE));
+    // the top label "10"
+    let label = |x, y| screen(&sim, x, y) == Rgb565::WHITE;
+    assert!((0..PLOT_X - 2).any(|x| (0..10).any(|y| label(x, y))));
+}
+
+#[test]
+fn strip_chart_autoscales_range() {
+    let bus = MockBus::new();
+    let (mut display, mut sim) = setup(&bus, DisplayModel::Pimoroni240x240);
+    let mut chart = StripChart::new(&mut display, &FONT_6X10, [Rgb565::RED], 0.0..=10.0).unwrap();
+
+    chart.push(&mut display, [10.0]).unwrap();
+    chart.push(&mut display, [20.0]).unwrap();
+    assert_eq!(chart.range(), 0.0..=22.0);
+    sim.consume(&bus);
+    // the first sample was redrawn for the new range
+    let y = 239 - (10.0f32 / 22.0 * 239.0).round() as u16;
+    assert_eq!(screen(&sim, PLOT_X, y), Rgb565::RED);
+    assert_eq!(screen(&sim, PLOT_X, 0), Rgb565::BLACK);
+
+    chart.set_autoscale(false);
+    chart.push(&mut display, [-5.0]).unwrap();
+    assert_eq!(chart.range(), 0.0..=22.0);
+    sim.consume(&bus);
+    assert_eq!(screen(&sim, PLOT_X + 2, 239), Rgb565::RED);
+
+    assert!(chart.set_range(&mut display, 5.0..=5.0).is_err());
+}
@@ -0,0 +1,970 @@
+use embedded_graphics::{
+    pixelcolor::Rgb565,
+    prelude::*,
+    primitives::{PrimitiveStyle, Rectangle},
+};
+use st7789::testing::{Command, Event, MockBus, MockPin, MockSpi, PinRole};
+use st7789::{
+    BrightnessControl, CabcMode, DisplayInterface, DisplayMode, DisplayModel, Error, FrameRate,
+    GammaCurve, InitSequence, InitStep, Instruction, MemoryAccessControl, Orientation, PanelConfig,
+    PixelFormat, Porch, PorchConfig, PowerState, SpiInterface, TearingEffect, ThreeWireInterface,
+    ST7789,
+};
+
+fn display(bus: &MockBus) -> ST7789<SpiInterface<MockSpi, MockPin>, MockPin> {
+    ST7789::new(
+        SpiInterface::new(bus.spi(), bus.pin(PinRole::Dc)),
+        Some(bus.pin(PinRole::Reset)),
+        Some(bus.pin(PinRole::Backlight)),
+        240,
+        240,
+    )
+}
+
+fn window(sx: u16, sy: u16, ex: u16, ey: u16) -> [Command; 2] {
+    let [sx0, sx1] = sx.to_be_bytes();
+    let [ex0, ex1] = ex.to_be_bytes();
+    let [sy0, sy1] = sy.to_be_bytes();
+    let [ey0, ey1] = ey.to_be_bytes();
+    [
+        Command::new(Instruction::CASET, &[sx0, sx1, ex0, ex1]),
+        Command::new(Instruction::RASET, &[sy0, sy1, ey0, ey1]),
+    ]
+}
+
+#[test]
+fn init_sends_default_sequence() {
+    let bus = MockBus::new();
+    let mut display = display(&bus);
+    display.init(&mut bus.delay()).unwrap();
+
+    assert_eq!(
+        bus.commands(),
+        [
+            Command::new(Instruction::MADCTL, &[0x70]),
+            Command::new(Instruction::COLMOD, &[0x55]),
+            Command::new(Instruction::PORCTRL, &[0x0c, 0x0c, 0x00, 0x33, 0x33]),
+            Command::new(Instruction::GCTRL, &[0x35]),
+            Command::new(Instruction::VCOMS, &[0x19]),
+            Command::new(Instruction::LCMCTRL, &[0x2c]),
+            Command::new(Instruction::VDVVRHEN, &[0x01]),
+            Command::new(Instruction::VRHS, &[0x12]),
+            Command::new(Instruction::VDVS, &[0x20]),
+            Command::new(Instruction::FRCTRL2, &[0x0f]),
+            Command::new(Instruction::PWCTRL1, &[0xa4, 0xa1]),
+            Command::new(
+                Instruction::PVGAMCTRL,
+                &[
+                    0xd0, 0x04, 0x0d, 0x11, 0x13, 0x2b, 0x3f, 0x54, 0x4c, 0x18, 0x0d, 0x0b, 0x1f,
+                    0x23
+                ]
+            ),
+            Command::new(
+                Instruction::NVGAMCTRL,
+                &[
+                    0xd0, 0x04, 0x0c, 0x11, 0x13, 0x2c, 0x3f, 0x44, 0x51, 0x2f, 0x1f, 0x1f, 0x20,
+                    0x23
+                ]
+            ),
+            Command::new(Instruction::INVON, &[]),
+            Command::new(Instruction::SLPOUT, &[]),
+            Command::new(Instruction::DISPON, &[]),
+        ]
+    );
+}
+
+#[test]
+fn init_with_runs_custom_sequence() {
+    let bus = MockBus::new();
+    let mut display = display(&bus);
+    let sequence = InitSequence::new(&[
+        InitStep::Command(Instruction::SLPOUT, &[]),
+        InitStep::Delay(120_000),
+        InitStep::Raw(0xE4, &[0x1D, 0x00, 0x10]),
+        InitStep::Command(Instruction::INVOFF, &[]),
+    ]);
+    display.init_with(&sequence, &mut bus.delay()).unwrap();
+
+    assert_eq!(
+        bus.commands(),
+        [
+            Command::new(Instruction::SLPOUT, &[]),
+            Command {
+                opcode: 0xE4,
+                params: vec![0x1D, 0x00, 0x10],
+            },
+            Command::new(Instruction::INVOFF, &[]),
+        ]
+    );
+    // reset pulses, backlight toggle and the sequence delay
+    assert_eq!(bus.total_delay_us(), 30 + 10_000 + 120_000);
+}
+
+#[test]
+fn builtin_sequences_end_with_display_on() {
+    for sequence in [
+        InitSequence::DEFAULT,
+        InitSequence::LEGACY,
+        InitSequence::MINIMAL,
+    ] {
+        let bus = MockBus::new();
+        let mut display = display(&bus);
+        display.init_with(&sequence, &mut bus.delay()).unwrap();
+
+        let commands = bus.commands();
+        assert!(commands.contains(&Command::new(Instruction::COLMOD, &[0x55])));
+        assert!(commands.contains(&Command::new(Instruction::SLPOUT, &[])));
+        assert_eq!(
+            commands.last().and_then(Command::instruction),
+            Some(Instruction::DISPON)
+        );
+    }
+}
+
+#[test]
+fn hard_reset_pulses_reset_pin() {
+    let bus = MockBus::new();
+    let mut display = display(&bus);
+    display.hard_reset(&mut bus.delay()).unwrap();
+
+    let reset = |high| Event::Pin {
+        role: PinRole::Reset,
+        high,
+    };
+    assert_eq!(
+        bus.events(),
+        [
+            reset(true),
+            Event::Delay(10),
+            reset(false),
+            Event::Delay(10),
+            reset(true),
+            Event::Delay(10),
+        ]
+    );
+}
+
+#[test]
+fn hard_reset_returns_to_register_defaults() {
+    let bus = MockBus::new();
+    let mut display = display(&bus);
+    display.init(&mut bus.delay()).unwrap();
+    display.hard_reset(&mut bus.delay()).unwrap();
+
+    assert_eq!(display.power_state(), PowerState::default());
+    assert_eq!(display.pixel_format(), PixelFormat::Rgb666);
+    assert_eq!(display.orientation(), Orientation::Portrait);
+    assert_eq!(display.display_mode(), DisplayMode::Normal);
+    // asleep again, so waking up is allowed
+    display.wake(&mut bus.delay()).unwrap();
+}
+
+#[test]
+fn set_orientation_writes_madctl() {
+    let cases = [
+        (Orientation::Portrait, 0x00),
+        (Orientation::Landscape, 0x60),
+        (Orientation::PortraitSwapped, 0xC0),
+        (Orientation::LandscapeSwapped, 0xA0),
+    ];
+
+    for (orientation, madctl) in cases {
+        let bus = MockBus::new();
+        let mut display = display(&bus);
+        display.set_orientation(orientation).unwrap();
+
+        assert_eq!(
+            bus.commands(),
+            [Command::new(Instruction::MADCTL, &[madctl])]
+        );
+    }
+}
+
+#[test]
+fn swapped_orientation_offsets_address_window() {
+    let bus = MockBus::new();
+    let mut display = display(&bus);
+    display
+        .set_orientation(Orientation::PortraitSwapped)
+        .unwrap();
+    bus.clear();
+
+    display.set_pixel(1, 2, 0xF800).unwrap();
+
+    let [caset, raset] = window(1, 82, 1, 82);
+    assert_eq!(
+        bus.commands(),
+        [
+            caset,
+            raset,
+            Command::new(Instruction::RAMWR, &[0xF8, 0x00])
+        ]
+    );
+}
+
+#[test]
+fn panel_offsets_apply_in_every_orientation() {
+    let cases = [
+        (Orientation::Portrait, (52, 40)),
+        (Orientation::Landscape, (40, 53)),
+        (Orientation::PortraitSwapped, (53, 40)),
+        (Orientation::LandscapeSwapped, (40, 52)),
+    ];
+
+    for (orientation, (xs, ys)) in cases {
+        let bus = MockBus::new();
+        let mut display = ST7789::with_panel(
+            SpiInterface::new(bus.spi(), bus.pin(PinRole::Dc)),
+            None::<MockPin>,
+            None,
+            DisplayModel::Ttgo135x240,
+        );
+        display.set_orientation(orientation).unwrap();
+        bus.clear();
+        display.set_pixel(0, 0, 0).unwrap();
+
+        let [caset, raset] = window(xs, ys, xs, ys);
+        assert_eq!(bus.commands()[..2], [caset, raset], "{:?}", orientation);
+    }
+}
+
+#[test]
+fn mirrored_orientation_offsets_address_window() {
+    let cases = [
+        // mirrored left to right, columns count from the far edge
+        (MemoryAccessControl::new().flip_horizontal(), (53, 40)),
+        // mirrored top to bottom, rows count from the far edge
+        (MemoryAccessControl::new().flip_vertical(), (52, 40)),
+        (
+            MemoryAccessControl::from(Orientation::Landscape).flip_horizontal(),
+            (40, 53),
+        ),
+    ];
+
+    for (madctl, (xs, ys)) in cases {
+        let bus = MockBus::new();
+        let mut display = ST7789::with_panel(
+            SpiInterface::new(bus.spi(), bus.pin(PinRole::Dc)),
+            None::<MockPin>,
+            None,
+            DisplayModel::Ttgo135x240,
+        );
+        display.set_orientation(madctl.bgr(true)).unwrap();
+        assert_eq!(
+            bus.commands(),
+            [Command::new(Instruction::MADCTL, &[madctl.bits() | 0x08])]
+        );
+        bus.clear();
+        display.set_pixel(0, 0, 0).unwrap();
+
+        let [caset, raset] = window(xs, ys, xs, ys);
+        assert_eq!(bus.commands()[..2], [caset, raset], "{:?}", madctl);
+    }
+}
+
+#[test]
+fn init_sequence_madctl_updates_orientation() {
+    let bus = MockBus::new();
+    let mut display = display(&bus);
+    display
+        .init_with(&InitSequence::MINIMAL, &mut bus.delay())
+        .unwrap();
+    assert_eq!(display.orientation(), Orientation::Portrait);
+
+    // the default sequence leaves the display rotated
+    display.init(&mut bus.delay()).unwrap();
+    assert_eq!(display.memory_access_control().bits(), 0x70);
+    assert_eq!(display.orientation(), Orientation::Landscape);
+}
+
+#[test]
+fn custom_panel_offsets() {
+    let panel = PanelConfig::new(240, 280).with_offset(0, 20);
+    assert_eq!(panel, DisplayModel::Waveshare240x280.into());
+    assert_eq!(panel.offsets(Orientation::Portrait), (0, 20));
+    assert_eq!(panel.offsets(Orientation::PortraitSwapped), (0, 20));
+    assert_eq!(panel.size(Orientation::Landscape), (280, 240));
+    // the old 240x240 behaviour is kept
+    let square = PanelConfig::new(240, 240);
+    assert_eq!(square.offsets(Orientation::PortraitSwapped), (0, 80));
+    assert_eq!(square.offsets(Orientation::LandscapeSwapped), (80, 0));
+}
+
+#[test]
+fn partial_area_follows_orientation_and_offset() {
+    let cases = [
+        (Orientation::Portrait, [0, 40, 0, 49]),
+        (Orientation::Landscape, [0, 40, 0, 49]),
+        // rows counted from the bottom of the frame memory
+        (Orientation::PortraitSwapped, [1, 14, 1, 23]),
+        (Orientation::LandscapeSwapped, [1, 14, 1, 23]),
+    ];
+
+    for (orientation, ptlar) in cases {
+        let bus = MockBus::new();
+        let mut display = ST7789::with_panel(
+            SpiInterface::new(bus.spi(), bus.pin(PinRole::Dc)),
+            None::<MockPin>,
+            None,
+            DisplayModel::Ttgo135x240,
+        );
+        display.set_orientation(orientation).unwrap();
+        bus.clear();
+        display.set_partial_area(0, 9).unwrap();
+
+        assert_eq!(
+            bus.commands(),
+            [Command::new(Instruction::PTLAR, &ptlar)],
+            "{:?}",
+            orientation
+        );
+    }
+}
+
+#[test]
+fn partial_area_rejects_lines_outside_panel() {
+    let bus = MockBus::new();
+    let mut display = ST7789::with_panel(
+        SpiInterface::new(bus.spi(), bus.pin(PinRole::Dc)),
+        None::<MockPin>,
+        None,
+        DisplayModel::Ttgo135x240,
+    );
+
+    assert!(matches!(
+        display.set_partial_area(0, 240),
+        Err(Error::InvalidArgument)
+    ));
+    assert!(matches!(
+        display.set_partial_area(10, 9),
+        Err(Error::InvalidArgument)
+    ));
+    assert!(bus.commands().is_empty());
+}
+
+#[test]
+fn display_mode_is_tracked() {
+    let bus = MockBus::new();
+    let mut display = display(&bus);
+    assert_eq!(display.display_mode(), DisplayMode::Normal);
+
+    display.enter_partial_mode().unwrap();
+    assert_eq!(display.display_mode(), DisplayMode::Partial);
+    display.enter_normal_mode().unwrap();
+    assert_eq!(display.display_mode(), DisplayMode::Normal);
+    assert_eq!(
+        bus.commands(),
+        [
+            Command::new(Instruction::PTLON, &[]),
+            Command::new(Instruction::NORON, &[])
+        ]
+    );
+
+    let sequence = InitSequence::new(&[
+        InitStep::Command(Instruction::PTLAR, &[0, 0, 0, 19]),
+        InitStep::Command(Instruction::PTLON, &[]),
+    ]);
+    display.init_with(&sequence, &mut bus.delay()).unwrap();
+    assert_eq!(display.display_mode(), DisplayMode::Partial);
+    display
+        .init_with(&InitSequence::MINIMAL, &mut bus.delay())
+        .unwrap();
+    assert_eq!(display.display_mode(), DisplayMode::Normal);
+}
+
+#[test]
+fn pixel_format_is_tracked() {
+    let bus = MockBus::new();
+    let mut display = display(&bus);
+    display.init(&mut bus.delay()).unwrap();
+    assert_eq!(display.pixel_format(), PixelFormat::Rgb565);
+
+    let sequence = InitSequence::new(&[
+        InitStep::Command(Instruction::SWRESET, &[]),
+        InitStep::Command(Instruction::SLPOUT, &[]),
+    ]);
+    display.init_with(&sequence, &mut bus.delay()).unwrap();
+    // reset leaves the controller in 18-bit mode
+    assert_eq!(display.pixel_format(), PixelFormat::Rgb666);
+
+    bus.clear();
+    display.set_pixel_format(PixelFormat::Rgb444).unwrap();
+    assert_eq!(display.pixel_format(), PixelFormat::Rgb444);
+    assert_eq!(bus.commands(), [Command::new(Instruction::COLMOD, &[0x33])]);
+}
+
+#[test]
+fn set_pixels_encodes_active_pixel_format() {
+    let bus = MockBus::new();
+    let mut display = display(&bus);
+    let colors = [0xF800, 0x07E0, 0x001F];
+
+    display.set_pixel_format(PixelFormat::Rgb444).unwrap();
+    bus.clear();
+    display.set_pixels(0, 0, 2, 0, colors).unwrap();
+    // two pixels in three bytes, the odd one out in two
+    assert_eq!(
+        bus.commands()[2],
+        Command::new(Instruction::RAMWR, &[0xF0, 0x00, 0xF0, 0x00, 0xF0])
+    );
+
+    display.set_pixel_format(PixelFormat::Rgb666).unwrap();
+    bus.clear();
+    display.set_pixels(0, 0, 2, 0, colors).unwrap();
+    // the address window is still set
+    assert_eq!(
+        bus.commands(),
+        [Command::new(
+            Instruction::RAMWR,
+            &[0xFC, 0x00, 0x00, 0x00, 0xFC, 0x00, 0x00, 0x00, 0xFC]
+        )]
+    );
+}
+
+#[test]
+fn address_window_is_only_sent_when_it_changes() {
+    let bus = MockBus::new();
+    let mut display = display(&bus);
+    display.set_pixel(1, 2, 0xF800).unwrap();
+    bus.clear();
+
+    // same row, only the columns move
+    display.set_pixel(2, 2, 0xF800).unwrap();
+    let [caset, _] = window(2, 2, 2, 2);
+    let ramwr = || Command::new(Instruction::RAMWR, &[0xF8, 0x00]);
+    assert_eq!(bus.commands(), [caset, ramwr()]);
+    bus.clear();
+
+    display.set_pixel(2, 2, 0xF800).unwrap();
+    assert_eq!(bus.commands(), [ramwr()]);
+    bus.clear();
+
+    // a reset puts the window back to its default
+    display.init(&mut bus.delay()).unwrap();
+    bus.clear();
+    display.set_pixel(2, 2, 0xF800).unwrap();
+    let [caset, raset] = window(2, 2, 2, 2);
+    assert_eq!(bus.commands(), [caset, raset, ramwr()]);
+}
+
+#[test]
+fn continue_pixels_writes_ramwrc() {
+    let bus = MockBus::new();
+    let mut display = display(&bus);
+
+    display.set_pixels(0, 0, 3, 0, [0xF800, 0x07E0]).unwrap();
+    display.continue_pixels([0x001F, 0xFFFF]).unwrap();
+    let [caset, raset] = window(0, 0, 3, 0);
+    assert_eq!(
+        bus.commands(),
+        [
+            caset,
+            raset,
+            Command::new(Instruction::RAMWR, &[0xF8, 0x00, 0x07, 0xE0]),
+            Command::new(Instruction::RAMWRC, &[0x00, 0x1F, 0xFF, 0xFF])
+        ]
+    );
+}
+
+#[test]
+fn blit_pixels_expects_active_pixel_format() {
+    let bus = MockBus::new();
+    let mut display = display(&bus);
+
+    assert!(display.blit_pixels(0, 0, 3, 1, &[0; 6]).is_ok());
+    display.set_pixel_format(PixelFormat::Rgb444).unwrap();
+    assert!(display.blit_pixels(0, 0, 3, 1, &[0; 6]).is_err());
+    assert!(display.blit_pixels(0, 0, 3, 1, &[0; 5]).is_ok());
+    display.set_pixel_format(PixelFormat::Rgb666).unwrap();
+    assert!(display.blit_pixels(0, 0, 3, 1, &[0; 9]).is_ok());
+}
+
+#[test]
+fn power_state_follows_init_sequence() {
+    let bus = MockBus::new();
+    let mut display = display(&bus);
+    assert_eq!(display.power_state(), PowerState::default());
+    assert!(display.power_state().sleeping);
+
+    display.init(&mut bus.delay()).unwrap();
+    assert_eq!(
+        display.power_state(),
+        PowerState {
+            sleeping: false,
+            display_on: true,
+            idle_mode: false,
+        }
+    );
+}
+
+#[test]
+fn sleep_and_wake_wait_for_the_controller() {
+    let bus = MockBus::new();
+    let mut display = display(&bus);
+    display.init(&mut bus.delay()).unwrap();
+    bus.clear();
+
+    display.display_off().unwrap();
+    display.sleep(&mut bus.delay()).unwrap();
+    assert!(display.power_state().sleeping);
+    assert!(!display.power_state().display_on);
+    assert_eq!(bus.total_delay_us(), 120_000);
+
+    display.wake(&mut bus.delay()).unwrap();
+    display.display_on().unwrap();
+    assert_eq!(bus.total_delay_us(), 240_000);
+    assert!(!display.power_state().sleeping);
+    assert_eq!(
+        bus.commands(),
+        [
+            Command::new(Instruction::DISPOFF, &[]),
+            Command::new(Instruction::SLPIN, &[]),
+            Command::new(Instruction::SLPOUT, &[]),
+            Command::new(Instruction::DISPON, &[]),
+        ]
+    );
+}
+
+#[test]
+fn invalid_power_transitions_are_rejected() {
+    let bus = MockBus::new();
+    let mut display = display(&bus);
+
+    // not woken up by an init sequence yet
+    assert!(matches!(
+        display.sleep(&mut bus.delay()),
+        Err(Error::InvalidState)
+    ));
+    display.wake(&mut bus.delay()).unwrap();
+    assert!(matches!(
+        display.wake(&mut bus.delay()),
+        Err(Error::InvalidState)
+    ));
+    assert_eq!(bus.commands(), [Command::new(Instruction::SLPOUT, &[])]);
+}
+
+#[test]
+fn idle_mode_switches_to_eight_colors() {
+    let bus = MockBus::new();
+    let mut display = display(&bus);
+
+    display.idle_mode(true).unwrap();
+    assert!(display.power_state().idle_mode);
+    display.idle_mode(false).unwrap();
+    assert!(!display.power_state().idle_mode);
+    assert_eq!(
+        bus.commands(),
+        [
+            Command::new(Instruction::IDMON, &[]),
+            Command::new(Instruction::IDMOFF, &[])
+        ]
+    );
+}
+
+#[test]
+fn typestate_driver_follows_lifecycle() {
+    let bus = MockBus::new();
+    let display = st7789::typestate::ST7789::new(
+        SpiInterface::new(bus.spi(), bus.pin(PinRole::Dc)),
+        None::<MockPin>,
+        None,
+        240,
+        240,
+    );
+    let mut display = display.init(&mut bus.delay()).unwrap();
+    display.set_pixel(0, 0, 0xFFFF).unwrap();
+    display.set_orientation(Orientation::Portrait).unwrap();
+    assert_eq!(display.orientation(), Orientation::Portrait);
+    bus.clear();
+
+    let display = display.sleep(&mut bus.delay()).unwrap();
+    let display = display.wake(&mut bus.delay()).unwrap();
+    assert_eq!(
+        bus.commands(),
+        [
+            Command::new(Instruction::SLPIN, &[]),
+            Command::new(Instruction::SLPOUT, &[])
+        ]
+    );
+    assert!(!display.into_inner().power_state().sleeping);
+}
+
+#[test]
+fn typestate_init_rejects_sequence_leaving_display_asleep() {
+    let bus = MockBus::new();
+    let display = st7789::typestate::ST7789::new(
+        SpiInterface::new(bus.spi(), bus.pin(PinRole::Dc)),
+        None::<MockPin>,
+        None,
+        240,
+        240,
+    );
+    let sequence = InitSequence::new(&[InitStep::Command(Instruction::SWRESET, &[])]);
+    let Err((error, display)) = display.init_with(&sequence, &mut bus.delay()) else {
+        panic!("sequence leaving the display asleep accepted");
+    };
+    assert!(matches!(error, Error::InvalidState));
+
+    // the handle comes back for another try
+    let display = display.init(&mut bus.delay()).unwrap();
+    assert!(!display.power_state().sleeping);
+}
+
+#[test]
+fn brightness_and_cabc_commands() {
+    let bus = MockBus::new();
+    let mut display = display(&bus);
+
+    display
+        .set_brightness_control(
+            BrightnessControl::new()
+                .brightness_control(true)
+                .backlight(true),
+        )
+        .unwrap();
+    display.set_display_brightness(0x80).unwrap();
+    display.set_cabc_mode(CabcMode::StillPicture).unwrap();
+    display.set_cabc_min_brightness(0x10).unwrap();
+    assert_eq!(
+        bus.commands(),
+        [
+            Command::new(Instruction::WRCTRLD, &[0x24]),
+            Command::new(Instruction::WRDISBV, &[0x80]),
+            Command::new(Instruction::WRCACE, &[0x02]),
+            Command::new(Instruction::WRCABCMB, &[0x10]),
+        ]
+    );
+}
+
+#[test]
+fn gamma_curve_round_trips_register_bytes() {
+    let bytes = [
+        0xd0, 0x04, 0x0d, 0x11, 0x13, 0x2b, 0x3f, 0x54, 0x4c, 0x18, 0x0d, 0x0b, 0x1f, 0x23,
+    ];
+    let curve = GammaCurve::from_bytes(bytes);
+    assert_eq!(curve, GammaCurve::DEFAULT_POSITIVE);
+    assert_eq!(
+        (curve.v63, curve.v0, curve.j0, curve.v13),
+        (0xd, 0x0, 2, 0xb)
+    );
+    assert_eq!((curve.v36, curve.v27, curve.j1, curve.v50), (5, 4, 1, 0x8));
+    assert_eq!(curve.to_bytes(), Some(bytes));
+
+    for curve in [
+        GammaCurve::DEFAULT_NEGATIVE,
+        GammaCurve::RESET_POSITIVE,
+        GammaCurve::RESET_NEGATIVE,
+        GammaCurve::GAMMA_2_2_POSITIVE,
+        GammaCurve::GAMMA_2_2_NEGATIVE,
+    ] {
+        assert!(curve.is_valid());
+    }
+}
+
+#[test]
+fn set_gamma_writes_both_curves() {
+    let bus = MockBus::new();
+    let mut display = display(&bus);
+    display
+        .set_gamma(&GammaCurve::RESET_POSITIVE, &GammaCurve::RESET_NEGATIVE)
+        .unwrap();
+
+    assert_eq!(
+        bus.commands(),
+        [
+            Command::new(
+                Instruction::PVGAMCTRL,
+                &[
+                    0xd0, 0x00, 0x02, 0x07, 0x0a, 0x28, 0x32, 0x44, 0x42, 0x06, 0x0e, 0x12, 0x14,
+                    0x17
+                ]
+            ),
+            Command::new(
+                Instruction::NVGAMCTRL,
+                &[
+                    0xd0, 0x00, 0x02, 0x07, 0x0a, 0x28, 0x31, 0x54, 0x47, 0x0e, 0x1c, 0x17, 0x1b,
+                    0x1e
+                ]
+            ),
+        ]
+    );
+}
+
+#[test]
+fn set_gamma_rejects_out_of_range_coefficients() {
+    let bus = MockBus::new();
+    let mut display = display(&bus);
+    let negative = GammaCurve {
+        j1: 4,
+        ..GammaCurve::DEFAULT_NEGATIVE
+    };
+
+    assert!(matches!(
+        display.set_gamma(&GammaCurve::DEFAULT_POSITIVE, &negative),
+        Err(Error::InvalidArgument)
+    ));
+    assert!(bus.commands().is_empty());
+}
+
+#[test]
+fn frame_rate_maps_to_hz() {
+    assert_eq!(FrameRate::Hz119 as u8, 0x00);
+    assert_eq!(FrameRate::Hz39 as u8, 0x1f);
+    assert_eq!(FrameRate::default().hz(), 60);
+    assert_eq!(FrameRate::from_bits(0xef), FrameRate::Hz60);
+    assert_eq!(FrameRate::from_hz(30), FrameRate::Hz39);
+    assert_eq!(FrameRate::from_hz(100), FrameRate::Hz99);
+    assert_eq!(FrameRate::from_hz(200), FrameRate::Hz119);
+}
+
+#[test]
+fn frame_rate_and_porch_commands() {
+    let bus = MockBus::new();
+    let mut display = display(&bus);
+    display.init(&mut bus.delay()).unwrap();
+    assert_eq!(display.frame_rate(), FrameRate::Hz60);
+    assert_eq!(display.porch(), PorchConfig::default());
+    bus.clear();
+
+    display.set_frame_rate(FrameRate::Hz40).unwrap();
+    display
+        .set_idle_partial_frame_rates(FrameRate::Hz39, FrameRate::Hz52)
+        .unwrap();
+    display
+        .set_porch(PorchConfig {
+            idle: Porch::new(8, 60),
+            separate: true,
+            ..PorchConfig::new(Porch::new(1, 127))
+        })
+        .unwrap();
+    assert_eq!(
+        bus.commands(),
+        [
+            Command::new(Instruction::FRCTRL2, &[0x1e]),
+            Command::new(Instruction::FRCTRL1, &[0x10, 0x1f, 0x14]),
+            Command::new(Instruction::PORCTRL, &[0x01, 0x7f, 0x01, 0x2f, 0x33]),
+        ]
+    );
+    assert_eq!(display.frame_rate(), FrameRate::Hz40);
+}
+
+#[test]
+fn set_porch_rejects_out_of_range_lines() {
+    let bus = MockBus::new();
+    let mut display = display(&bus);
+
+    for config in [
+        PorchConfig::new(Porch::new(0, 12)),
+        PorchConfig::new(Porch::new(12, 128)),
+        PorchConfig {
+            partial: Porch::new(6, 12),
+            ..PorchConfig::default()
+        },
+    ] {
+        assert!(matches!(
+            display.set_porch(config),
+            Err(Error::InvalidArgument)
+        ));
+    }
+    assert!(bus.commands().is_empty());
+}
+
+#[test]
+fn refresh_rate_follows_mode_and_porch() {
+    let bus = MockBus::new();
+    let mut display = display(&bus);
+    display.init(&mut bus.delay()).unwrap();
+    assert_eq!(display.refresh_rate(), 60);
+
+    display
+        .set_idle_partial_frame_rates(FrameRate::Hz39, FrameRate::Hz99)
+        .unwrap();
+    display.idle_mode(true).unwrap();
+    assert_eq!(display.refresh_rate(), 39);
+    display.idle_mode(false).unwrap();
+    display.set_partial_area(0, 100).unwrap();
+    display.enter_partial_mode().unwrap();
+    assert_eq!(display.refresh_rate(), 99);
+    display.enter_normal_mode().unwrap();
+
+    // doubling the porch lines lowers the rate
+    display
+        .set_porch(PorchConfig::new(Porch::new(24, 24)))
+        .unwrap();
+    assert_eq!(display.refresh_rate(), 56);
+
+    // a software reset restores the defaults
+    let reset = InitSequence::new(&[InitStep::Command(Instruction::SWRESET, &[])]);
+    display.init_with(&reset, &mut bus.delay()).unwrap();
+    display.set_frame_rate(FrameRate::Hz119).unwrap();
+    display.idle_mode(true).unwrap();
+    assert_eq!(display.refresh_rate(), 119);
+}
+
+#[test]
+fn set_scroll_offset_writes_vscad() {
+    let bus = MockBus::new();
+    let mut display = display(&bus);
+    display.set_scroll_offset(0x0123).unwrap();
+
+    assert_eq!(
+        bus.commands(),
+        [Command::new(Instruction::VSCAD, &[0x01, 0x23])]
+    );
+}
+
+#[test]
+fn define_scroll_area_writes_vscrder() {
+    let bus = MockBus::new();
+    let mut display = display(&bus);
+    let mut region = display.define_scroll_area(20, 280, 20).unwrap();
+    region.scroll_by(-1);
+    display.scroll(&region).unwrap();
+
+    assert_eq!(
+        bus.commands(),
+        [
+            Command::new(Instruction::VSCRDER, &[0, 20, 1, 24, 0, 20]),
+            // one line down wraps to the end of the scrolling area
+            Command::new(Instruction::VSCAD, &[1, 43]),
+        ]
+    );
+}
+
+#[test]
+fn define_scroll_area_rejects_wrong_sum() {
+    let bus = MockBus::new();
+    let mut display = display(&bus);
+
+    assert!(matches!(
+        display.define_scroll_area(20, 280, 0),
+        Err(Error::InvalidArgument)
+    ));
+    assert!(matches!(
+        display.define_scroll_area(160, 0, 160),
+        Err(Error::InvalidArgument)
+    ));
+    assert!(bus.commands().is_empty());
+}
+
+#[test]
+fn set_tearing_effect_writes_te_commands() {
+    let bus = MockBus::new();
+    let mut display = display(&bus);
+    display.set_tearing_effect(TearingEffect::Off).unwrap();
+    display.set_tearing_effect(TearingEffect::Vertical).unwrap();
+    display
+        .set_tearing_effect(TearingEffect::HorizontalAndVertical)
+        .unwrap();
+
+    assert_eq!(
+        bus.commands(),
+        [
+            Command::new(Instruction::TEOFF, &[]),
+            Command::new(Instruction::TEON, &[0]),
+            Command::new(Instruction::TEON, &[1]),
+        ]
+    );
+}
+
+#[test]
+fn fill_solid_writes_window_and_pixels() {
+    let bus = MockBus::new();
+    let mut display = display(&bus);
+    Rectangle::new(Point::new(10, 20), Size::new(3, 2))
+        .into_styled(PrimitiveStyle::with_fill(Rgb565::RED))
+        .draw(&mut display)
+        .unwrap();
+
+    let [caset, raset] = window(10, 20, 12, 21);
+    assert_eq!(
+        bus.commands(),
+        [
+            caset,
+            raset,
+            Command::new(Instruction::RAMWR, &[0xF8, 0x00].repeat(6))
+        ]
+    );
+}
+
+#[test]
+fn three_wire_packs_dc_bit_into_frames() {
+    let bus = MockBus::new();
+    let mut display: ST7789<ThreeWireInterface<MockSpi>, MockPin> =
+        ST7789::new(ThreeWireInterface::new(bus.spi()), None, None, 240, 240);
+    display.set_scroll_offset(10).unwrap();
+
+    // 0|0011_0111 1|0000_0000 1|0000_1010 in one transaction, padded to 4 bytes
+    assert_eq!(bus.bytes(), [0x1B, 0xC0, 0x21, 0x40]);
+    let selects = bus.events().into_iter().filter(|event| {
+        *event
+            == Event::Pin {
+                role: PinRole::Cs,
+                high: false,
+            }
+    });
+    assert_eq!(selects.count(), 1);
+}
+
+#[test]
+fn three_wire_sends_same_commands_as_four_wire() {
+    fn draw<DI: DisplayInterface>(
+        display: &mut ST7789<DI, MockPin>,
+        bus: &MockBus,
+    ) -> Vec<Command> {
+        display.init(&mut bus.delay()).unwrap();
+        // long enough to span several encoder buffers
+        Rectangle::new(Point::new(10, 20), Size::new(30, 7))
+            .into_styled(PrimitiveStyle::with_fill(Rgb565::CSS_ORANGE))
+            .draw(display)
+            .unwrap();
+        display
+            .blit_pixels(0, 0, 3, 1, &[1, 2, 3, 4, 5, 6])
+            .unwrap();
+        bus.commands()
+    }
+
+    let four_wire = MockBus::new();
+    let expected = draw(&mut display(&four_wire), &four_wire);
+    let three_wire = MockBus::new();
+    let mut display = ST7789::new(
+        ThreeWireInterface::new(three_wire.spi_3wire()),
+        None::<MockPin>,
+        None,
+        240,
+        240,
+    );
+
+    assert_eq!(draw(&mut display, &three_wire), expected);
+}
+
+#[test]
+fn blit_pixels_checks_arguments() {
+    let bus = MockBus::new();
+    let mut display = display(&bus);
+
+    assert!(matches!(
+        display.blit_pixels(0, 0, 2, 2, &[0; 7]),
+        Err(Error::InvalidArgument)
+    ));
+    assert!(matches!(
+        display.blit_pixels(u16::MAX, 0, 2, 1, &[0; 4]),
+        Err(Error::InvalidArgument)
+    ));
+    // nothing to draw
+    assert!(display.blit_pixels(0, 0, 0, 0, &[]).is_ok());
+    assert!(display.blit_pixels(5, 5, 3, 0, &[]).is_ok());
+    assert!(bus.commands().is_empty());
+}
+
+#[test]
+fn instruction_opcodes_map_back() {
+    for opcode in 0..=u8::MAX {
+        match Instruction::try_from(opcode) {
+            Ok(instruction) => assert_eq!(instruction as u8, opcode),
+            Err(unknown) => assert_eq!(unknown, opcode),
+        }
+    }
+    assert_eq!(Instruction::try_from(0x3C), Ok(Instruction::RAMWRC));
+    assert_eq!(Instruction::try_from(0xFF), Err(0xFF));
+}
@@ -0,0 +1,125 @@
+use core::fmt::Write;
+
+use embedded_graphics::{
+    mono_font::ascii::FONT_6X10,
+    pixelcolor::{raw::RawU16, Rgb565},
+    prelude::*,
+};
+use st7789::testing::{MockBus, MockPin, MockSpi, PinRole, Simulator};
+use st7789::{Console, DisplayModel, Instruction, SpiInterface, ST7789};
+
+fn setup(
+    bus: &MockBus,
+    model: DisplayModel,
+) -> (ST7789<SpiInterface<MockSpi, MockPin>, MockPin>, Simulator) {
+    let panel = model.config();
+    let mut display = ST7789::with_panel(
+        SpiInterface::new(bus.spi(), bus.pin(PinRole::Dc)),
+        None,
+        None,
+        model,
+    );
+    display.init(&mut bus.delay()).unwrap();
+    let sim = Simulator::new(
+        panel.width,
+        panel.height,
+        panel.col_offset,
+        panel.row_offset,
+    )
+    .with_inverted_panel();
+    (display, sim)
+}
+
+/// Color of the background in the middle of a character cell
+fn cell(sim: &Simulator, column: u16, row: u16) -> Rgb565 {
+    let raw = sim.pixel_rgb565(column * 6 + 3, row * 10 + 5);
+    Rgb565::from(RawU16::new(raw))
+}
+
+#[test]
+fn console_scrolls_instead_of_redrawing() {
+    let bus = MockBus::new();
+    let (mut display, mut sim) = setup(&bus, DisplayModel::Pimoroni240x240);
+    let mut console = Console::new(&mut display, &FONT_6X10).unwrap();
+    assert_eq!(console.size(), (40, 24));
+    sim.consume(&bus);
+
+    // every line starts with a cell in its own background colour
+    for i in 0..30 {
+        writeln!(
+            console.writer(&mut display),
+            "\x1b[4{}m \x1b[0m{}",
+            i % 8,
+            i
+        )
+        .unwrap();
+    }
+
+    // no write is larger than one text line
+    let largest = bus
+        .commands()
+        .iter()
+        .filter(|c| c.instruction() == Some(Instruction::RAMWR))
+        .map(|c| c.params.len())
+        .max()
+        .unwrap();
+    assert!(largest <= 240 * 10 * 2, "{}", largest);
+    sim.consume(&bus);
+
+    // 30 lines and the empty one after the last newline, the first 7 scrolled off
+    assert_eq!(console.cursor(), (0, 23));
+    let colors = [
+        Rgb565::new(0, 0, 0),
+        Rgb565::new(21, 0, 0),
+        Rgb565::new(0, 42, 0),
+        Rgb565::new(21, 21, 0),
+        Rgb565::new(0, 0, 21),
+        Rgb565::new(21, 0, 21),
+        Rgb565::new(0, 42, 21),
+        Rgb565::new(21, 42, 21),
+    ];
+    for row in 0..23u16 {
+        let i = usize::from(row) + 7;
+        assert_eq!(cell(&sim, 0, row), colors[i % 8], "row {}", row);
+    }
+    assert_eq!(cell(&sim, 0, 23), Rgb565::BLACK);
+}
+
+#[test]
+fn console_wraps_long_lines() {
+    let bus = MockBus::new();
+    let (mut display, mut sim) = setup(&bus, DisplayModel::Pimoroni240x240);
+    let mut console = Console::new(&mut display, &FONT_6X10).unwrap();
+
+    // 40 columns fit, the 41st cell wraps to the next row
+    let line = "\x1b[41m".to_string() + &" ".repeat(40) + "\x1b[44m \x1b[0m";
+    console.write_str(&mut display, &line).unwrap();
+    sim.consume(&bus);
+
+    assert_eq!(console.cursor(), (1, 1));
+    assert_eq!(cell(&sim, 39, 0), Rgb565::new(21, 0, 0));
+    assert_eq!(cell(&sim, 0, 1), Rgb565::new(0, 0, 21));
+    assert_eq!(cell(&sim, 1, 1), Rgb565::BLACK);
+}
+
+#[test]
+fn console_handles_bright_colors_and_offset_panels() {
+    let bus = MockBus::new();
+    let (mut display, mut sim) = setup(&bus, DisplayModel::Ttgo135x240);
+    let mut console = Console::new(&mut display, &FONT_6X10).unwrap();
+    assert_eq!(console.size(), (22, 24));
+
+    for _ in 0..30 {
+        console.write_str(&mut display, "\n").unwrap();
+    }
+    console
+        .write_str(&mut display, "\x1b[102m \x1b[49;1m\x1b[31mx")
+        .unwrap();
+    sim.consume(&bus);
+
+    assert_eq!(cell(&sim, 0, 23), Rgb565::new(10, 63, 10));
+    assert_eq!(cell(&sim, 1, 23), Rgb565::BLACK);
+    // bold turns the red text bright
+    let bright_red = Rgb565::new(31, 21, 10).into_storage();
+    assert!((6..12).any(|x| (230..240).any(|y| sim.pixel_rgb565(x, y) == bright_red)));
+}
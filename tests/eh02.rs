@@ -0,0 +1,186 @@
+use core::convert::Infallible;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use embedded_hal_02::blocking::{delay::DelayUs, spi};
+use embedded_hal_02::digital::v2::OutputPin;
+use embedded_hal_02::PwmPin;
+use st7789::{eh02, Backlight, PwmBacklight, SpiInterface, ST7789};
+
+#[derive(Clone, Debug, PartialEq)]
+enum Op {
+    Cs(bool),
+    Dc(bool),
+    Write(Vec<u8>),
+    Delay(u32),
+}
+
+type Log = Rc<RefCell<Vec<Op>>>;
+
+struct HalSpi(Log);
+
+impl spi::Write<u8> for HalSpi {
+    type Error = Infallible;
+
+    fn write(&mut self, words: &[u8]) -> Result<(), Infallible> {
+        self.0.borrow_mut().push(Op::Write(words.to_vec()));
+        Ok(())
+    }
+}
+
+impl spi::Transfer<u8> for HalSpi {
+    type Error = Infallible;
+
+    fn transfer<'w>(&mut self, words: &'w mut [u8]) -> Result<&'w [u8], Infallible> {
+        self.0.borrow_mut().push(Op::Write(words.to_vec()));
+        Ok(words)
+    }
+}
+
+struct HalPin(Log, fn(bool) -> Op);
+
+impl OutputPin for HalPin {
+    type Error = Infallible;
+
+    fn set_low(&mut self) -> Result<(), Infallible> {
+        self.0.borrow_mut().push((self.1)(false));
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Infallible> {
+        self.0.borrow_mut().push((self.1)(true));
+        Ok(())
+    }
+}
+
+struct HalDelay(Log);
+
+impl DelayUs<u32> for HalDelay {
+    fn delay_us(&mut self, us: u32) {
+        self.0.borrow_mut().push(Op::Delay(us));
+    }
+}
+
+struct HalPwm {
+    enabled: bool,
+    duty: u16,
+}
+
+impl PwmPin for HalPwm {
+    type Duty = u16;
+
+    fn disable(&mut self) {
+        self.enabled = false;
+    }
+
+    fn enable(&mut self) {
+        self.enabled = true;
+    }
+
+    fn get_duty(&self) -> u16 {
+        self.duty
+    }
+
+    fn get_max_duty(&self) -> u16 {
+        1000
+    }
+
+    fn set_duty(&mut self, duty: u16) {
+        self.duty = duty;
+    }
+}
+
+#[test]
+fn spi_adapter_frames_every_write_with_cs() {
+    let log = Log::default();
+    let mut display = ST7789::new(
+        SpiInterface::new(
+            eh02::Spi::with_cs(HalSpi(log.clone()), HalPin(log.clone(), Op::Cs)),
+            eh02::Pin(HalPin(log.clone(), Op::Dc)),
+        ),
+        None::<eh02::Pin<HalPin>>,
+        None,
+        240,
+        240,
+    );
+
+    display.set_scroll_offset(10).unwrap();
+    assert_eq!(
+        log.borrow()[..],
+        [
+            Op::Dc(false),
+            Op::Cs(false),
+            Op::Write(vec![0x37]),
+            Op::Cs(true),
+            Op::Dc(true),
+            Op::Cs(false),
+            Op::Write(vec![0, 10]),
+            Op::Cs(true),
+        ]
+    );
+}
+
+#[test]
+fn spi_adapter_delays_within_transaction() {
+    use embedded_hal::spi::{Operation, SpiDevice};
+
+    let log = Log::default();
+    let mut spi = eh02::Spi::with_cs(HalSpi(log.clone()), HalPin(log.clone(), Op::Cs))
+        .with_delay(eh02::Delay(HalDelay(log.clone())));
+    spi.transaction(&mut [
+        Operation::Write(&[1]),
+        Operation::DelayNs(3_000),
+        Operation::Write(&[2]),
+    ])
+    .unwrap();
+    assert_eq!(
+        log.borrow()[..],
+        [
+            Op::Cs(false),
+            Op::Write(vec![1]),
+            Op::Delay(3),
+            Op::Write(vec![2]),
+            Op::Cs(true),
+        ]
+    );
+}
+
+#[test]
+fn spi_adapter_without_delay_rejects_delay() {
+    use embedded_hal::spi::{Operation, SpiDevice};
+
+    let log = Log::default();
+    let mut spi = eh02::Spi::with_cs(HalSpi(log.clone()), HalPin(log.clone(), Op::Cs));
+    assert_eq!(
+        spi.transaction(&mut [Operation::Write(&[1]), Operation::DelayNs(3_000)]),
+        Err(eh02::AdapterError::Unsupported)
+    );
+    assert_eq!(
+        log.borrow()[..],
+        [Op::Cs(false), Op::Write(vec![1]), Op::Cs(true)]
+    );
+}
+
+#[test]
+fn delay_adapter_rounds_up_to_microseconds() {
+    use embedded_hal::delay::DelayNs;
+
+    let log = Log::default();
+    let mut delay = eh02::Delay(HalDelay(log.clone()));
+    delay.delay_ns(1_500);
+    delay.delay_ms(2);
+    assert_eq!(log.borrow()[..], [Op::Delay(2), Op::Delay(2_000)]);
+}
+
+#[test]
+fn pwm_adapter_enables_and_scales() {
+    let mut backlight = Backlight::new(PwmBacklight::new(eh02::Pwm::new(HalPwm {
+        enabled: false,
+        duty: 0,
+    })));
+    backlight.set_brightness(128).unwrap();
+
+    let pwm = backlight.release().release().release();
+    assert!(pwm.enabled);
+    assert_eq!(pwm.duty, 501);
+}
@@ -0,0 +1,84 @@
+use embedded_graphics::{pixelcolor::Rgb565, prelude::*};
+use st7789::testing::{Command, MockBus, MockPin, MockSpi, PinRole};
+use st7789::{Framebuffer, Instruction, SpiInterface, ST7789};
+
+fn display(bus: &MockBus) -> ST7789<SpiInterface<MockSpi, MockPin>, MockPin> {
+    ST7789::new(
+        SpiInterface::new(bus.spi(), bus.pin(PinRole::Dc)),
+        None,
+        None,
+        240,
+        240,
+    )
+}
+
+fn window(sx: u16, sy: u16, ex: u16, ey: u16) -> [Command; 2] {
+    let [sx0, sx1] = sx.to_be_bytes();
+    let [ex0, ex1] = ex.to_be_bytes();
+    let [sy0, sy1] = sy.to_be_bytes();
+    let [ey0, ey1] = ey.to_be_bytes();
+    [
+        Command::new(Instruction::CASET, &[sx0, sx1, ex0, ex1]),
+        Command::new(Instruction::RASET, &[sy0, sy1, ey0, ey1]),
+    ]
+}
+
+#[test]
+fn framebuffer_flushes_only_dirty_regions() {
+    let bus = MockBus::new();
+    let mut display = display(&bus);
+    let mut fb = Framebuffer::<16, 16>::new();
+    assert!(!fb.is_dirty());
+
+    Pixel(Point::new(3, 4), Rgb565::BLUE).draw(&mut fb).unwrap();
+    Pixel(Point::new(12, 12), Rgb565::GREEN)
+        .draw(&mut fb)
+        .unwrap();
+    fb.flush(&mut display).unwrap();
+
+    let [caset1, raset1] = window(3, 4, 3, 4);
+    let [caset2, raset2] = window(12, 12, 12, 12);
+    assert_eq!(
+        bus.commands(),
+        [
+            caset1,
+            raset1,
+            Command::new(Instruction::RAMWR, &[0x00, 0x1F]),
+            caset2,
+            raset2,
+            Command::new(Instruction::RAMWR, &[0x07, 0xE0]),
+        ]
+    );
+
+    // nothing left to send
+    bus.clear();
+    fb.flush(&mut display).unwrap();
+    assert!(!fb.is_dirty());
+    assert!(bus.commands().is_empty());
+}
+
+#[test]
+fn framebuffer_merges_touching_regions() {
+    let mut fb = Framebuffer::<16, 16>::new();
+    Pixel(Point::new(3, 4), Rgb565::BLUE).draw(&mut fb).unwrap();
+    Pixel(Point::new(4, 4), Rgb565::BLUE).draw(&mut fb).unwrap();
+    Pixel(Point::new(4, 5), Rgb565::BLUE).draw(&mut fb).unwrap();
+    // clipped away entirely
+    Pixel(Point::new(-1, 40), Rgb565::BLUE)
+        .draw(&mut fb)
+        .unwrap();
+
+    let rects: Vec<_> = fb.dirty_rects().collect();
+    assert_eq!(rects.len(), 1);
+    assert_eq!(
+        (
+            rects[0].x_left,
+            rects[0].y_top,
+            rects[0].x_right,
+            rects[0].y_bottom
+        ),
+        (3, 4, 4, 5)
+    );
+    assert_eq!(fb.pixel(4, 5), Some(Rgb565::BLUE));
+    assert_eq!(fb.pixel(5, 5), Some(Rgb565::BLACK));
+}
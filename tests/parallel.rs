@@ -0,0 +1,71 @@
+use embedded_graphics::{
+    pixelcolor::Rgb565,
+    prelude::*,
+    primitives::{PrimitiveStyle, Rectangle},
+};
+use st7789::testing::{Command, MockBus, MockPin, PinRole, Simulator};
+use st7789::{DisplayInterface, Parallel16Bit, Parallel8Bit, SpiInterface, ST7789};
+
+/// Initializes the display and draws a little, returning the command stream
+fn draw<DI: DisplayInterface>(display: &mut ST7789<DI, MockPin>, bus: &MockBus) -> Vec<Command> {
+    display.init(&mut bus.delay()).unwrap();
+    Rectangle::new(Point::new(10, 20), Size::new(30, 7))
+        .into_styled(PrimitiveStyle::with_fill(Rgb565::CSS_ORANGE))
+        .draw(display)
+        .unwrap();
+    display
+        .blit_pixels(0, 0, 3, 1, &[1, 2, 3, 4, 5, 6])
+        .unwrap();
+    display.set_pixel(239, 239, 0xABCD).unwrap();
+    display
+        .set_pixels(100, 100, 101, 101, [0x1234, 0x5678])
+        .unwrap();
+    display.continue_pixels([0x9ABC, 0xDEF0]).unwrap();
+    bus.commands()
+}
+
+fn spi_commands() -> Vec<Command> {
+    let bus = MockBus::new();
+    let di = SpiInterface::new(bus.spi(), bus.pin(PinRole::Dc));
+    let mut display = ST7789::new(di, None, None, 240, 240);
+    draw(&mut display, &bus)
+}
+
+#[test]
+fn parallel_8bit_sends_same_commands_as_spi() {
+    let bus = MockBus::new();
+    let di = Parallel8Bit::new(bus.data_pins(), bus.pin(PinRole::Wr), bus.pin(PinRole::Dc));
+    let mut display = ST7789::new(di, None, None, 240, 240);
+
+    assert_eq!(draw(&mut display, &bus), spi_commands());
+}
+
+#[test]
+fn parallel_16bit_sends_same_commands_as_spi() {
+    let bus = MockBus::new();
+    let di = Parallel16Bit::new(bus.data_pins(), bus.pin(PinRole::Wr), bus.pin(PinRole::Dc));
+    let mut display = ST7789::new(di, None, None, 240, 240);
+
+    assert_eq!(draw(&mut display, &bus), spi_commands());
+}
+
+#[test]
+fn parallel_draws_same_image_as_spi() {
+    let render = |bus: &MockBus| {
+        let mut sim = Simulator::new(240, 240, 0, 0);
+        sim.consume(bus);
+        sim.to_rgb()
+    };
+    let spi = MockBus::new();
+    let di = SpiInterface::new(spi.spi(), spi.pin(PinRole::Dc));
+    draw(&mut ST7789::new(di, None, None, 240, 240), &spi);
+    let parallel = MockBus::new();
+    let di = Parallel8Bit::new(
+        parallel.data_pins(),
+        parallel.pin(PinRole::Wr),
+        parallel.pin(PinRole::Dc),
+    );
+    draw(&mut ST7789::new(di, None, None, 240, 240), &parallel);
+
+    assert_eq!(render(&parallel), render(&spi));
+}
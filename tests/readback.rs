@@ -0,0 +1,277 @@
+use embedded_graphics::{
+    pixelcolor::Rgb565,
+    prelude::*,
+    primitives::{PrimitiveStyle, Rectangle},
+};
+use st7789::testing::{Command, Event, MockBus, MockPin, MockSpi, PinRole, Simulator};
+use st7789::{
+    DisplayId, DisplayInterface, Error, ImageFormat, InitSequence, Instruction,
+    MemoryAccessControl, Orientation, PixelFormat, PowerMode, ReadInterface, SpiInterface,
+    TearingEffect, ThreeWireInterface, ST7789,
+};
+
+fn display(bus: &MockBus) -> ST7789<SpiInterface<MockSpi, MockPin>, MockPin> {
+    ST7789::new(
+        SpiInterface::new(bus.spi(), bus.pin(PinRole::Dc)),
+        None,
+        None,
+        240,
+        240,
+    )
+}
+
+#[test]
+fn read_display_id() {
+    let bus = MockBus::new();
+    let mut display = display(&bus);
+    bus.set_register(Instruction::RDDID, &[0x85, 0x85, 0x52]);
+
+    assert_eq!(
+        display.read_display_id().unwrap(),
+        DisplayId {
+            manufacturer: 0x85,
+            version: 0x85,
+            module: 0x52,
+        }
+    );
+    assert_eq!(bus.commands(), [Command::new(Instruction::RDDID, &[])]);
+}
+
+#[test]
+fn register_read_is_one_transaction() {
+    let bus = MockBus::new();
+    let mut display = display(&bus);
+    display.read_power_mode().unwrap();
+
+    let pin = |role, high| Event::Pin { role, high };
+    assert_eq!(
+        bus.events(),
+        [
+            pin(PinRole::Dc, false),
+            pin(PinRole::Cs, false),
+            Event::Byte {
+                byte: Instruction::RDDPM as u8,
+                dc: Some(false),
+                cs: Some(false),
+            },
+            pin(PinRole::Cs, true),
+        ]
+    );
+}
+
+#[test]
+fn read_display_status() {
+    let bus = MockBus::new();
+    let mut display = display(&bus);
+    // booster on, landscape BGR, 16 bit, sleep out, normal mode, inverted, display on, TE on
+    bus.set_register(Instruction::RDDST, &[0xB4, 0x53, 0x26, 0x00]);
+
+    let status = display.read_display_status().unwrap();
+    assert!(status.booster_on);
+    assert_eq!(
+        status.madctl,
+        MemoryAccessControl::from(Orientation::Landscape).bgr(true)
+    );
+    assert_eq!(status.madctl.orientation(), Orientation::Landscape);
+    assert_eq!(status.pixel_format, Some(PixelFormat::Rgb565));
+    assert!(!status.sleeping && status.normal_mode && !status.partial_mode);
+    assert!(status.inverted && status.display_on);
+    assert_eq!(status.tearing_effect, TearingEffect::Vertical);
+}
+
+#[test]
+fn read_power_mode_and_madctl() {
+    let bus = MockBus::new();
+    let mut display = display(&bus);
+    bus.set_register(Instruction::RDDPM, &[0x9C]);
+    bus.set_register(Instruction::RDDMADCTL, &[0xC0]);
+
+    assert_eq!(
+        display.read_power_mode().unwrap(),
+        PowerMode {
+            booster_on: true,
+            idle_mode: false,
+            partial_mode: false,
+            sleeping: false,
+            normal_mode: true,
+            display_on: true,
+        }
+    );
+    assert_eq!(
+        display.read_memory_access_control().unwrap().orientation(),
+        Orientation::PortraitSwapped
+    );
+}
+
+#[test]
+fn three_wire_reads_after_command_frame() {
+    let bus = MockBus::new();
+    let mut display: ST7789<ThreeWireInterface<MockSpi>, MockPin> = ST7789::new(
+        ThreeWireInterface::new(bus.spi_3wire()),
+        None,
+        None,
+        240,
+        240,
+    );
+    bus.set_register(Instruction::RDDID, &[0x85, 0x85, 0x52]);
+    bus.set_register(Instruction::RDDPM, &[0x08]);
+
+    assert_eq!(display.read_display_id().unwrap().module, 0x52);
+    assert!(display.read_power_mode().unwrap().sleeping);
+    assert_eq!(
+        bus.commands(),
+        [
+            Command::new(Instruction::RDDID, &[]),
+            Command::new(Instruction::RDDPM, &[])
+        ]
+    );
+}
+
+/// Draws 4 pixel wide vertical stripes, so every row reads back the same
+fn draw_stripes<DI: DisplayInterface>(display: &mut ST7789<DI, MockPin>) {
+    let colors = [Rgb565::RED, Rgb565::GREEN, Rgb565::BLUE, Rgb565::CSS_ORANGE];
+    for (i, color) in colors.iter().cycle().take(60).enumerate() {
+        Rectangle::new(Point::new(i as i32 * 4, 0), Size::new(4, 240))
+            .into_styled(PrimitiveStyle::with_fill(*color))
+            .draw(display)
+            .unwrap();
+    }
+}
+
+/// Reads back an area, answering RAMRD with what the simulated frame memory holds
+fn read_back<DI: ReadInterface>(
+    display: &mut ST7789<DI, MockPin>,
+    bus: &MockBus,
+    sim: &mut Simulator,
+    area: Rectangle,
+) -> Vec<Rgb565> {
+    let pixels = display.read_pixels(area).unwrap();
+    sim.consume(bus);
+    bus.set_register(Instruction::RAMRD, &sim.read_memory());
+    pixels.map(Result::unwrap).collect()
+}
+
+#[test]
+fn read_pixels_returns_drawn_colors() {
+    for orientation in [Orientation::Portrait, Orientation::LandscapeSwapped] {
+        let bus = MockBus::new();
+        let mut display = display(&bus);
+        let mut sim = Simulator::new(240, 240, 0, 0);
+        display.init(&mut bus.delay()).unwrap();
+        display.set_orientation(orientation).unwrap();
+        display.clear(Rgb565::BLACK).unwrap();
+        Pixel(Point::new(10, 5), Rgb565::CSS_ORANGE)
+            .draw(&mut display)
+            .unwrap();
+        sim.consume(&bus);
+
+        let area = Rectangle::new(Point::new(9, 5), Size::new(3, 2));
+        assert_eq!(
+            read_back(&mut display, &bus, &mut sim, area),
+            [
+                Rgb565::BLACK,
+                Rgb565::CSS_ORANGE,
+                Rgb565::BLACK,
+                Rgb565::BLACK,
+                Rgb565::BLACK,
+                Rgb565::BLACK
+            ],
+            "{:?}",
+            orientation
+        );
+    }
+}
+
+#[test]
+fn three_wire_read_pixels() {
+    let bus = MockBus::new();
+    let mut display: ST7789<ThreeWireInterface<MockSpi>, MockPin> = ST7789::new(
+        ThreeWireInterface::new(bus.spi_3wire()),
+        None,
+        None,
+        240,
+        240,
+    );
+    let mut sim = Simulator::new(240, 240, 0, 0);
+    display
+        .init_with(&InitSequence::MINIMAL, &mut bus.delay())
+        .unwrap();
+    draw_stripes(&mut display);
+    sim.consume(&bus);
+
+    // long enough to span several transfers
+    let area = Rectangle::new(Point::new(2, 7), Size::new(40, 2));
+    let pixels = read_back(&mut display, &bus, &mut sim, area);
+    assert_eq!(pixels.len(), 80);
+    assert_eq!(pixels[0], Rgb565::RED);
+    assert_eq!(pixels[2], Rgb565::GREEN);
+    assert_eq!(pixels[10], Rgb565::CSS_ORANGE);
+    assert_eq!(pixels[39], Rgb565::BLUE);
+    assert_eq!(pixels[..40], pixels[40..]);
+}
+
+#[test]
+fn read_pixels_continues_with_ramrdc() {
+    let bus = MockBus::new();
+    let mut display = display(&bus);
+    let mut sim = Simulator::new(240, 240, 0, 0);
+    display
+        .init_with(&InitSequence::MINIMAL, &mut bus.delay())
+        .unwrap();
+    draw_stripes(&mut display);
+    sim.consume(&bus);
+
+    // 15 pixels fit into one transaction
+    let area = Rectangle::new(Point::new(0, 3), Size::new(40, 1));
+    let pixels = read_back(&mut display, &bus, &mut sim, area);
+    let reads: Vec<_> = bus
+        .commands()
+        .into_iter()
+        .filter_map(|command| command.instruction())
+        .filter(|instruction| matches!(instruction, Instruction::RAMRD | Instruction::RAMRDC))
+        .collect();
+    assert_eq!(
+        reads,
+        [Instruction::RAMRD, Instruction::RAMRDC, Instruction::RAMRDC]
+    );
+    assert_eq!(pixels[..4], [Rgb565::RED; 4]);
+    assert_eq!(pixels[12..16], [Rgb565::CSS_ORANGE; 4]);
+    assert_eq!(pixels[16..20], [Rgb565::RED; 4]);
+    assert_eq!(pixels[32..36], [Rgb565::RED; 4]);
+}
+
+#[test]
+fn read_pixels_rejects_area_outside_display() {
+    let bus = MockBus::new();
+    let mut display = display(&bus);
+    let area = Rectangle::new(Point::new(200, 0), Size::new(41, 1));
+
+    assert!(matches!(
+        display.read_pixels(area),
+        Err(Error::InvalidArgument)
+    ));
+    assert!(bus.commands().is_empty());
+}
+
+#[test]
+fn screenshot_matches_panel() {
+    for format in [ImageFormat::Ppm, ImageFormat::Bmp] {
+        let bus = MockBus::new();
+        let mut display = display(&bus);
+        let mut sim = Simulator::new(240, 240, 0, 0);
+        display
+            .init_with(&InitSequence::MINIMAL, &mut bus.delay())
+            .unwrap();
+        draw_stripes(&mut display);
+        sim.consume(&bus);
+        // every row holds the same stripes
+        let row = Rectangle::new(Point::zero(), Size::new(240, 1));
+        read_back(&mut display, &bus, &mut sim, row);
+
+        let mut image = Vec::new();
+        display
+            .screenshot(format, |bytes| image.extend_from_slice(bytes))
+            .unwrap();
+        assert_eq!(image, sim.to_image(format), "{:?}", format);
+    }
+}
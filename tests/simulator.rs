@@ -0,0 +1,380 @@
+use std::{env, fs, path::PathBuf};
+
+use embedded_graphics::{
+    pixelcolor::{Rgb565, Rgb666, Rgb888},
+    prelude::*,
+    primitives::{Circle, Line, PrimitiveStyle, Rectangle},
+};
+use st7789::testing::{MockBus, MockPin, MockSpi, PinRole, Simulator};
+use st7789::{
+    DisplayModel, InitSequence, MemoryAccessControl, Orientation, PixelFormat, SpiInterface, ST7789,
+};
+
+const SIZE: u16 = 32;
+
+fn display(
+    bus: &MockBus,
+    size_x: u16,
+    size_y: u16,
+) -> ST7789<SpiInterface<MockSpi, MockPin>, MockPin> {
+    ST7789::new(
+        SpiInterface::new(bus.spi(), bus.pin(PinRole::Dc)),
+        None,
+        None,
+        size_x,
+        size_y,
+    )
+}
+
+fn rgb565(color: Rgb565) -> u16 {
+    color.into_storage()
+}
+
+/// Draws an asymmetric test picture so mirroring and rotation show up in the output
+fn draw_scene<D: DrawTarget<Color = Rgb565>>(target: &mut D) -> Result<(), D::Error> {
+    target.clear(Rgb565::BLACK)?;
+    Rectangle::new(Point::new(0, 0), Size::new(12, 4))
+        .into_styled(PrimitiveStyle::with_fill(Rgb565::RED))
+        .draw(target)?;
+    Line::new(Point::new(0, 4), Point::new(0, 20))
+        .into_styled(PrimitiveStyle::with_stroke(Rgb565::GREEN, 1))
+        .draw(target)?;
+    Circle::new(Point::new(16, 16), 12)
+        .into_styled(PrimitiveStyle::with_fill(Rgb565::BLUE))
+        .draw(target)?;
+    Ok(())
+}
+
+/// Compares the rendered panel against `tests/golden/<name>.ppm`.
+/// Run with `UPDATE_GOLDEN=1` to rewrite the golden images.
+fn assert_golden(name: &str, sim: &Simulator) {
+    let path: PathBuf = [env!("CARGO_MANIFEST_DIR"), "tests", "golden"]
+        .iter()
+        .collect::<PathBuf>()
+        .join(format!("{}.ppm", name));
+    let actual = sim.to_ppm();
+
+    if env::var_os("UPDATE_GOLDEN").is_some() {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, &actual).unwrap();
+        return;
+    }
+
+    let expected = fs::read(&path).unwrap_or_else(|_| {
+        panic!(
+            "missing golden image {}, run with UPDATE_GOLDEN=1",
+            path.display()
+        )
+    });
+    if expected != actual {
+        let out = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(format!("{}.ppm", name));
+        fs::write(&out, &actual).unwrap();
+        panic!(
+            "{} differs from golden image, actual output written to {}",
+            name,
+            out.display()
+        );
+    }
+}
+
+/// Returns an initialized display together with a simulator of an IPS panel
+fn setup(bus: &MockBus) -> (ST7789<SpiInterface<MockSpi, MockPin>, MockPin>, Simulator) {
+    let mut display = display(bus, SIZE, SIZE);
+    display.init(&mut bus.delay()).unwrap();
+    (
+        display,
+        Simulator::new(SIZE, SIZE, 0, 0).with_inverted_panel(),
+    )
+}
+
+fn render(orientation: Orientation) -> Simulator {
+    let bus = MockBus::new();
+    let (mut display, mut sim) = setup(&bus);
+    display.set_orientation(orientation).unwrap();
+    draw_scene(&mut display).unwrap();
+    sim.consume(&bus);
+    sim
+}
+
+#[test]
+fn golden_portrait() {
+    let sim = render(Orientation::Portrait);
+    assert_eq!(sim.pixel_rgb565(0, 0), rgb565(Rgb565::RED));
+    assert_eq!(sim.pixel_rgb565(0, 10), rgb565(Rgb565::GREEN));
+    assert_eq!(sim.pixel_rgb565(22, 22), rgb565(Rgb565::BLUE));
+    assert_golden("portrait", &sim);
+}
+
+#[test]
+fn golden_landscape() {
+    let sim = render(Orientation::Landscape);
+    assert_eq!(sim.pixel_rgb565(SIZE - 1, 0), rgb565(Rgb565::RED));
+    assert_golden("landscape", &sim);
+}
+
+#[test]
+fn golden_portrait_swapped() {
+    let sim = render(Orientation::PortraitSwapped);
+    assert_eq!(sim.pixel_rgb565(SIZE - 1, SIZE - 1), rgb565(Rgb565::RED));
+    assert_golden("portrait_swapped", &sim);
+}
+
+#[test]
+fn golden_portrait_mirrored() {
+    let bus = MockBus::new();
+    let (mut display, mut sim) = setup(&bus);
+    display
+        .set_orientation(MemoryAccessControl::new().flip_horizontal())
+        .unwrap();
+    draw_scene(&mut display).unwrap();
+    sim.consume(&bus);
+
+    assert_eq!(sim.pixel_rgb565(SIZE - 1, 0), rgb565(Rgb565::RED));
+    assert_eq!(sim.pixel_rgb565(SIZE - 1, 10), rgb565(Rgb565::GREEN));
+    assert_golden("portrait_mirrored", &sim);
+}
+
+#[test]
+fn bgr_panel_needs_bgr_order() {
+    for bgr in [false, true] {
+        let bus = MockBus::new();
+        let (mut display, _) = setup(&bus);
+        let mut sim = Simulator::new(SIZE, SIZE, 0, 0)
+            .with_inverted_panel()
+            .with_bgr_panel();
+        display
+            .set_orientation(MemoryAccessControl::new().bgr(bgr))
+            .unwrap();
+        display.clear(Rgb565::RED).unwrap();
+        sim.consume(&bus);
+
+        let expected = if bgr { Rgb565::RED } else { Rgb565::BLUE };
+        assert_eq!(sim.pixel_rgb565(3, 3), rgb565(expected), "bgr: {}", bgr);
+    }
+}
+
+#[test]
+fn every_pixel_format_renders_the_same_picture() {
+    let expected = render(Orientation::Portrait).to_rgb();
+    for format in [PixelFormat::Rgb444, PixelFormat::Rgb666] {
+        let bus = MockBus::new();
+        let (mut display, mut sim) = setup(&bus);
+        display.set_orientation(Orientation::Portrait).unwrap();
+        display.set_pixel_format(format).unwrap();
+        draw_scene(&mut display).unwrap();
+        sim.consume(&bus);
+
+        assert!(sim.to_rgb() == expected, "{:?}", format);
+    }
+}
+
+#[test]
+fn rgb666_keeps_deep_colors() {
+    let bus = MockBus::new();
+    let (mut display, mut sim) = setup(&bus);
+    display.set_orientation(Orientation::Portrait).unwrap();
+    display.set_pixel_format(PixelFormat::Rgb666).unwrap();
+    let mut target = display.color_target::<Rgb666>();
+    // odd red and blue values RGB565 cannot show
+    Rectangle::new(Point::zero(), Size::new(4, 4))
+        .into_styled(PrimitiveStyle::with_fill(Rgb666::new(33, 40, 1)))
+        .draw(&mut target)
+        .unwrap();
+    Pixel(Point::new(5, 0), Rgb888::new(0x84, 0xA0, 0x04))
+        .draw(&mut display.color_target())
+        .unwrap();
+    sim.consume(&bus);
+
+    let deep = [(33 << 2) | (33 >> 4), 40 << 2 | 40 >> 4, 1 << 2];
+    assert_eq!(sim.pixel(3, 3), deep);
+    assert_eq!(sim.pixel(5, 0), deep);
+    assert_eq!(sim.pixel(4, 0), [0, 0, 0]);
+}
+
+#[test]
+fn landscape_rotates_full_width_panel() {
+    let bus = MockBus::new();
+    let mut display = display(&bus, 240, 240);
+    let mut sim = Simulator::new(240, 240, 0, 0).with_inverted_panel();
+    display.init(&mut bus.delay()).unwrap();
+    display.set_orientation(Orientation::Landscape).unwrap();
+    draw_scene(&mut display).unwrap();
+    sim.consume(&bus);
+
+    // rotated by 90 degrees, logical origin ends up in the top right corner
+    assert_eq!(sim.pixel_rgb565(239, 0), rgb565(Rgb565::RED));
+    assert_eq!(sim.pixel_rgb565(239, 11), rgb565(Rgb565::RED));
+    assert_eq!(sim.pixel_rgb565(236, 11), rgb565(Rgb565::RED));
+    assert_eq!(sim.pixel_rgb565(235, 11), rgb565(Rgb565::BLACK));
+    assert_eq!(sim.pixel_rgb565(235, 0), rgb565(Rgb565::GREEN));
+    assert_eq!(sim.pixel_rgb565(219, 0), rgb565(Rgb565::GREEN));
+}
+
+#[test]
+fn portrait_swapped_rotates_full_width_panel() {
+    let bus = MockBus::new();
+    let mut display = display(&bus, 240, 240);
+    let mut sim = Simulator::new(240, 240, 0, 0).with_inverted_panel();
+    display.init(&mut bus.delay()).unwrap();
+    display
+        .set_orientation(Orientation::PortraitSwapped)
+        .unwrap();
+    draw_scene(&mut display).unwrap();
+    sim.consume(&bus);
+
+    assert_eq!(sim.pixel_rgb565(239, 239), rgb565(Rgb565::RED));
+    assert_eq!(sim.pixel_rgb565(228, 239), rgb565(Rgb565::RED));
+    assert_eq!(sim.pixel_rgb565(239, 235), rgb565(Rgb565::GREEN));
+}
+
+#[test]
+fn golden_landscape_swapped() {
+    let sim = render(Orientation::LandscapeSwapped);
+    assert_eq!(sim.pixel_rgb565(0, SIZE - 1), rgb565(Rgb565::RED));
+    assert_eq!(sim.pixel_rgb565(0, SIZE - 12), rgb565(Rgb565::RED));
+    assert_golden("landscape_swapped", &sim);
+}
+
+#[test]
+fn continue_pixels_fills_window_across_calls() {
+    let bus = MockBus::new();
+    let (mut display, mut sim) = setup(&bus);
+    let colors = [Rgb565::RED, Rgb565::GREEN, Rgb565::BLUE].map(rgb565);
+    display.set_orientation(Orientation::Portrait).unwrap();
+
+    display.set_pixels(4, 4, 5, 5, colors).unwrap();
+    display.continue_pixels([rgb565(Rgb565::WHITE)]).unwrap();
+    sim.consume(&bus);
+
+    assert_eq!(sim.pixel_rgb565(4, 4), colors[0]);
+    assert_eq!(sim.pixel_rgb565(5, 4), colors[1]);
+    assert_eq!(sim.pixel_rgb565(4, 5), colors[2]);
+    assert_eq!(sim.pixel_rgb565(5, 5), rgb565(Rgb565::WHITE));
+}
+
+#[test]
+fn scroll_offset_moves_picture_up() {
+    let bus = MockBus::new();
+    let (mut display, mut sim) = setup(&bus);
+    display.set_orientation(Orientation::Portrait).unwrap();
+    Line::new(Point::new(0, 10), Point::new(31, 10))
+        .into_styled(PrimitiveStyle::with_stroke(Rgb565::WHITE, 1))
+        .draw(&mut display)
+        .unwrap();
+    sim.consume(&bus);
+    assert_eq!(sim.pixel_rgb565(5, 10), rgb565(Rgb565::WHITE));
+
+    display.set_scroll_offset(10).unwrap();
+    sim.consume(&bus);
+    assert_eq!(sim.pixel_rgb565(5, 0), rgb565(Rgb565::WHITE));
+    assert_eq!(sim.pixel_rgb565(5, 10), rgb565(Rgb565::BLACK));
+}
+
+#[test]
+fn scroll_region_keeps_header_and_footer() {
+    let bus = MockBus::new();
+    let mut display = display(&bus, 240, 320);
+    let mut sim = Simulator::new(240, 320, 0, 0).with_inverted_panel();
+    display.init(&mut bus.delay()).unwrap();
+    display.set_orientation(Orientation::Portrait).unwrap();
+    display.clear(Rgb565::BLACK).unwrap();
+    let mut region = display.define_scroll_area(10, 300, 10).unwrap();
+
+    let line = |display: &mut ST7789<SpiInterface<MockSpi, MockPin>, MockPin>, y: u16, color| {
+        Line::new(Point::new(0, y.into()), Point::new(239, y.into()))
+            .into_styled(PrimitiveStyle::with_stroke(color, 1))
+            .draw(display)
+            .unwrap();
+    };
+    line(&mut display, 0, Rgb565::RED);
+    line(&mut display, 319, Rgb565::GREEN);
+    region.scroll_by(25);
+    display.scroll(&region).unwrap();
+    // draw where the last scrolling line now shows
+    line(&mut display, region.gram_row(309), Rgb565::BLUE);
+    sim.consume(&bus);
+
+    assert_eq!(sim.pixel_rgb565(5, 0), rgb565(Rgb565::RED));
+    assert_eq!(sim.pixel_rgb565(5, 319), rgb565(Rgb565::GREEN));
+    assert_eq!(sim.pixel_rgb565(5, 309), rgb565(Rgb565::BLUE));
+    assert_eq!(sim.pixel_rgb565(5, 308), rgb565(Rgb565::BLACK));
+}
+
+#[test]
+fn inversion_depends_on_panel_type() {
+    let bus = MockBus::new();
+    let mut display = display(&bus, SIZE, SIZE);
+    let mut ips = Simulator::new(SIZE, SIZE, 0, 0).with_inverted_panel();
+    let mut tn = Simulator::new(SIZE, SIZE, 0, 0);
+    display.init(&mut bus.delay()).unwrap();
+    display.clear(Rgb565::CYAN).unwrap();
+    for command in bus.commands() {
+        ips.execute(&command);
+        tn.execute(&command);
+    }
+
+    assert_eq!(ips.pixel(3, 3), [0x00, 0xFF, 0xFF]);
+    assert_eq!(tn.pixel(3, 3), [0xFF, 0x00, 0x00]);
+}
+
+#[test]
+fn minimal_sequence_suits_tn_panels() {
+    let bus = MockBus::new();
+    let mut display = display(&bus, SIZE, SIZE);
+    let mut sim = Simulator::new(SIZE, SIZE, 0, 0);
+    display
+        .init_with(&InitSequence::MINIMAL, &mut bus.delay())
+        .unwrap();
+    display.clear(Rgb565::YELLOW).unwrap();
+    sim.consume(&bus);
+
+    assert_eq!(sim.pixel(0, 0), [0xFF, 0xFF, 0x00]);
+}
+
+#[test]
+fn offset_panel_origin_follows_orientation() {
+    let model = DisplayModel::Ttgo135x240;
+    let panel = model.config();
+    // where the logical origin shows up on the panel
+    let cases = [
+        (Orientation::Portrait, (0, 0)),
+        (Orientation::Landscape, (134, 0)),
+        (Orientation::PortraitSwapped, (134, 239)),
+        (Orientation::LandscapeSwapped, (0, 239)),
+    ];
+
+    for (orientation, (x, y)) in cases {
+        let bus = MockBus::new();
+        let mut display = ST7789::with_panel(
+            SpiInterface::new(bus.spi(), bus.pin(PinRole::Dc)),
+            None::<MockPin>,
+            None,
+            model,
+        );
+        let mut sim = Simulator::new(
+            panel.width,
+            panel.height,
+            panel.col_offset,
+            panel.row_offset,
+        )
+        .with_inverted_panel();
+        display.init(&mut bus.delay()).unwrap();
+        display.set_orientation(orientation).unwrap();
+        display.clear(Rgb565::BLACK).unwrap();
+        Pixel(Point::zero(), Rgb565::WHITE)
+            .draw(&mut display)
+            .unwrap();
+        sim.consume(&bus);
+
+        assert_eq!(
+            sim.pixel_rgb565(x, y),
+            rgb565(Rgb565::WHITE),
+            "{:?}",
+            orientation
+        );
+        assert_eq!(display.size(), {
+            let (w, h) = panel.size(orientation);
+            Size::new(w.into(), h.into())
+        });
+    }
+}
@@ -0,0 +1,261 @@
+use embedded_graphics::{
+    pixelcolor::Rgb565,
+    prelude::*,
+    primitives::{Circle, PrimitiveStyle, Rectangle},
+};
+use st7789::testing::{Event, MockBus, MockPin, MockSpi, PinRole, Simulator};
+use st7789::{DataFormat, DisplayInterface, Error, PixelFormat, SpiError, SpiInterface, ST7789};
+
+fn display(bus: &MockBus) -> ST7789<SpiInterface<MockSpi, MockPin>, MockPin> {
+    ST7789::new(
+        SpiInterface::new(bus.spi(), bus.pin(PinRole::Dc)),
+        None,
+        None,
+        240,
+        240,
+    )
+}
+
+/// Number of SPI transactions, one for every write
+fn writes(bus: &MockBus) -> usize {
+    bus.events()
+        .into_iter()
+        .filter(|event| {
+            *event
+                == Event::Pin {
+                    role: PinRole::Cs,
+                    high: false,
+                }
+        })
+        .count()
+}
+
+#[test]
+fn set_pixels_buffered_writes_whole_buffers() {
+    let colors = || (0..1000u16).map(|i| i * 61);
+    let unbuffered = MockBus::new();
+    display(&unbuffered)
+        .set_pixels(0, 0, 99, 9, colors())
+        .unwrap();
+
+    let bus = MockBus::new();
+    let mut buffer = [0; 501];
+    display(&bus)
+        .set_pixels_buffered(0, 0, 99, 9, colors(), &mut buffer)
+        .unwrap();
+    assert_eq!(bus.commands(), unbuffered.commands());
+    // CASET, RASET and RAMWR with their parameters, then 2000 bytes in writes of 500
+    assert_eq!(writes(&bus), 5 + 4);
+}
+
+#[test]
+fn fill_solid_buffered_clears_screen_in_few_writes() {
+    let blue = Rgb565::BLUE.into_storage();
+    let unbuffered = MockBus::new();
+    display(&unbuffered)
+        .set_pixels(0, 0, 239, 319, core::iter::repeat(blue).take(240 * 320))
+        .unwrap();
+
+    let bus = MockBus::new();
+    let mut buffer = [0; 4096];
+    display(&bus)
+        .scratch_target(&mut buffer)
+        .clear(Rgb565::BLUE)
+        .unwrap();
+    assert_eq!(bus.commands(), unbuffered.commands());
+    // CASET, RASET and RAMWR with their parameters, then 153600 bytes in writes of 4096
+    assert_eq!(writes(&bus), 5 + 38);
+
+    // without a scratch buffer a row at a time
+    let plain = MockBus::new();
+    display(&plain).clear(Rgb565::BLUE).unwrap();
+    assert_eq!(plain.commands(), unbuffered.commands());
+    assert_eq!(writes(&plain), 5 + 320);
+    assert!(writes(&plain) * 3 < writes(&unbuffered));
+}
+
+#[test]
+fn fill_solid_buffered_encodes_active_pixel_format() {
+    for format in [
+        PixelFormat::Rgb444,
+        PixelFormat::Rgb565,
+        PixelFormat::Rgb666,
+    ] {
+        let bus = MockBus::new();
+        let mut display = display(&bus);
+        display.set_pixel_format(format).unwrap();
+        bus.clear();
+        // an odd number of pixels and a buffer not holding whole pixels
+        display.set_pixels(3, 4, 5, 6, [0xF81F; 9]).unwrap();
+        let expected = bus.commands();
+        display.set_pixel(0, 0, 0).unwrap();
+        bus.clear();
+
+        let mut buffer = [0; 7];
+        display
+            .fill_solid_buffered(3, 4, 5, 6, 0xF81F, &mut buffer)
+            .unwrap();
+        assert_eq!(bus.commands(), expected, "{:?}", format);
+    }
+}
+
+#[test]
+fn scratch_target_draws_same_image() {
+    let render = |buffered: bool| {
+        let bus = MockBus::new();
+        let mut display = display(&bus);
+        let mut sim = Simulator::new(240, 240, 0, 0);
+        let mut buffer = [0; 64];
+        let circle = Circle::new(Point::new(20, 30), 50)
+            .into_styled(PrimitiveStyle::with_fill(Rgb565::CSS_ORANGE));
+        let area = Rectangle::new(Point::new(100, 100), Size::new(13, 7));
+        let colors = (0..91u16).map(|i| Rgb565::new(i as u8 % 32, i as u8 % 64, 0));
+
+        display.init(&mut bus.delay()).unwrap();
+        if buffered {
+            let mut target = display.scratch_target(&mut buffer);
+            target.clear(Rgb565::BLUE).unwrap();
+            circle.draw(&mut target).unwrap();
+            target.fill_contiguous(&area, colors).unwrap();
+        } else {
+            display.clear(Rgb565::BLUE).unwrap();
+            circle.draw(&mut display).unwrap();
+            display.fill_contiguous(&area, colors).unwrap();
+        }
+        sim.consume(&bus);
+        sim.to_rgb()
+    };
+
+    assert!(render(true) == render(false));
+}
+
+#[test]
+fn scratch_writes_check_arguments() {
+    let bus = MockBus::new();
+    let mut display = display(&bus);
+    display.set_pixel_format(PixelFormat::Rgb666).unwrap();
+    bus.clear();
+
+    assert!(matches!(
+        display.fill_solid_buffered(0, 0, 9, 9, 0, &mut [0; 5]),
+        Err(Error::InvalidArgument)
+    ));
+    assert!(matches!(
+        display.set_pixels_buffered(0, 0, 9, 9, [0; 100], &mut [0; 5]),
+        Err(Error::InvalidArgument)
+    ));
+    // end before start
+    assert!(matches!(
+        display.fill_solid_buffered(9, 0, 0, 9, 0, &mut [0; 6]),
+        Err(Error::InvalidArgument)
+    ));
+    assert!(bus.commands().is_empty());
+}
+
+#[test]
+fn blit_pixels_nb_hands_buffer_back() {
+    let bus = MockBus::new();
+    let mut nb_display = display(&bus);
+    let data: &'static mut [u8] = Box::leak(Box::new([0x5A; 2 * 40 * 20]));
+
+    let transfer = nb_display.blit_pixels_nb(10, 10, 40, 20, data).unwrap();
+    let (data, result) = transfer.wait();
+    assert!(result.is_ok());
+    assert_eq!(data.len(), 1600);
+
+    let blocking = MockBus::new();
+    display(&blocking)
+        .blit_pixels(10, 10, 40, 20, data)
+        .unwrap();
+    assert_eq!(bus.commands(), blocking.commands());
+}
+
+#[test]
+fn blit_pixels_nb_returns_buffer_on_wrong_length() {
+    let bus = MockBus::new();
+    let mut display = display(&bus);
+    let data: &'static mut [u8] = Box::leak(Box::new([0; 7]));
+
+    let Err((error, data)) = display.blit_pixels_nb(0, 0, 2, 2, data) else {
+        panic!("blit of 7 bytes into 4 pixels started");
+    };
+    assert!(matches!(error, Error::InvalidArgument));
+    assert_eq!(data.len(), 7);
+    assert!(bus.commands().is_empty());
+}
+
+#[test]
+fn blit_pixels_nb_checks_area() {
+    let bus = MockBus::new();
+    let mut display = display(&bus);
+
+    let data: &'static mut [u8] = Box::leak(Box::new([]));
+    let (data, result) = display.blit_pixels_nb(5, 5, 0, 10, data).unwrap().wait();
+    assert!(result.is_ok());
+    assert!(data.is_empty());
+
+    let data: &'static mut [u8] = Box::leak(Box::new([0; 4]));
+    let Err((error, _)) = display.blit_pixels_nb(u16::MAX, 0, 2, 1, data) else {
+        panic!("blit past the end of the address space started");
+    };
+    assert!(matches!(error, Error::InvalidArgument));
+    assert!(bus.commands().is_empty());
+}
+
+/// Interface pretending to write in the background, done after a few polls
+struct BackgroundInterface {
+    di: SpiInterface<MockSpi, MockPin>,
+    pending: Option<(&'static mut [u8], u8)>,
+}
+
+impl DisplayInterface for BackgroundInterface {
+    fn write_command(&mut self, command: u8) -> Result<(), SpiError> {
+        self.di.write_command(command)
+    }
+
+    fn write_data(&mut self, data: DataFormat<'_>) -> Result<(), SpiError> {
+        self.di.write_data(data)
+    }
+
+    fn start_write(
+        &mut self,
+        data: &'static mut [u8],
+    ) -> Result<Option<&'static mut [u8]>, (SpiError, &'static mut [u8])> {
+        self.pending = Some((data, 3));
+        Ok(None)
+    }
+
+    fn poll_write(&mut self) -> nb::Result<&'static mut [u8], (SpiError, &'static mut [u8])> {
+        match self.pending.take() {
+            Some((data, 0)) => match self.di.write_data(DataFormat::U8(data)) {
+                Ok(()) => Ok(data),
+                Err(error) => Err(nb::Error::Other((error, data))),
+            },
+            Some((data, polls)) => {
+                self.pending = Some((data, polls - 1));
+                Err(nb::Error::WouldBlock)
+            }
+            None => panic!("polled without a running write"),
+        }
+    }
+}
+
+#[test]
+fn blit_pixels_nb_polls_background_transfer() {
+    let bus = MockBus::new();
+    let di = BackgroundInterface {
+        di: SpiInterface::new(bus.spi(), bus.pin(PinRole::Dc)),
+        pending: None,
+    };
+    let mut display = ST7789::new(di, None::<MockPin>, None, 240, 240);
+    let data: &'static mut [u8] = Box::leak(Box::new([0xA5; 8]));
+
+    let mut transfer = display.blit_pixels_nb(0, 0, 2, 2, data).unwrap();
+    assert!(matches!(transfer.poll(), Err(nb::Error::WouldBlock)));
+    // window and RAMWR went out, the pixels didn't yet
+    assert_eq!(bus.commands().last().unwrap().params, []);
+
+    let (data, result) = transfer.wait();
+    assert!(result.is_ok());
+    assert_eq!(bus.commands().last().unwrap().params, data);
+}